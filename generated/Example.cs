@@ -0,0 +1,74 @@
+// this file is generated by tools, do NOT edit this file!
+using System;
+using System.Collections.Generic;
+
+[Serializable]
+public class Example
+{
+    /// <summary>主键1</summary>
+    public int KEY1_id;
+    /// <summary>主键2</summary>
+    public int KEY2_id;
+    /// <summary>字段1</summary>
+    public int field1;
+    /// <summary>字段2</summary>
+    public int field2;
+    /// <summary>字段3</summary>
+    public string field3 = "";
+
+    public static Example Read(TableReader reader)
+    {
+        var value = new Example();
+        reader.Expect('{');
+        while (reader.HasNext('}'))
+        {
+            switch (reader.ReadName())
+            {
+                case "KEY1_id":
+                    value.KEY1_id = reader.ReadInt();
+                    break;
+                case "KEY2_id":
+                    value.KEY2_id = reader.ReadInt();
+                    break;
+                case "field1":
+                    value.field1 = reader.ReadInt();
+                    break;
+                case "field2":
+                    value.field2 = reader.ReadInt();
+                    break;
+                case "field3":
+                    value.field3 = reader.ReadString();
+                    break;
+                default:
+                    reader.Skip();
+                    break;
+            }
+        }
+        return value;
+    }
+}
+
+public class ExampleTable
+{
+    public readonly List<Example> Rows = new List<Example>();
+    public readonly Dictionary<(int, int), Example> ByKey = new Dictionary<(int, int), Example>();
+
+    public Example Get(int KEY1_id, int KEY2_id)
+    {
+        return ByKey.TryGetValue((KEY1_id, KEY2_id), out var found) ? found : null;
+    }
+
+    public static ExampleTable Load(string json)
+    {
+        var table = new ExampleTable();
+        var reader = new TableReader(json);
+        reader.Expect('[');
+        while (reader.HasNext(']'))
+        {
+            var row = Example.Read(reader);
+            table.Rows.Add(row);
+            table.ByKey[(row.KEY1_id, row.KEY2_id)] = row;
+        }
+        return table;
+    }
+}
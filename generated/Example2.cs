@@ -0,0 +1,59 @@
+// this file is generated by tools, do NOT edit this file!
+using System;
+using System.Collections.Generic;
+
+[Serializable]
+public class Example2
+{
+    /// <summary>主键1</summary>
+    public int KEY1_id;
+    /// <summary>字段1</summary>
+    public int field1;
+
+    public static Example2 Read(TableReader reader)
+    {
+        var value = new Example2();
+        reader.Expect('{');
+        while (reader.HasNext('}'))
+        {
+            switch (reader.ReadName())
+            {
+                case "KEY1_id":
+                    value.KEY1_id = reader.ReadInt();
+                    break;
+                case "field1":
+                    value.field1 = reader.ReadInt();
+                    break;
+                default:
+                    reader.Skip();
+                    break;
+            }
+        }
+        return value;
+    }
+}
+
+public class Example2Table
+{
+    public readonly List<Example2> Rows = new List<Example2>();
+    public readonly Dictionary<int, Example2> ByKey = new Dictionary<int, Example2>();
+
+    public Example2 Get(int KEY1_id)
+    {
+        return ByKey.TryGetValue(KEY1_id, out var found) ? found : null;
+    }
+
+    public static Example2Table Load(string json)
+    {
+        var table = new Example2Table();
+        var reader = new TableReader(json);
+        reader.Expect('[');
+        while (reader.HasNext(']'))
+        {
+            var row = Example2.Read(reader);
+            table.Rows.Add(row);
+            table.ByKey[row.KEY1_id] = row;
+        }
+        return table;
+    }
+}
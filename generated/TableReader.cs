@@ -0,0 +1,254 @@
+// this file is generated by tools, do NOT edit this file!
+using System;
+using System.Collections.Generic;
+using System.Globalization;
+using System.Text;
+
+/// <summary>reads the json tables exported by xlsxto</summary>
+public sealed class TableReader
+{
+    private readonly string text;
+    private int pos;
+
+    public TableReader(string text)
+    {
+        this.text = text;
+    }
+
+    public void Expect(char c)
+    {
+        SkipSpace();
+        if (pos >= text.Length || text[pos] != c)
+        {
+            throw Error("expected '" + c + "'");
+        }
+        pos++;
+    }
+
+    /// <summary>true when another element follows, false after reading `close`</summary>
+    public bool HasNext(char close)
+    {
+        SkipSpace();
+        if (pos < text.Length && text[pos] == close)
+        {
+            pos++;
+            return false;
+        }
+        if (pos < text.Length && text[pos] == ',')
+        {
+            pos++;
+        }
+        return true;
+    }
+
+    /// <summary>moves to the next element of a tuple</summary>
+    public TableReader Item()
+    {
+        SkipSpace();
+        if (pos < text.Length && text[pos] == ',')
+        {
+            pos++;
+        }
+        return this;
+    }
+
+    public string ReadName()
+    {
+        var name = ReadString();
+        Expect(':');
+        return name;
+    }
+
+    public int ReadInt()
+    {
+        return int.Parse(ReadNumber(), NumberStyles.Float, CultureInfo.InvariantCulture);
+    }
+
+    public long ReadLong()
+    {
+        return long.Parse(ReadNumber(), NumberStyles.Float, CultureInfo.InvariantCulture);
+    }
+
+    public float ReadFloat()
+    {
+        return float.Parse(ReadNumber(), NumberStyles.Float, CultureInfo.InvariantCulture);
+    }
+
+    public bool ReadBool()
+    {
+        if (Match("true"))
+        {
+            return true;
+        }
+        if (Match("false"))
+        {
+            return false;
+        }
+        throw Error("expected a bool");
+    }
+
+    public string ReadString()
+    {
+        Expect('"');
+        var sb = new StringBuilder();
+        while (pos < text.Length)
+        {
+            char c = text[pos++];
+            if (c == '"')
+            {
+                return sb.ToString();
+            }
+            if (c != '\\')
+            {
+                sb.Append(c);
+                continue;
+            }
+            if (pos >= text.Length)
+            {
+                break;
+            }
+            c = text[pos++];
+            switch (c)
+            {
+                case 'b': sb.Append('\b'); break;
+                case 'f': sb.Append('\f'); break;
+                case 'n': sb.Append('\n'); break;
+                case 'r': sb.Append('\r'); break;
+                case 't': sb.Append('\t'); break;
+                case 'u':
+                    if (pos + 4 > text.Length)
+                    {
+                        throw Error("bad escape");
+                    }
+                    sb.Append((char)Convert.ToInt32(text.Substring(pos, 4), 16));
+                    pos += 4;
+                    break;
+                default: sb.Append(c); break;
+            }
+        }
+        throw Error("unterminated string");
+    }
+
+    /// <summary>dates and datetimes, null for empty cells</summary>
+    public DateTime? ReadDate()
+    {
+        if (Match("null"))
+        {
+            return null;
+        }
+        return DateTime.Parse(ReadString(), CultureInfo.InvariantCulture, DateTimeStyles.None);
+    }
+
+    public TimeSpan ReadDuration()
+    {
+        return TimeSpan.FromSeconds(ReadLong());
+    }
+
+    /// <summary>the json text of the next value, null for null</summary>
+    public string ReadRaw()
+    {
+        if (Match("null"))
+        {
+            return null;
+        }
+        int start = pos;
+        Skip();
+        return text.Substring(start, pos - start);
+    }
+
+    public List<T> ReadList<T>(Func<T> item)
+    {
+        var list = new List<T>();
+        Expect('[');
+        while (HasNext(']'))
+        {
+            list.Add(item());
+        }
+        return list;
+    }
+
+    /// <summary>`items` reads the elements with `Item()`, extra elements are skipped</summary>
+    public T ReadTuple<T>(Func<T> items)
+    {
+        Expect('[');
+        var value = items();
+        while (HasNext(']'))
+        {
+            Skip();
+        }
+        return value;
+    }
+
+    public void Skip()
+    {
+        SkipSpace();
+        if (pos >= text.Length)
+        {
+            throw Error("unexpected end");
+        }
+        char c = text[pos];
+        if (c == '"')
+        {
+            ReadString();
+        }
+        else if (c == '[' || c == '{')
+        {
+            char close = c == '[' ? ']' : '}';
+            pos++;
+            while (HasNext(close))
+            {
+                if (close == '}')
+                {
+                    ReadName();
+                }
+                Skip();
+            }
+        }
+        else
+        {
+            while (pos < text.Length && ",]}".IndexOf(text[pos]) < 0 && !char.IsWhiteSpace(text[pos]))
+            {
+                pos++;
+            }
+        }
+    }
+
+    private string ReadNumber()
+    {
+        SkipSpace();
+        int start = pos;
+        while (pos < text.Length && "+-.0123456789eE".IndexOf(text[pos]) >= 0)
+        {
+            pos++;
+        }
+        if (start == pos)
+        {
+            throw Error("expected a number");
+        }
+        return text.Substring(start, pos - start);
+    }
+
+    private bool Match(string word)
+    {
+        SkipSpace();
+        if (string.CompareOrdinal(text, pos, word, 0, word.Length) != 0)
+        {
+            return false;
+        }
+        pos += word.Length;
+        return true;
+    }
+
+    private void SkipSpace()
+    {
+        while (pos < text.Length && char.IsWhiteSpace(text[pos]))
+        {
+            pos++;
+        }
+    }
+
+    private FormatException Error(string message)
+    {
+        return new FormatException(message + " at " + pos);
+    }
+}
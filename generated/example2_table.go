@@ -0,0 +1,49 @@
+// this file is generated by tools, do NOT edit this file!
+
+package config
+
+// Example2 is a row of example2.json
+type Example2 struct {
+	// 主键1
+	KEY1Id int32 `json:"KEY1_id"`
+	// 字段1
+	Field1 int32 `json:"field1"`
+}
+
+// Example2Table holds the rows of example2.json
+type Example2Table struct {
+	Rows  []*Example2
+	ByKey map[int32]*Example2
+}
+
+// TExample2 holds example2.json after Load
+var TExample2 = &Example2Table{}
+
+func init() {
+	register("example2.json", func(dir string) error {
+		t, err := LoadExample2(dir)
+		if err == nil {
+			TExample2 = t
+		}
+		return err
+	})
+}
+
+// Get returns the row with the key, nil if there is none
+func (t *Example2Table) Get(key int32) *Example2 {
+	return t.ByKey[key]
+}
+
+// LoadExample2 reads example2.json from dir
+func LoadExample2(dir string) (*Example2Table, error) {
+	t := &Example2Table{
+		ByKey: map[int32]*Example2{},
+	}
+	if err := loadJSON(dir, "example2.json", &t.Rows); err != nil {
+		return nil, err
+	}
+	for _, row := range t.Rows {
+		t.ByKey[row.KEY1Id] = row
+	}
+	return t, nil
+}
@@ -0,0 +1,61 @@
+// this file is generated by tools, do NOT edit this file!
+
+package config
+
+// Example is a row of example.json
+type Example struct {
+	// 主键1
+	KEY1Id int32  `json:"KEY1_id"`
+	// 主键2
+	KEY2Id int32  `json:"KEY2_id"`
+	// 字段1
+	Field1 int32  `json:"field1"`
+	// 字段2
+	Field2 int32  `json:"field2"`
+	// 字段3
+	Field3 string `json:"field3"`
+}
+
+// ExampleKey is the primary key of Example
+type ExampleKey struct {
+	KEY1Id int32
+	KEY2Id int32
+}
+
+// ExampleTable holds the rows of example.json
+type ExampleTable struct {
+	Rows  []*Example
+	ByKey map[ExampleKey]*Example
+}
+
+// TExample holds example.json after Load
+var TExample = &ExampleTable{}
+
+func init() {
+	register("example.json", func(dir string) error {
+		t, err := LoadExample(dir)
+		if err == nil {
+			TExample = t
+		}
+		return err
+	})
+}
+
+// Get returns the row with the key, nil if there is none
+func (t *ExampleTable) Get(key ExampleKey) *Example {
+	return t.ByKey[key]
+}
+
+// LoadExample reads example.json from dir
+func LoadExample(dir string) (*ExampleTable, error) {
+	t := &ExampleTable{
+		ByKey: map[ExampleKey]*Example{},
+	}
+	if err := loadJSON(dir, "example.json", &t.Rows); err != nil {
+		return nil, err
+	}
+	for _, row := range t.Rows {
+		t.ByKey[ExampleKey{row.KEY1Id, row.KEY2Id}] = row
+	}
+	return t, nil
+}
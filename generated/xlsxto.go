@@ -0,0 +1,100 @@
+// this file is generated by tools, do NOT edit this file!
+
+package config
+
+import (
+	"encoding/json"
+	"fmt"
+	"os"
+	"path/filepath"
+	"time"
+)
+
+// Time is a date or datetime column, empty cells are the zero time
+type Time struct {
+	time.Time
+}
+
+// UnmarshalJSON reads the times as UTC, the way they were exported
+func (t *Time) UnmarshalJSON(data []byte) error {
+	if string(data) == "null" {
+		t.Time = time.Time{}
+		return nil
+	}
+	var s string
+	if err := json.Unmarshal(data, &s); err != nil {
+		return err
+	}
+	layout := "2006-01-02T15:04:05"
+	if len(s) == len("2006-01-02") {
+		layout = "2006-01-02"
+	}
+	v, err := time.Parse(layout, s)
+	if err != nil {
+		return err
+	}
+	t.Time = v
+	return nil
+}
+
+// Duration is a duration column, exported as seconds
+type Duration struct {
+	time.Duration
+}
+
+// UnmarshalJSON reads the seconds
+func (d *Duration) UnmarshalJSON(data []byte) error {
+	var secs int64
+	if err := json.Unmarshal(data, &secs); err != nil {
+		return err
+	}
+	d.Duration = time.Duration(secs) * time.Second
+	return nil
+}
+
+// unmarshalTuple reads a json array into the elements of a tuple
+func unmarshalTuple(data []byte, items ...interface{}) error {
+	var values []json.RawMessage
+	if err := json.Unmarshal(data, &values); err != nil {
+		return err
+	}
+	for i, item := range items {
+		if i >= len(values) {
+			break
+		}
+		if err := json.Unmarshal(values[i], item); err != nil {
+			return err
+		}
+	}
+	return nil
+}
+
+type loader struct {
+	file string
+	load func(dir string) error
+}
+
+var loaders []loader
+
+func register(file string, load func(dir string) error) {
+	loaders = append(loaders, loader{file, load})
+}
+
+func loadJSON(dir, file string, rows interface{}) error {
+	data, err := os.ReadFile(filepath.Join(dir, filepath.FromSlash(file)))
+	if err != nil {
+		return err
+	}
+	return json.Unmarshal(data, rows)
+}
+
+// Load reads every table from the json files in dir, a table that fails to
+// load keeps its old rows
+func Load(dir string) error {
+	for _, l := range loaders {
+		if err := l.load(dir); err != nil {
+			return fmt.Errorf("%s: %w", l.file, err)
+		}
+	}
+	return nil
+}
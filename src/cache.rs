@@ -0,0 +1,436 @@
+/*
+Copyright (c) 2024- rickhan<rick.han@yahoo.com>
+
+Permission is hereby granted, free of charge, to any person obtaining
+a copy of this software and associated documentation files (the
+"Software"), to deal in the Software without restriction, including
+without limitation the rights to use, copy, modify, merge, publish,
+distribute, sublicense, and/or sell copies of the Software, and to
+permit persons to whom the Software is furnished to do so, subject to
+the following conditions:
+
+The above copyright notice and this permission notice shall be
+included in all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+*/
+
+//! the build cache lets a run skip workbooks that did not change since the
+//! last successful run
+
+use crate::context::ExportContext;
+use crate::error::Error;
+use crate::generator::exported_names;
+use crate::generator::write_file;
+use crate::xlsx::{table_file, ALLXLSX};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+
+/// name of the cache manifest written to the output directory
+pub const CACHE_FILE_NAME: &str = ".xlsxto-cache.toml";
+
+/// 64-bit FNV-1a of `bytes` as hex, stable across runs and platforms
+pub fn content_hash(bytes: &[u8]) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for b in bytes {
+        hash ^= *b as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("{:016x}", hash)
+}
+
+/// content hashes of `files` under `input`
+pub fn hash_files(input: &str, files: &[String]) -> Result<BTreeMap<String, String>, Error> {
+    let mut hashes = BTreeMap::new();
+    for f in files {
+        let path = format!("{}/{}", input, f);
+        let bytes = fs::read(&path).map_err(|e| Error::io(&path, e))?;
+        hashes.insert(f.clone(), content_hash(&bytes));
+    }
+    Ok(hashes)
+}
+
+/// the workbook holding the table `reference`, by path or by a file name
+/// no other folder uses
+fn resolve_file(reference: &str, files: &[String]) -> Option<String> {
+    let book = table_file(reference);
+    if files.iter().any(|f| f == book) {
+        return Some(book.to_string());
+    }
+    let mut found = files.iter().filter(|f| f.rsplit('/').next() == Some(book));
+    match (found.next(), found.next()) {
+        (Some(f), None) => Some(f.clone()),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CacheEntry {
+    pub hash: String,
+    /// workbooks looked up by `expect` checks or defining the enums used
+    pub references: Vec<String>,
+    /// enums defined by the workbook
+    pub enums: Vec<String>,
+    /// file and type names the tables of the workbook export
+    pub tables: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BuildCache {
+    pub version: String,
+    /// the export options the outputs were generated with
+    pub options: String,
+    pub files: BTreeMap<String, CacheEntry>,
+}
+
+impl BuildCache {
+    pub fn new(options: &str) -> BuildCache {
+        BuildCache {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            options: options.to_string(),
+            files: BTreeMap::new(),
+        }
+    }
+
+    /// the cache in `output`, an empty one when it is missing, unreadable or
+    /// was written by another version or with other options
+    pub fn load(output: &str, options: &str) -> BuildCache {
+        let empty = BuildCache::new(options);
+        let path = Path::new(output).join(CACHE_FILE_NAME);
+        let cache = match fs::read_to_string(path) {
+            Ok(content) => match BuildCache::parse(&content) {
+                Ok(cache) => cache,
+                Err(e) => {
+                    log::warn!("ignore broken cache {}: {}", CACHE_FILE_NAME, e);
+                    return empty;
+                }
+            },
+            Err(_) => return empty,
+        };
+        if cache.version != empty.version || cache.options != empty.options {
+            return empty;
+        }
+        cache
+    }
+
+    pub fn parse(content: &str) -> Result<BuildCache, String> {
+        let table = content.parse::<toml::Table>().map_err(|e| e.to_string())?;
+        let text = |t: &toml::Table, key: &str| -> Result<String, String> {
+            match t.get(key).and_then(|v| v.as_str()) {
+                Some(s) => Ok(s.to_string()),
+                None => Err(format!("{} must be a string", key)),
+            }
+        };
+        let list = |t: &toml::Table, key: &str| -> Vec<String> {
+            t.get(key)
+                .and_then(|v| v.as_array())
+                .map(|a| {
+                    a.iter()
+                        .filter_map(|v| v.as_str().map(|s| s.to_string()))
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+
+        let mut cache = BuildCache {
+            version: text(&table, "version")?,
+            options: text(&table, "options")?,
+            files: BTreeMap::new(),
+        };
+        if let Some(files) = table.get("files").and_then(|v| v.as_table()) {
+            for (name, entry) in files {
+                let entry = entry
+                    .as_table()
+                    .ok_or(format!("files.{} must be a table", name))?;
+                cache.files.insert(
+                    name.clone(),
+                    CacheEntry {
+                        hash: text(entry, "hash")?,
+                        references: list(entry, "references"),
+                        enums: list(entry, "enums"),
+                        tables: list(entry, "tables"),
+                    },
+                );
+            }
+        }
+        Ok(cache)
+    }
+
+    pub fn to_toml(&self) -> String {
+        let list = |items: &Vec<String>| {
+            toml::Value::Array(
+                items
+                    .iter()
+                    .map(|s| toml::Value::String(s.clone()))
+                    .collect(),
+            )
+        };
+        let mut files = toml::Table::new();
+        for (name, entry) in &self.files {
+            let mut t = toml::Table::new();
+            t.insert("hash".to_string(), toml::Value::String(entry.hash.clone()));
+            t.insert("references".to_string(), list(&entry.references));
+            t.insert("enums".to_string(), list(&entry.enums));
+            t.insert("tables".to_string(), list(&entry.tables));
+            files.insert(name.clone(), toml::Value::Table(t));
+        }
+        let mut root = toml::Table::new();
+        root.insert(
+            "version".to_string(),
+            toml::Value::String(self.version.clone()),
+        );
+        root.insert(
+            "options".to_string(),
+            toml::Value::String(self.options.clone()),
+        );
+        root.insert("files".to_string(), toml::Value::Table(files));
+        root.to_string()
+    }
+
+    pub fn save(&self, output: &str) -> Result<(), Error> {
+        write_file(&format!("{}/{}", output, CACHE_FILE_NAME), &self.to_toml())
+    }
+
+    /// workbooks that must be parsed, checked and generated: new and changed
+    /// ones, and the ones referencing a changed or removed workbook
+    pub fn stale_files(&self, hashes: &BTreeMap<String, String>) -> Vec<String> {
+        let mut touched: BTreeSet<&String> = hashes
+            .iter()
+            .filter(|(f, h)| self.files.get(*f).map(|e| &e.hash) != Some(*h))
+            .map(|(f, _)| f)
+            .collect();
+        touched.extend(self.files.keys().filter(|f| !hashes.contains_key(*f)));
+
+        hashes
+            .keys()
+            .filter(|f| {
+                touched.contains(f)
+                    || self
+                        .files
+                        .get(*f)
+                        .is_some_and(|e| e.references.iter().any(|r| touched.contains(r)))
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// references of the workbooks whose tables are in `all`
+    fn parsed_references(
+        &self,
+        all: &ALLXLSX,
+        files: &[String],
+        ctx: &ExportContext,
+    ) -> BTreeMap<String, CacheEntry> {
+        let mut enum_files: BTreeMap<String, String> = BTreeMap::new();
+        for (name, entry) in &self.files {
+            for e in &entry.enums {
+                enum_files.insert(e.clone(), name.clone());
+            }
+        }
+        for (name, xlsx) in all.tables() {
+            for def in xlsx.enum_list() {
+                enum_files.insert(def.name.clone(), table_file(name).to_string());
+            }
+        }
+
+        let mut entries: BTreeMap<String, CacheEntry> = BTreeMap::new();
+        for (name, xlsx) in all.tables() {
+            let file = table_file(name).to_string();
+            let entry = entries.entry(file.clone()).or_default();
+            for r in xlsx.references() {
+                entry.references.extend(resolve_file(&r, files));
+            }
+            for e in xlsx.enums_used() {
+                entry.references.extend(enum_files.get(&e).cloned());
+            }
+            entry
+                .enums
+                .extend(xlsx.enum_list().iter().map(|e| e.name.clone()));
+            entry.tables.extend(
+                exported_names(name, xlsx, ctx)
+                    .into_iter()
+                    .map(|(key, _)| key),
+            );
+        }
+        for (file, entry) in entries.iter_mut() {
+            entry.references.retain(|r| r != file);
+            entry.references.sort();
+            entry.references.dedup();
+        }
+        entries
+    }
+
+    /// workbooks the tables in `all` look values up in that are not loaded yet,
+    /// and the ones whose tables export files or types of the same names
+    pub fn lookup_files(
+        &self,
+        all: &ALLXLSX,
+        files: &[String],
+        ctx: &ExportContext,
+    ) -> Vec<String> {
+        let entries = self.parsed_references(all, files, ctx);
+        let mut needed: BTreeSet<String> = BTreeSet::new();
+        for entry in entries.values() {
+            needed.extend(
+                entry
+                    .references
+                    .iter()
+                    .filter(|r| !entries.contains_key(*r))
+                    .cloned(),
+            );
+        }
+        let outputs: BTreeSet<&String> = entries.values().flat_map(|e| &e.tables).collect();
+        needed.extend(
+            files
+                .iter()
+                .filter(|f| !entries.contains_key(*f))
+                .filter(|f| {
+                    self.files
+                        .get(*f)
+                        .is_some_and(|e| e.tables.iter().any(|t| outputs.contains(t)))
+                })
+                .cloned(),
+        );
+        needed.into_iter().collect()
+    }
+
+    /// records the workbooks in `parsed` after a successful run and forgets
+    /// the ones that are gone
+    pub fn update(
+        &mut self,
+        all: &ALLXLSX,
+        hashes: &BTreeMap<String, String>,
+        parsed: &[String],
+        ctx: &ExportContext,
+    ) {
+        let files: Vec<String> = hashes.keys().cloned().collect();
+        let mut entries = self.parsed_references(all, &files, ctx);
+        for f in parsed {
+            let mut entry = entries.remove(f).unwrap_or_default();
+            entry.hash = hashes.get(f).cloned().unwrap_or_default();
+            self.files.insert(f.clone(), entry);
+        }
+        self.files.retain(|f, _| hashes.contains_key(f));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{content_hash, BuildCache, CacheEntry};
+    use crate::context::ExportContext;
+    use crate::fixture::{self, sheet1, text};
+    use std::collections::BTreeMap;
+
+    fn entry(hash: &str, references: &[&str]) -> CacheEntry {
+        CacheEntry {
+            hash: hash.to_string(),
+            references: references.iter().map(|r| r.to_string()).collect(),
+            enums: vec![],
+            tables: vec![],
+        }
+    }
+
+    #[test]
+    fn test_content_hash() {
+        assert_eq!(content_hash(b""), "cbf29ce484222325");
+        assert_eq!(content_hash(b"a"), "af63dc4c8601ec8c");
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut cache = BuildCache::new("target=lua");
+        cache
+            .files
+            .insert("battle/skill.xlsx".to_string(), entry("01", &["item.xlsx"]));
+        cache.files.insert(
+            "enum.xlsx".to_string(),
+            CacheEntry {
+                enums: vec!["Quality".to_string()],
+                tables: vec!["/enum".to_string()],
+                ..entry("02", &[])
+            },
+        );
+        assert_eq!(BuildCache::parse(&cache.to_toml()).unwrap(), cache);
+        assert!(BuildCache::parse("version = 1").is_err());
+    }
+
+    #[test]
+    fn test_stale_files() {
+        let mut cache = BuildCache::new("");
+        cache.files.insert("a.xlsx".to_string(), entry("1", &[]));
+        cache
+            .files
+            .insert("b.xlsx".to_string(), entry("2", &["a.xlsx"]));
+        cache
+            .files
+            .insert("c.xlsx".to_string(), entry("3", &["b.xlsx"]));
+        cache
+            .files
+            .insert("d.xlsx".to_string(), entry("4", &["gone.xlsx"]));
+        cache.files.insert("gone.xlsx".to_string(), entry("5", &[]));
+
+        let hashes = |items: &[(&str, &str)]| -> BTreeMap<String, String> {
+            items
+                .iter()
+                .map(|(f, h)| (f.to_string(), h.to_string()))
+                .collect()
+        };
+        let unchanged = hashes(&[
+            ("a.xlsx", "1"),
+            ("b.xlsx", "2"),
+            ("c.xlsx", "3"),
+            ("d.xlsx", "4"),
+        ]);
+        assert_eq!(cache.stale_files(&unchanged), vec!["d.xlsx"]);
+
+        let changed = hashes(&[
+            ("a.xlsx", "9"),
+            ("b.xlsx", "2"),
+            ("c.xlsx", "3"),
+            ("d.xlsx", "4"),
+            ("gone.xlsx", "5"),
+            ("new.xlsx", "6"),
+        ]);
+        assert_eq!(
+            cache.stale_files(&changed),
+            vec!["a.xlsx", "b.xlsx", "new.xlsx"]
+        );
+    }
+
+    #[test]
+    fn test_lookup_files() {
+        let buff = vec![text(&["id"]), text(&["int!key"]), text(&["1"])];
+        let all = fixture::load(
+            "xlsxto_lookup_files",
+            &[("buff.xlsx", sheet1(buff))],
+            &fixture::layout(),
+        );
+
+        // the unchanged skill.xlsx has a `t_buff` sheet writing the same files
+        let mut cache = BuildCache::new("");
+        cache.files.insert(
+            "skill.xlsx".to_string(),
+            CacheEntry {
+                tables: vec!["/skill".to_string(), "/buff".to_string()],
+                ..entry("1", &[])
+            },
+        );
+        cache.files.insert(
+            "item.xlsx".to_string(),
+            CacheEntry {
+                tables: vec!["/item".to_string()],
+                ..entry("2", &[])
+            },
+        );
+        let files = ["buff.xlsx", "item.xlsx", "skill.xlsx"].map(String::from);
+        let ctx = ExportContext::new("");
+        assert_eq!(cache.lookup_files(&all, &files, &ctx), vec!["skill.xlsx"]);
+    }
+}
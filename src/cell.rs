@@ -0,0 +1,165 @@
+/*
+Copyright (c) 2024- rickhan<rick.han@yahoo.com>
+
+Permission is hereby granted, free of charge, to any person obtaining
+a copy of this software and associated documentation files (the
+"Software"), to deal in the Software without restriction, including
+without limitation the rights to use, copy, modify, merge, publish,
+distribute, sublicense, and/or sell copies of the Software, and to
+permit persons to whom the Software is furnished to do so, subject to
+the following conditions:
+
+The above copyright notice and this permission notice shall be
+included in all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+*/
+
+use calamine::DataType;
+use std::fmt;
+
+/// a cell as read from the workbook, before it is parsed by the field type
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum CellValue {
+    #[default]
+    Empty,
+    Int(i64),
+    Float(f64),
+    String(String),
+    Bool(bool),
+    /// excel serial number of a date or time cell
+    DateTime(f64),
+    /// formula errors such as `#DIV/0!`
+    Error(String),
+}
+
+/// a number the way excel shows it: no `.0` on integers and at most 15
+/// significant digits, so `0.1 + 0.2` is written as `0.3`
+pub fn format_number(f: f64) -> String {
+    if !f.is_finite() {
+        return f.to_string();
+    }
+    if f == f.trunc() && f.abs() < 1e15 {
+        return (f as i64).to_string();
+    }
+    let rounded = format!("{:.14e}", f).parse::<f64>().unwrap_or(f);
+    rounded.to_string()
+}
+
+impl CellValue {
+    /// empty cells and cells holding only spaces
+    pub fn is_empty(&self) -> bool {
+        match self {
+            CellValue::Empty => true,
+            CellValue::String(s) => s.trim().is_empty(),
+            _ => false,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            CellValue::Int(i) => Some(*i as f64),
+            CellValue::Float(f) | CellValue::DateTime(f) => Some(*f),
+            CellValue::String(s) => s.trim().parse::<f64>().ok(),
+            _ => None,
+        }
+    }
+
+    /// integers, and floats without a fractional part
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            CellValue::Int(i) => Some(*i),
+            CellValue::String(s) if s.trim().parse::<i64>().is_ok() => s.trim().parse().ok(),
+            _ => self
+                .as_f64()
+                .filter(|f| f.is_finite() && *f == f.trunc())
+                .map(|f| f as i64),
+        }
+    }
+}
+
+impl fmt::Display for CellValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CellValue::Empty => Ok(()),
+            CellValue::Int(i) => write!(f, "{}", i),
+            CellValue::Float(n) | CellValue::DateTime(n) => write!(f, "{}", format_number(*n)),
+            CellValue::String(s) => write!(f, "{}", s),
+            CellValue::Bool(b) => write!(f, "{}", b),
+            CellValue::Error(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<&DataType> for CellValue {
+    fn from(cell: &DataType) -> Self {
+        match cell {
+            DataType::Empty => CellValue::Empty,
+            DataType::Int(i) => CellValue::Int(*i),
+            DataType::Float(f) => CellValue::Float(*f),
+            DataType::String(s) => CellValue::String(s.clone()),
+            DataType::Bool(b) => CellValue::Bool(*b),
+            DataType::DateTime(f) => CellValue::DateTime(*f),
+            DataType::Error(e) => CellValue::Error(e.to_string()),
+        }
+    }
+}
+
+impl From<&str> for CellValue {
+    fn from(s: &str) -> Self {
+        CellValue::String(s.to_string())
+    }
+}
+
+impl From<String> for CellValue {
+    fn from(s: String) -> Self {
+        CellValue::String(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_number, CellValue};
+    use calamine::{CellErrorType, DataType};
+
+    #[test]
+    fn test_format_number() {
+        assert_eq!(format_number(1.0), "1");
+        assert_eq!(format_number(-0.0), "0");
+        assert_eq!(format_number(0.1), "0.1");
+        assert_eq!(format_number(0.1 + 0.2), "0.3");
+        assert_eq!(format_number(2.5), "2.5");
+        assert_eq!(format_number(-1.25), "-1.25");
+        assert_eq!(format_number(1.0 / 3.0), "0.333333333333333");
+        assert_eq!(format_number(123456789012.0), "123456789012");
+        assert_eq!(format_number(1e20), "100000000000000000000");
+        assert_eq!(format_number(0.000001), "0.000001");
+    }
+
+    #[test]
+    fn test_cell_value() {
+        let cell = |d: DataType| CellValue::from(&d).to_string();
+        assert_eq!(cell(DataType::Float(1.0)), "1");
+        assert_eq!(cell(DataType::Float(0.1)), "0.1");
+        assert_eq!(cell(DataType::Int(42)), "42");
+        assert_eq!(cell(DataType::Bool(true)), "true");
+        assert_eq!(cell(DataType::String("007".to_string())), "007");
+        assert_eq!(cell(DataType::Error(CellErrorType::Div0)), "#DIV/0!");
+        assert_eq!(cell(DataType::Empty), "");
+
+        assert!(CellValue::Empty.is_empty());
+        assert!(CellValue::from(" ").is_empty());
+        assert!(!CellValue::Error("#N/A".to_string()).is_empty());
+        assert_eq!(CellValue::Float(3.0).as_i64(), Some(3));
+        assert_eq!(CellValue::Float(3.5).as_i64(), None);
+        assert_eq!(CellValue::from(" 12 ").as_i64(), Some(12));
+        assert_eq!(CellValue::from("1.5").as_f64(), Some(1.5));
+        assert_eq!(CellValue::Bool(true).as_f64(), None);
+    }
+}
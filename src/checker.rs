@@ -22,13 +22,14 @@ WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
 */
 
 use super::xlsx::ALLXLSX;
+use crate::cell::CellValue;
+use crate::error::{Error, Location};
 use lex_lua::{Keyword, Lexer, Punct, Token};
 use std::collections::LinkedList;
 use std::fmt::Debug;
-use std::io::{Error, ErrorKind};
 
-pub trait Checker: Debug {
-    fn test(&self, value: &String) -> bool;
+pub trait Checker: Debug + Send + Sync {
+    fn test(&self, value: &CellValue) -> bool;
     fn add(&mut self, checker: Box<dyn Checker>) -> bool;
     fn add_param(&mut self, param: String) -> bool;
     fn expect_more(&self) -> bool;
@@ -36,9 +37,19 @@ pub trait Checker: Debug {
         false
     }
 
-    fn test_on_all_load(&self, _: &String, _: &ALLXLSX) -> bool {
+    fn test_on_all_load(&self, _: &CellValue, _: &ALLXLSX) -> bool {
         true
     }
+
+    /// tables this checker looks values up in
+    fn references(&self) -> Vec<String> {
+        vec![]
+    }
+
+    /// whether the values of the column must not repeat
+    fn is_unique(&self) -> bool {
+        false
+    }
 }
 
 #[derive(Debug)]
@@ -56,8 +67,9 @@ mod lt;
 mod not;
 mod or;
 mod range;
+mod unique;
 
-fn func_name_to_checker_obj(name: &str, field_type: &String) -> Option<Box<dyn Checker>> {
+fn func_name_to_checker_obj(name: &str, field_type: &str) -> Option<Box<dyn Checker>> {
     match name {
         "empty" => Some(Box::new(empty::Empty::new())),
         "range" => Some(Box::new(range::Range::new(field_type))),
@@ -66,15 +78,16 @@ fn func_name_to_checker_obj(name: &str, field_type: &String) -> Option<Box<dyn C
         "lt" => Some(Box::new(lt::Lt::new(field_type))),
         "le" => Some(Box::new(lt::Le::new(field_type))),
         "eq" => Some(Box::new(eq::Eq::new())),
-        "len" => Some(Box::new(length::Len::new(field_type))),
+        "len" => length::Len::new(field_type).map(|c| Box::new(c) as Box<dyn Checker>),
         "expect" => Some(Box::new(expect_field::ExpectField::new())),
+        "unique" => Some(Box::new(unique::Unique::new())),
         _ => None,
     }
 }
 
-fn process_token(checker: &mut CheckObj, lex: &mut Lexer, field_type: &String) -> bool {
+fn process_token(checker: &mut CheckObj, lex: &mut Lexer, field_type: &str) -> bool {
     let mut expect_from: LinkedList<usize> = LinkedList::new();
-    for (_, token) in lex.enumerate() {
+    for token in lex {
         let v: bool = match token {
             Token::Keyword(word) => match word {
                 Keyword::Not => {
@@ -85,7 +98,7 @@ fn process_token(checker: &mut CheckObj, lex: &mut Lexer, field_type: &String) -
                 }
                 Keyword::And => {
                     let mut result = true;
-                    if checker.len() == 0 {
+                    if checker.is_empty() {
                         result = false;
                     }
 
@@ -104,7 +117,7 @@ fn process_token(checker: &mut CheckObj, lex: &mut Lexer, field_type: &String) -
                 }
                 Keyword::Or => {
                     let mut result = true;
-                    if checker.len() == 0 {
+                    if checker.is_empty() {
                         result = false;
                     }
 
@@ -138,7 +151,7 @@ fn process_token(checker: &mut CheckObj, lex: &mut Lexer, field_type: &String) -
                     } else {
                         let index = expect_from.pop_back();
                         if let Some(i) = index {
-                            if checker.is_ready(i) == false {
+                            if !checker.is_ready(i) {
                                 result = false;
                             } else {
                                 if !checker.check_finished(&mut expect_from) {
@@ -153,7 +166,7 @@ fn process_token(checker: &mut CheckObj, lex: &mut Lexer, field_type: &String) -
             },
             Token::Name(name) => {
                 let mut result = true;
-                let inst = func_name_to_checker_obj(name.as_ref(), &field_type);
+                let inst = func_name_to_checker_obj(name.as_ref(), field_type);
                 if let Some(c) = inst {
                     checker.add(c);
                     expect_from.push_back(checker.len() - 1);
@@ -197,14 +210,10 @@ fn process_token(checker: &mut CheckObj, lex: &mut Lexer, field_type: &String) -
         }
     }
 
-    return checker.is_all_ready();
+    checker.is_all_ready()
 }
 
-pub fn generate_checker(
-    expr: String,
-    field_type: String,
-    fname: &String,
-) -> Result<CheckObj, Box<dyn std::error::Error>> {
+pub fn generate_checker(expr: String, field_type: String, fname: &str) -> Result<CheckObj, Error> {
     let mut checker = CheckObj::new();
 
     let expr_trimd = expr.trim();
@@ -214,16 +223,16 @@ pub fn generate_checker(
 
     let mut lex = Lexer::new(expr.as_bytes());
     if !process_token(&mut checker, &mut lex, &field_type) {
-        return Err(Box::new(Error::new(
-            ErrorKind::InvalidData,
-            format!(
-                "**导表校验失败**\n**xlsx = {}**\n  unsupport check condition!",
-                fname
-            ),
-        )));
+        return Err(Error::InvalidCondition {
+            location: Box::new(Location {
+                file: fname.to_string(),
+                ..Default::default()
+            }),
+            condition: expr,
+        });
     }
 
-    return Ok(checker);
+    Ok(checker)
 }
 
 impl CheckObj {
@@ -233,7 +242,7 @@ impl CheckObj {
         }
     }
 
-    pub fn test(&self, value: &String) -> bool {
+    pub fn test(&self, value: &CellValue) -> bool {
         for c in &self.checkers {
             if !c.test(value) {
                 return false;
@@ -242,7 +251,7 @@ impl CheckObj {
         true
     }
 
-    pub fn test_on_all_load(&self, value: &String, all: &ALLXLSX) -> bool {
+    pub fn test_on_all_load(&self, value: &CellValue, all: &ALLXLSX) -> bool {
         for c in &self.checkers {
             if !c.test_on_all_load(value, all) {
                 return false;
@@ -251,6 +260,14 @@ impl CheckObj {
         true
     }
 
+    pub fn references(&self) -> Vec<String> {
+        self.checkers.iter().flat_map(|c| c.references()).collect()
+    }
+
+    pub fn is_unique(&self) -> bool {
+        self.checkers.iter().any(|c| c.is_unique())
+    }
+
     pub fn need_full_load(&self) -> bool {
         for c in &self.checkers {
             if c.need_full_load() {
@@ -262,11 +279,11 @@ impl CheckObj {
     }
 
     pub fn get_mut(&mut self, id: usize) -> Option<&mut Box<dyn Checker>> {
-        return self.checkers.get_mut(id);
+        self.checkers.get_mut(id)
     }
 
-    pub fn get(&self, id: usize) -> Option<&Box<dyn Checker>> {
-        return self.checkers.get(id);
+    pub fn get(&self, id: usize) -> Option<&dyn Checker> {
+        self.checkers.get(id).map(|c| c.as_ref())
     }
 
     pub fn add(&mut self, c: Box<dyn Checker>) {
@@ -274,15 +291,15 @@ impl CheckObj {
     }
 
     pub fn pop(&mut self) -> Option<Box<dyn Checker>> {
-        return self.checkers.pop();
+        self.checkers.pop()
     }
 
     pub fn len(&self) -> usize {
-        return self.checkers.len();
+        self.checkers.len()
     }
 
     pub fn is_empty(&self) -> bool {
-        return self.checkers.is_empty();
+        self.checkers.is_empty()
     }
 
     fn is_ready(&self, index: usize) -> bool {
@@ -337,163 +354,207 @@ impl CheckObj {
             }
         }
 
-        return true;
+        true
+    }
+}
+
+impl Default for CheckObj {
+    fn default() -> Self {
+        CheckObj::new()
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::cell::CellValue;
     use crate::checker::generate_checker;
 
     #[test]
     fn test_gt() {
-        let ret = generate_checker(
-            "gt(12)".to_string(),
-            "int".to_string(),
-            &"test.xlsx".to_string(),
-        );
+        let ret = generate_checker("gt(12)".to_string(), "int".to_string(), "test.xlsx");
         if let Ok(checker) = ret {
-            assert_eq!(checker.test(&"13".to_string()), true);
-            assert_eq!(checker.test(&"13345463".to_string()), true);
-            assert_eq!(checker.test(&"1".to_string()), false);
-            assert_eq!(checker.test(&"-1232141".to_string()), false);
-            assert_eq!(checker.test(&"-0".to_string()), false);
-            assert_eq!(checker.test(&"0".to_string()), false);
-            assert_eq!(checker.test(&"12".to_string()), false);
+            assert!(checker.test(&CellValue::from("13")));
+            assert!(checker.test(&CellValue::from("13345463")));
+            assert!(!checker.test(&CellValue::from("1")));
+            assert!(!checker.test(&CellValue::from("-1232141")));
+            assert!(!checker.test(&CellValue::from("-0")));
+            assert!(!checker.test(&CellValue::from("0")));
+            assert!(!checker.test(&CellValue::from("12")));
         } else {
-            assert!(false); // impossible
+            panic!("condition should parse");
         }
     }
 
     #[test]
     fn test_lt() {
-        let ret = generate_checker(
-            "lt(12)".to_string(),
-            "int".to_string(),
-            &"test.xlsx".to_string(),
-        );
+        let ret = generate_checker("lt(12)".to_string(), "int".to_string(), "test.xlsx");
         if let Ok(checker) = ret {
-            assert_eq!(checker.test(&"13".to_string()), false);
-            assert_eq!(checker.test(&"13345463".to_string()), false);
-            assert_eq!(checker.test(&"1".to_string()), true);
-            assert_eq!(checker.test(&"-1232141".to_string()), true);
-            assert_eq!(checker.test(&"-0".to_string()), true);
-            assert_eq!(checker.test(&"0".to_string()), true);
-            assert_eq!(checker.test(&"12".to_string()), false);
+            assert!(!checker.test(&CellValue::from("13")));
+            assert!(!checker.test(&CellValue::from("13345463")));
+            assert!(checker.test(&CellValue::from("1")));
+            assert!(checker.test(&CellValue::from("-1232141")));
+            assert!(checker.test(&CellValue::from("-0")));
+            assert!(checker.test(&CellValue::from("0")));
+            assert!(!checker.test(&CellValue::from("12")));
         } else {
-            assert!(false); // impossible
+            panic!("condition should parse");
         }
     }
 
     #[test]
     fn test_range() {
-        let ret = generate_checker(
-            "range(10, 100)".to_string(),
-            "int".to_string(),
-            &"test.xlsx".to_string(),
-        );
+        let ret = generate_checker("range(10, 100)".to_string(), "int".to_string(), "test.xlsx");
         if let Ok(checker) = ret {
-            assert_eq!(checker.test(&"13".to_string()), true);
-            assert_eq!(checker.test(&"13345463".to_string()), false);
-            assert_eq!(checker.test(&"1".to_string()), false);
-            assert_eq!(checker.test(&"-1232141".to_string()), false);
-            assert_eq!(checker.test(&"-0".to_string()), false);
-            assert_eq!(checker.test(&"0".to_string()), false);
-            assert_eq!(checker.test(&"10".to_string()), true);
-            assert_eq!(checker.test(&"100".to_string()), true);
+            assert!(checker.test(&CellValue::from("13")));
+            assert!(!checker.test(&CellValue::from("13345463")));
+            assert!(!checker.test(&CellValue::from("1")));
+            assert!(!checker.test(&CellValue::from("-1232141")));
+            assert!(!checker.test(&CellValue::from("-0")));
+            assert!(!checker.test(&CellValue::from("0")));
+            assert!(checker.test(&CellValue::from("10")));
+            assert!(checker.test(&CellValue::from("100")));
         } else {
-            assert!(false); // impossible
+            panic!("condition should parse");
         }
 
         let ret = generate_checker(
             "range(10, 100)".to_string(),
             "float".to_string(),
-            &"test.xlsx".to_string(),
+            "test.xlsx",
         );
         if let Ok(checker) = ret {
-            assert_eq!(checker.test(&"13".to_string()), true);
-            assert_eq!(checker.test(&"13345463.0".to_string()), false);
-            assert_eq!(checker.test(&"1".to_string()), false);
-            assert_eq!(checker.test(&"-1232141.0".to_string()), false);
-            assert_eq!(checker.test(&"-0".to_string()), false);
-            assert_eq!(checker.test(&"0".to_string()), false);
-            assert_eq!(checker.test(&"10.0".to_string()), true);
-            assert_eq!(checker.test(&"100.0".to_string()), true);
-            assert_eq!(checker.test(&"9.9999".to_string()), false);
-            assert_eq!(checker.test(&"10.0001".to_string()), true);
-            assert_eq!(checker.test(&"99.9999".to_string()), true);
-            assert_eq!(checker.test(&"100.0001".to_string()), false);
+            assert!(checker.test(&CellValue::from("13")));
+            assert!(!checker.test(&CellValue::from("13345463.0")));
+            assert!(!checker.test(&CellValue::from("1")));
+            assert!(!checker.test(&CellValue::from("-1232141.0")));
+            assert!(!checker.test(&CellValue::from("-0")));
+            assert!(!checker.test(&CellValue::from("0")));
+            assert!(checker.test(&CellValue::from("10.0")));
+            assert!(checker.test(&CellValue::from("100.0")));
+            assert!(!checker.test(&CellValue::from("9.9999")));
+            assert!(checker.test(&CellValue::from("10.0001")));
+            assert!(checker.test(&CellValue::from("99.9999")));
+            assert!(!checker.test(&CellValue::from("100.0001")));
         } else {
-            assert!(false); // impossible
+            panic!("condition should parse");
         }
     }
 
     #[test]
     fn test_length() {
-        let ret = generate_checker(
-            "len(2, 4)".to_string(),
-            "int[]".to_string(),
-            &"test.xlsx".to_string(),
-        );
+        let ret = generate_checker("len(2, 4)".to_string(), "int[]".to_string(), "test.xlsx");
         if let Ok(checker) = ret {
-            assert_eq!(checker.test(&"[13,13, 13, 13]".to_string()), true);
-            assert_eq!(checker.test(&"13345463".to_string()), false);
-            assert_eq!(checker.test(&"[12]".to_string()), false);
-            assert_eq!(checker.test(&"[12,13]".to_string()), true);
-            assert_eq!(checker.test(&"[12,13,14]".to_string()), true);
-            assert_eq!(checker.test(&"[12,13,14,15,15,15,15]".to_string()), false);
+            assert!(checker.test(&CellValue::from("[13,13, 13, 13]")));
+            assert!(!checker.test(&CellValue::from("13345463")));
+            assert!(!checker.test(&CellValue::from("[12]")));
+            assert!(checker.test(&CellValue::from("[12,13]")));
+            assert!(checker.test(&CellValue::from("[12,13,14]")));
+            assert!(!checker.test(&CellValue::from("[12,13,14,15,15,15,15]")));
         } else {
-            assert!(false); // impossible
+            panic!("condition should parse");
         }
 
-        let ret = generate_checker(
-            "len(2, 4)".to_string(),
-            "string".to_string(),
-            &"test.xlsx".to_string(),
-        );
+        let ret = generate_checker("len(2, 4)".to_string(), "string".to_string(), "test.xlsx");
+        if let Ok(checker) = ret {
+            assert!(!checker.test(&CellValue::from("helloworld")));
+            assert!(checker.test(&CellValue::from("hell")));
+            assert!(!checker.test(&CellValue::from("h")));
+            assert!(!checker.test(&CellValue::from("")));
+            assert!(!checker.test(&CellValue::from("world")));
+        } else {
+            panic!("condition should parse");
+        }
+
+        let ret = generate_checker("len(2, 4)".to_string(), "string[]".to_string(), "test.xlsx");
         if let Ok(checker) = ret {
-            assert_eq!(checker.test(&"helloworld".to_string()), false);
-            assert_eq!(checker.test(&"hell".to_string()), true);
-            assert_eq!(checker.test(&"h".to_string()), false);
-            assert_eq!(checker.test(&"".to_string()), false);
-            assert_eq!(checker.test(&"world".to_string()), false);
+            assert!(checker.test(&CellValue::from("[\"a,b\", c]")));
+            assert!(!checker.test(&CellValue::from("[helloworld]")));
         } else {
-            assert!(false); // impossible
+            panic!("condition should parse");
         }
+
+        // numbers have no length
+        assert!(generate_checker("len(2, 4)".to_string(), "int".to_string(), "test.xlsx").is_err());
+        assert!(
+            generate_checker("len(2, 4)".to_string(), "int[".to_string(), "test.xlsx").is_err()
+        );
     }
 
     #[test]
     fn test_eq() {
-        let ret = generate_checker(
-            "eq(2)".to_string(),
-            "int".to_string(),
-            &"test.xlsx".to_string(),
-        );
+        let ret = generate_checker("eq(2)".to_string(), "int".to_string(), "test.xlsx");
         if let Ok(checker) = ret {
-            assert_eq!(checker.test(&"2".to_string()), true);
-            assert_eq!(checker.test(&"13345463".to_string()), false);
-            assert_eq!(checker.test(&"[12]".to_string()), false);
-            assert_eq!(checker.test(&"".to_string()), false);
-            assert_eq!(checker.test(&"[12,13,14,15,15,15,15]".to_string()), false);
+            assert!(checker.test(&CellValue::from("2")));
+            assert!(!checker.test(&CellValue::from("13345463")));
+            assert!(!checker.test(&CellValue::from("[12]")));
+            assert!(!checker.test(&CellValue::from("")));
+            assert!(!checker.test(&CellValue::from("[12,13,14,15,15,15,15]")));
         } else {
-            assert!(false); // impossible
+            panic!("condition should parse");
         }
     }
 
     #[test]
     fn test_or() {
-        let ret = generate_checker(
-            "eq(2) or eq(3)".to_string(),
-            "int".to_string(),
-            &"test.xlsx".to_string(),
-        );
+        let ret = generate_checker("eq(2) or eq(3)".to_string(), "int".to_string(), "test.xlsx");
         if let Ok(checker) = ret {
-            assert_eq!(checker.test(&"2".to_string()), true);
-            assert_eq!(checker.test(&"3".to_string()), true);
-            assert_eq!(checker.test(&"2.5".to_string()), false);
-            assert_eq!(checker.test(&"100".to_string()), false);
+            assert!(checker.test(&CellValue::from("2")));
+            assert!(checker.test(&CellValue::from("3")));
+            assert!(!checker.test(&CellValue::from("2.5")));
+            assert!(!checker.test(&CellValue::from("100")));
         } else {
-            assert!(false);
+            panic!("condition should parse");
         }
     }
+
+    #[test]
+    fn test_typed_values() {
+        let fname = "test.xlsx".to_string();
+        let checker = generate_checker("gt(12)".to_string(), "int".to_string(), &fname).unwrap();
+        assert!(checker.test(&CellValue::Int(13)));
+        assert!(checker.test(&CellValue::Float(13.0)));
+        assert!(!checker.test(&CellValue::Float(12.5)));
+        assert!(!checker.test(&CellValue::Empty));
+        assert!(!checker.test(&CellValue::Error("#N/A".to_string())));
+
+        let checker =
+            generate_checker("range(0.1, 0.3)".to_string(), "float".to_string(), &fname).unwrap();
+        assert!(checker.test(&CellValue::Float(0.1)));
+        assert!(checker.test(&CellValue::Float(0.2)));
+        assert!(!checker.test(&CellValue::Float(0.30001)));
+
+        let checker = generate_checker("eq(2)".to_string(), "int".to_string(), &fname).unwrap();
+        assert!(checker.test(&CellValue::Float(2.0)));
+        assert!(checker.test(&CellValue::Int(2)));
+
+        let checker = generate_checker("empty()".to_string(), "int".to_string(), &fname).unwrap();
+        assert!(checker.test(&CellValue::Empty));
+        assert!(!checker.test(&CellValue::Error("#N/A".to_string())));
+    }
+
+    #[test]
+    fn test_unique() {
+        let fname = "test.xlsx".to_string();
+        let unique = |expr: &str| {
+            generate_checker(expr.to_string(), "string".to_string(), &fname)
+                .unwrap()
+                .is_unique()
+        };
+        assert!(unique("unique()"));
+        assert!(unique("len(1,10) and unique()"));
+        assert!(!unique("len(1,10)"));
+        assert!(unique("eq(1) or eq(2) and unique()"));
+
+        // unique applies to the whole column, not to a branch
+        let invalid =
+            |expr: &str| generate_checker(expr.to_string(), "string".to_string(), &fname).is_err();
+        assert!(invalid("unique() or eq(1)"));
+        assert!(invalid("eq(1) or unique()"));
+        assert!(invalid("unique() and eq(1) or eq(2)"));
+        assert!(invalid("not unique()"));
+
+        let checker =
+            generate_checker("unique()".to_string(), "string".to_string(), &fname).unwrap();
+        assert!(checker.test(&CellValue::from("a")));
+    }
 }
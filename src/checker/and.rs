@@ -22,6 +22,7 @@ WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
 */
 
 use super::Checker;
+use crate::cell::CellValue;
 
 #[derive(Debug)]
 pub struct And {
@@ -35,7 +36,7 @@ impl And {
 }
 
 impl Checker for And {
-    fn test(&self, value: &String) -> bool {
+    fn test(&self, value: &CellValue) -> bool {
         for c in &self.childs {
             if !c.test(value) {
                 return false;
@@ -64,4 +65,12 @@ impl Checker for And {
     fn need_full_load(&self) -> bool {
         false
     }
+
+    fn references(&self) -> Vec<String> {
+        self.childs.iter().flat_map(|c| c.references()).collect()
+    }
+
+    fn is_unique(&self) -> bool {
+        self.childs.iter().any(|c| c.is_unique())
+    }
 }
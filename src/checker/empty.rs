@@ -22,6 +22,7 @@ WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
 */
 
 use super::Checker;
+use crate::cell::CellValue;
 
 #[derive(Debug)]
 pub struct Empty {}
@@ -33,8 +34,8 @@ impl Empty {
 }
 
 impl Checker for Empty {
-    fn test(&self, value: &String) -> bool {
-        if value.trim().is_empty() {
+    fn test(&self, value: &CellValue) -> bool {
+        if value.is_empty() {
             return true;
         }
 
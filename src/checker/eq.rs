@@ -21,6 +21,7 @@ OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
 WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
 */
 use super::Checker;
+use crate::cell::CellValue;
 
 #[derive(Debug)]
 pub struct Eq {
@@ -36,8 +37,8 @@ impl Eq {
 }
 
 impl Checker for Eq {
-    fn test(&self, value: &String) -> bool {
-        if self.expect.trim().eq(value.trim()) {
+    fn test(&self, value: &CellValue) -> bool {
+        if self.expect.trim().eq(value.to_string().trim()) {
             return true;
         }
 
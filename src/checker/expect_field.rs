@@ -21,6 +21,7 @@ OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
 WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
 */
 use super::Checker;
+use crate::cell::CellValue;
 use crate::ALLXLSX;
 
 #[derive(Debug)]
@@ -39,8 +40,8 @@ impl ExpectField {
 }
 
 impl Checker for ExpectField {
-    fn test(&self, _value: &String) -> bool {
-        return true;
+    fn test(&self, _value: &CellValue) -> bool {
+        true
     }
 
     fn add(&mut self, _: Box<dyn Checker>) -> bool {
@@ -73,11 +74,15 @@ impl Checker for ExpectField {
         true
     }
 
-    fn test_on_all_load(&self, value: &String, all: &ALLXLSX) -> bool {
+    fn references(&self) -> Vec<String> {
+        vec![self.tab.clone()]
+    }
+
+    fn test_on_all_load(&self, value: &CellValue, all: &ALLXLSX) -> bool {
         if self.tab.is_empty() || self.field.is_empty() {
             return true;
         }
 
-        return all.has_field(&self.tab, &self.field, value);
+        all.has_field(&self.tab, &self.field, value)
     }
 }
@@ -21,10 +21,11 @@ OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
 WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
 */
 use super::Checker;
+use crate::cell::CellValue;
 use std::fmt::Debug;
 
-pub trait TypeGreater: Debug {
-    fn check_condition(&self, field_type: &String, base: &String, value: &String) -> bool;
+pub trait TypeGreater: Debug + Send + Sync {
+    fn check_condition(&self, field_type: &str, base: &str, value: &CellValue) -> bool;
 }
 
 #[derive(Debug)]
@@ -38,9 +39,9 @@ impl<T> Generic<T>
 where
     T: TypeGreater + Default,
 {
-    pub fn new(f: &String) -> Self {
+    pub fn new(f: &str) -> Self {
         Generic {
-            field_type: f.clone(),
+            field_type: f.to_string(),
             base: String::from(""),
             greater: T::default(),
         }
@@ -51,14 +52,13 @@ impl<T> Checker for Generic<T>
 where
     T: TypeGreater + Default,
 {
-    fn test(&self, value: &String) -> bool {
-        if value.trim().is_empty() {
+    fn test(&self, value: &CellValue) -> bool {
+        if value.is_empty() {
             return false;
         }
 
-        return self
-            .greater
-            .check_condition(&self.field_type, &self.base, value);
+        self.greater
+            .check_condition(&self.field_type, &self.base, value)
     }
 
     fn add(&mut self, _: Box<dyn Checker>) -> bool {
@@ -95,34 +95,42 @@ pub type Gt = Generic<_GT>;
 pub type Ge = Generic<_GE>;
 
 impl TypeGreater for _GT {
-    fn check_condition(&self, field_type: &String, base: &String, value: &String) -> bool {
+    fn check_condition(&self, field_type: &str, base: &str, value: &CellValue) -> bool {
         if field_type.contains("float") {
-            let base = base.parse::<f32>().unwrap();
-            let v = value.parse::<f32>().unwrap();
-            return base < v;
+            let base = base.parse::<f64>().unwrap();
+            return match value.as_f64() {
+                Some(v) => base < v,
+                None => false,
+            };
         }
 
         if field_type.contains("int") {
-            let base = base.parse::<i32>().unwrap();
-            let v = value.parse::<i32>().unwrap();
-            return base < v;
+            let base = base.parse::<i64>().unwrap();
+            return match value.as_i64() {
+                Some(v) => base < v,
+                None => false,
+            };
         }
         false
     }
 }
 
 impl TypeGreater for _GE {
-    fn check_condition(&self, field_type: &String, base: &String, value: &String) -> bool {
+    fn check_condition(&self, field_type: &str, base: &str, value: &CellValue) -> bool {
         if field_type.contains("float") {
-            let base = base.parse::<f32>().unwrap();
-            let v = value.parse::<f32>().unwrap();
-            return base <= v;
+            let base = base.parse::<f64>().unwrap();
+            return match value.as_f64() {
+                Some(v) => base <= v,
+                None => false,
+            };
         }
 
         if field_type.contains("int") {
-            let base = base.parse::<i32>().unwrap();
-            let v = value.parse::<i32>().unwrap();
-            return base <= v;
+            let base = base.parse::<i64>().unwrap();
+            return match value.as_i64() {
+                Some(v) => base <= v,
+                None => false,
+            };
         }
         false
     }
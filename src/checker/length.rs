@@ -21,36 +21,53 @@ OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
 WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
 */
 use super::Checker;
+use crate::cell::CellValue;
+use crate::enums::AnyEnum;
+use crate::types::FieldType;
 
 #[derive(Debug)]
 pub struct Len {
-    field_type: String,
+    field_type: FieldType,
     len_min: i32,
     len_max: i32,
 }
 
 impl Len {
-    pub fn new(field_type: &String) -> Len {
-        Len {
-            field_type: field_type.clone(),
+    /// None when values of `field_type` have no length
+    pub fn new(field_type: &str) -> Option<Len> {
+        let field_type = match FieldType::parse(field_type) {
+            Ok(
+                t @ (FieldType::String
+                | FieldType::Array(_)
+                | FieldType::Tuple(_)
+                | FieldType::Struct(_)),
+            ) => t,
+            Ok(FieldType::Custom(name)) if name == "localize" => FieldType::Custom(name),
+            _ => return None,
+        };
+        Some(Len {
+            field_type,
             len_min: -1,
             len_max: -1,
-        }
+        })
     }
 }
 
 impl Checker for Len {
-    fn test(&self, value: &String) -> bool {
-        if self.field_type.contains("string") || self.field_type.contains("localize") {
-            let l = value.len();
-            return l as i32 >= self.len_min && l as i32 <= self.len_max;
-        }
-        if self.field_type.contains("[]") {
-            let a = value.split(",").enumerate().count() as i32;
-            return a >= self.len_min && a <= self.len_max;
-        }
+    fn test(&self, value: &CellValue) -> bool {
+        let value = value.to_string();
+        let l = match &self.field_type {
+            // number of items, not characters
+            t @ (FieldType::Array(_) | FieldType::Tuple(_) | FieldType::Struct(_)) => {
+                match t.parse_value(&value, &AnyEnum) {
+                    Ok(v) => v.len(),
+                    Err(_) => return false,
+                }
+            }
+            _ => value.len(),
+        };
 
-        false
+        l as i32 >= self.len_min && l as i32 <= self.len_max
     }
 
     fn add(&mut self, _: Box<dyn Checker>) -> bool {
@@ -74,7 +91,7 @@ impl Checker for Len {
             return true;
         }
 
-        return false;
+        false
     }
 
     fn need_full_load(&self) -> bool {
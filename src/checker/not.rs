@@ -21,6 +21,7 @@ OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
 WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
 */
 use super::Checker;
+use crate::cell::CellValue;
 
 #[derive(Debug)]
 pub struct Not {
@@ -34,7 +35,7 @@ impl Not {
 }
 
 impl Checker for Not {
-    fn test(&self, value: &String) -> bool {
+    fn test(&self, value: &CellValue) -> bool {
         if let Some(checker) = &self.next {
             return !checker.test(value);
         }
@@ -42,7 +43,7 @@ impl Checker for Not {
     }
 
     fn add(&mut self, checker: Box<dyn Checker>) -> bool {
-        if let Some(_) = &self.next {
+        if self.next.is_some() || checker.is_unique() {
             return false;
         }
 
@@ -51,7 +52,7 @@ impl Checker for Not {
     }
 
     fn expect_more(&self) -> bool {
-        if let Some(_) = &self.next {
+        if self.next.is_some() {
             return false;
         }
 
@@ -65,4 +66,11 @@ impl Checker for Not {
     fn need_full_load(&self) -> bool {
         false
     }
+
+    fn references(&self) -> Vec<String> {
+        match &self.next {
+            Some(checker) => checker.references(),
+            None => vec![],
+        }
+    }
 }
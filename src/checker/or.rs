@@ -21,6 +21,7 @@ OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
 WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
 */
 use super::Checker;
+use crate::cell::CellValue;
 
 #[derive(Debug)]
 pub struct Or {
@@ -34,7 +35,7 @@ impl Or {
 }
 
 impl Checker for Or {
-    fn test(&self, value: &String) -> bool {
+    fn test(&self, value: &CellValue) -> bool {
         for c in &self.childs {
             if c.test(value) {
                 return true;
@@ -45,6 +46,10 @@ impl Checker for Or {
     }
 
     fn add(&mut self, checker: Box<dyn Checker>) -> bool {
+        // a column can not be unique on one branch only
+        if checker.is_unique() {
+            return false;
+        }
         self.childs.push(checker);
         true
     }
@@ -63,4 +68,8 @@ impl Checker for Or {
     fn need_full_load(&self) -> bool {
         false
     }
+
+    fn references(&self) -> Vec<String> {
+        self.childs.iter().flat_map(|c| c.references()).collect()
+    }
 }
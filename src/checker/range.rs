@@ -23,6 +23,7 @@ WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
 use std::str::FromStr;
 
 use super::Checker;
+use crate::cell::CellValue;
 
 #[derive(Debug)]
 pub struct Range {
@@ -32,39 +33,38 @@ pub struct Range {
 }
 
 impl Range {
-    pub fn new(field_type: &String) -> Range {
+    pub fn new(field_type: &str) -> Range {
         Range {
-            field_type: field_type.clone(),
+            field_type: field_type.to_string(),
             start: String::from(""),
             end: String::from(""),
         }
     }
 
-    pub fn is_in_range<T: PartialOrd + FromStr>(&self, value: &String) -> bool
+    pub fn is_in_range<T: PartialOrd + FromStr>(&self, v: T) -> bool
     where
         <T as FromStr>::Err: std::fmt::Debug,
     {
-        let v = value.parse::<T>().unwrap();
         let start = self.start.parse::<T>().unwrap();
         let end = self.end.parse::<T>().unwrap();
         if v < start || v > end {
             return false;
         }
 
-        return true;
+        true
     }
 }
 
 impl Checker for Range {
-    fn test(&self, value: &String) -> bool {
-        if value.trim().is_empty() {
-            return false;
-        }
-
+    fn test(&self, value: &CellValue) -> bool {
         if self.field_type.contains("float") {
-            return self.is_in_range::<f32>(value);
+            if let Some(v) = value.as_f64() {
+                return self.is_in_range::<f64>(v);
+            }
         } else if self.field_type.contains("int") {
-            return self.is_in_range::<i32>(value);
+            if let Some(v) = value.as_i64() {
+                return self.is_in_range::<i64>(v);
+            }
         }
 
         false
@@ -0,0 +1,58 @@
+/*
+Copyright (c) 2024- rickhan<rick.han@yahoo.com>
+
+Permission is hereby granted, free of charge, to any person obtaining
+a copy of this software and associated documentation files (the
+"Software"), to deal in the Software without restriction, including
+without limitation the rights to use, copy, modify, merge, publish,
+distribute, sublicense, and/or sell copies of the Software, and to
+permit persons to whom the Software is furnished to do so, subject to
+the following conditions:
+
+The above copyright notice and this permission notice shall be
+included in all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+*/
+
+use super::Checker;
+use crate::cell::CellValue;
+
+/// values of the column must differ from each other, checked over the whole
+/// table by `ALLXLSX::check_xlsx_valid`, every single value passes
+#[derive(Debug)]
+pub struct Unique {}
+
+impl Unique {
+    pub fn new() -> Self {
+        Unique {}
+    }
+}
+
+impl Checker for Unique {
+    fn test(&self, _: &CellValue) -> bool {
+        true
+    }
+
+    fn add(&mut self, _: Box<dyn Checker>) -> bool {
+        false
+    }
+
+    fn expect_more(&self) -> bool {
+        false
+    }
+
+    fn add_param(&mut self, _: String) -> bool {
+        false
+    }
+
+    fn is_unique(&self) -> bool {
+        true
+    }
+}
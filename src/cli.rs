@@ -0,0 +1,612 @@
+/*
+Copyright (c) 2024- rickhan<rick.han@yahoo.com>
+
+Permission is hereby granted, free of charge, to any person obtaining
+a copy of this software and associated documentation files (the
+"Software"), to deal in the Software without restriction, including
+without limitation the rights to use, copy, modify, merge, publish,
+distribute, sublicense, and/or sell copies of the Software, and to
+permit persons to whom the Software is furnished to do so, subject to
+the following conditions:
+
+The above copyright notice and this permission notice shall be
+included in all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+*/
+
+use crate::cache::{content_hash, hash_files, BuildCache};
+use crate::context::{ExportContext, ReportFormat};
+use crate::diagnostics::Diagnostics;
+use crate::diff::diff_all;
+use crate::generator::*;
+use crate::layout::ProjectConfig;
+use crate::layout::CONFIG_FILE_NAME;
+use crate::notifier::WebhookNotifier;
+use crate::pool::par_map;
+use crate::scan::find_workbooks;
+use crate::schema;
+use crate::template::{example_sheets, write_workbook};
+use crate::watch::{snapshot, Changes, Debouncer, IDLE_INTERVAL, POLL_INTERVAL};
+use crate::xlsx::{ALLXLSX, XLSX};
+use clap::builder::PossibleValue;
+use clap::{Parser, Subcommand, ValueEnum};
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None, args_conflicts_with_subcommands = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    // without a subcommand the options of `export` are accepted
+    #[command(flatten)]
+    args: Args,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// validate the workbooks and generate code
+    Export(Args),
+    /// validate the workbooks without writing anything
+    Check(CheckArgs),
+    /// compare the data of two folders of workbooks
+    Diff(DiffArgs),
+    /// print the fields parsed from the header rows
+    Schema(SchemaArgs),
+    /// write an example workbook with the expected header rows
+    Init(InitArgs),
+    /// export, then export again whenever a workbook is saved
+    Watch {
+        #[command(flatten)]
+        args: Args,
+
+        /// milliseconds the input must stay unchanged before exporting
+        #[arg(long, default_value_t = 500)]
+        debounce: u64,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum Target {
+    Lua,
+    Json,
+    Python,
+    Sql,
+    Yaml,
+    Csharp,
+    Go,
+    All,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum SchemaFormat {
+    Text,
+    Json,
+}
+
+// which workbooks are read
+#[derive(clap::Args, Debug)]
+struct FilterArgs {
+    /// only read workbooks matching these globs, relative to the input path,
+    /// e.g. `battle/**`
+    #[arg(long, value_delimiter = ',')]
+    include: Vec<String>,
+
+    /// skip workbooks matching these globs, e.g. `test_*.xlsx`
+    #[arg(long, value_delimiter = ',')]
+    exclude: Vec<String>,
+
+    /// threads used to load and generate workbooks, 0 uses one per cpu
+    #[arg(short, long, default_value_t = 0)]
+    jobs: usize,
+}
+
+#[derive(clap::Args, Debug)]
+struct Args {
+    /// input path to specific
+    #[arg(short, long, default_value_t = String::from("."))]
+    input: String,
+
+    /// output path to specific
+    #[arg(short, long, default_value_t = String::from("."))]
+    output: String,
+
+    /// comma separated generators
+    #[arg(short, long, value_enum, value_delimiter = ',', default_value = "lua")]
+    target: Vec<Target>,
+
+    #[arg(short, long, default_value_t = String::from(""))]
+    notify_url: String,
+
+    #[arg(short, long, default_value_t = String::from(""))]
+    project: String,
+
+    /// how validation errors are printed
+    #[arg(long, value_enum, default_value_t = ReportFormat::Text)]
+    error_format: ReportFormat,
+
+    /// only export columns for this side: client, server or a custom tag,
+    /// per target like lua=client,sql=server
+    #[arg(long, default_value_t = String::from(""))]
+    side: String,
+
+    #[command(flatten)]
+    filter: FilterArgs,
+
+    /// write rows sorted by their key columns instead of in workbook order
+    #[arg(long, default_value_t = false)]
+    sort_rows: bool,
+
+    /// ignore the build cache and export every workbook
+    #[arg(long, default_value_t = false)]
+    no_cache: bool,
+
+    /// also export the enums defined in `__enum__` sheets
+    #[arg(long, default_value_t = false)]
+    emit_enums: bool,
+
+    /// namespace of the generated c# code
+    #[arg(long, default_value_t = String::from(""))]
+    namespace: String,
+
+    /// package of the generated go code
+    #[arg(long, default_value_t = String::from("config"))]
+    package: String,
+}
+
+#[derive(clap::Args, Debug)]
+struct CheckArgs {
+    /// input path to specific
+    #[arg(short, long, default_value_t = String::from("."))]
+    input: String,
+
+    #[arg(short, long, default_value_t = String::from(""))]
+    project: String,
+
+    /// how validation errors are printed
+    #[arg(long, value_enum, default_value_t = ReportFormat::Text)]
+    error_format: ReportFormat,
+
+    #[command(flatten)]
+    filter: FilterArgs,
+}
+
+#[derive(clap::Args, Debug)]
+struct DiffArgs {
+    /// folder with the old workbooks
+    old: String,
+
+    /// folder with the new workbooks
+    new: String,
+
+    #[command(flatten)]
+    filter: FilterArgs,
+}
+
+#[derive(clap::Args, Debug)]
+struct SchemaArgs {
+    /// input path to specific
+    #[arg(short, long, default_value_t = String::from("."))]
+    input: String,
+
+    #[arg(long, value_enum, default_value_t = SchemaFormat::Text)]
+    format: SchemaFormat,
+
+    #[command(flatten)]
+    filter: FilterArgs,
+}
+
+#[derive(clap::Args, Debug)]
+struct InitArgs {
+    /// the workbook to write
+    #[arg(default_value_t = String::from("example.xlsx"))]
+    path: String,
+
+    /// overwrite an existing file
+    #[arg(long, default_value_t = false)]
+    force: bool,
+}
+
+const PROG_SIG: &str = r"
+       .__                    __                          .__        __   .__                   
+___  __|  |   _________  ____/  |_  ____           _______|__| ____ |  | _|  |__ _____    ____  
+\  \/  /  |  /  ___/\  \/  /\   __\/  _ \   ______ \_  __ \  |/ ___\|  |/ /  |  \\__  \  /    \ 
+ >    <|  |__\___ \  >    <  |  | (  <_> ) /_____/  |  | \/  \  \___|    <|   Y  \/ __ \|   |  \
+/__/\_ \____/____  >/__/\_ \ |__|  \____/           |__|  |__|\___  >__|_ \___|  (____  /___|  /
+      \/         \/       \/                                      \/     \/    \/     \/     \/ 
+";
+
+fn target_names(targets: &[Target]) -> Vec<String> {
+    targets
+        .iter()
+        .filter_map(|t| t.to_possible_value())
+        .map(|v: PossibleValue| v.get_name().to_string())
+        .collect()
+}
+
+fn context(args: &Args) -> ExportContext {
+    let mut ctx = ExportContext::new(&args.output);
+    ctx.project = args.project.clone();
+    ctx.targets = target_names(&args.target);
+    ctx.notifier = Some(Box::new(WebhookNotifier::new(&args.notify_url)));
+    ctx.options.report_format = args.error_format;
+    ctx.options.set_sides(&args.side);
+    ctx.options.emit_enums = args.emit_enums;
+    ctx.options.jobs = args.filter.jobs;
+    ctx.options.sort_by_key = args.sort_rows;
+    ctx.options.namespace = args.namespace.clone();
+    ctx.options.package = args.package.clone();
+    ctx
+}
+
+// outputs depend on the project config and on what is exported
+fn options_key(args: &Args) -> String {
+    let config_hash = fs::read(Path::new(&args.input).join(CONFIG_FILE_NAME))
+        .map(|b| content_hash(&b))
+        .unwrap_or_default();
+    format!(
+        "target={} side={} emit_enums={} sort_rows={} namespace={} package={} config={}",
+        target_names(&args.target).join(","),
+        args.side,
+        args.emit_enums,
+        args.sort_rows,
+        args.namespace,
+        args.package,
+        config_hash
+    )
+}
+
+fn open_cache(args: &Args) -> BuildCache {
+    if args.no_cache {
+        return BuildCache::new(&options_key(args));
+    }
+    BuildCache::load(&args.output, &options_key(args))
+}
+
+/// reads the tables of `files` on `jobs` threads
+fn load_files(
+    input: &str,
+    files: &[String],
+    config: &ProjectConfig,
+    jobs: usize,
+) -> Result<Vec<(String, XLSX)>, crate::Error> {
+    let mut tables = vec![];
+    for ret in par_map(files, jobs, |fname| {
+        let layout = config.layout_for(fname).clone();
+        XLSX::parse_tables(input, fname, layout)
+    }) {
+        tables.extend(ret?);
+    }
+    Ok(tables)
+}
+
+/// reads every workbook under `input` selected by `filter`
+fn load_all(input: &str, filter: &FilterArgs) -> Result<ALLXLSX, Box<dyn Error>> {
+    let config = ProjectConfig::load(input)?;
+    let files = find_workbooks(input, &filter.include, &filter.exclude)?;
+    let mut all = ALLXLSX::new();
+    for (name, xlsx) in load_files(input, &files, &config, filter.jobs)? {
+        all.add(&name, xlsx);
+    }
+    Ok(all)
+}
+
+/// exports the workbooks that changed since `cache` was updated, returns
+/// them or None when validation failed
+fn export(
+    args: &Args,
+    ctx: &ExportContext,
+    cache: &mut BuildCache,
+) -> Result<Option<Vec<String>>, Box<dyn Error>> {
+    let options = options_key(args);
+    if cache.options != options {
+        *cache = BuildCache::new(&options);
+    }
+    let filter = &args.filter;
+    let config = ProjectConfig::load(&args.input)?;
+    let files = find_workbooks(&args.input, &filter.include, &filter.exclude)?;
+    let hashes = hash_files(&args.input, &files)?;
+    let stale = cache.stale_files(&hashes);
+    log::info!("{} of {} workbooks to export", stale.len(), files.len());
+    if stale.is_empty() {
+        println!("没有改动的表");
+        return Ok(Some(stale));
+    }
+
+    let mut all = ALLXLSX::new();
+    for (name, xlsx) in load_files(&args.input, &stale, &config, filter.jobs)? {
+        all.add(&name, xlsx);
+    }
+    // unchanged workbooks the stale ones look values up in
+    let lookups = cache.lookup_files(&all, &files, ctx);
+    for (name, xlsx) in load_files(&args.input, &lookups, &config, filter.jobs)? {
+        all.add_lookup_only(&name, xlsx);
+    }
+
+    let mut diagnostics = Diagnostics::new();
+    all.check_xlsx_valid(ctx, &mut diagnostics);
+    if !diagnostics.is_empty() {
+        eprintln!("{}", ctx.report(&diagnostics));
+        return Ok(None);
+    }
+
+    generate_all(&all, ctx)?;
+
+    cache.update(&all, &hashes, &stale, ctx);
+    cache.save(&args.output)?;
+    for fname in &stale {
+        println!("{} 导出成功", fname);
+    }
+
+    Ok(Some(stale))
+}
+
+fn run_export(args: &Args) -> Result<bool, Box<dyn Error>> {
+    println!(
+        "{} xlsx path={}, output path={}",
+        PROG_SIG, args.input, args.output
+    );
+    let ctx = context(args);
+    let mut cache = open_cache(args);
+    Ok(export(args, &ctx, &mut cache)?.is_some())
+}
+
+fn watch(args: &Args, debounce: u64) -> Result<(), Box<dyn Error>> {
+    println!(
+        "{} xlsx path={}, output path={}",
+        PROG_SIG, args.input, args.output
+    );
+    let ctx = context(args);
+    let mut cache = open_cache(args);
+    // the project config is watched too, a change to it exports everything
+    let watched = || -> Vec<String> {
+        let mut files = find_workbooks(&args.input, &args.filter.include, &args.filter.exclude)
+            .unwrap_or_default();
+        files.push(CONFIG_FILE_NAME.to_string());
+        files
+    };
+
+    let changes = Changes::new(&args.input);
+    let mut debouncer = Debouncer::new(
+        Duration::from_millis(debounce),
+        snapshot(&args.input, &watched()),
+    );
+    let mut changed = true;
+    loop {
+        if changed {
+            // a workbook may be unreadable half way through a save, the next
+            // save exports it again
+            if let Err(e) = export(args, &ctx, &mut cache) {
+                eprintln!("{}", e);
+            }
+            println!("正在监视{}，按Ctrl+C退出", args.input);
+        }
+        // a save in progress is checked again shortly, otherwise sleep until
+        // a file is touched
+        let timeout = if debouncer.is_settling() {
+            POLL_INTERVAL
+        } else {
+            IDLE_INTERVAL
+        };
+        changes.wait(timeout);
+        changed = debouncer.poll(snapshot(&args.input, &watched()), Instant::now());
+    }
+}
+
+/// validates every workbook, false when there were errors
+fn check(args: &CheckArgs) -> Result<bool, Box<dyn Error>> {
+    let all = load_all(&args.input, &args.filter)?;
+    let mut ctx = ExportContext::new("");
+    ctx.project = args.project.clone();
+    ctx.options.report_format = args.error_format;
+
+    let mut diagnostics = Diagnostics::new();
+    all.check_xlsx_valid(&ctx, &mut diagnostics);
+    if !diagnostics.is_empty() {
+        eprintln!("{}", ctx.report(&diagnostics));
+        return Ok(false);
+    }
+    println!("{}个表校验通过", all.tables().len());
+    Ok(true)
+}
+
+fn diff(args: &DiffArgs) -> Result<(), Box<dyn Error>> {
+    let old = load_all(&args.old, &args.filter)?;
+    let new = load_all(&args.new, &args.filter)?;
+    let diffs = diff_all(&old, &new);
+    if diffs.is_empty() {
+        println!("没有差异");
+    }
+    for d in diffs {
+        println!("{}", d);
+    }
+    Ok(())
+}
+
+fn print_schema(args: &SchemaArgs) -> Result<(), Box<dyn Error>> {
+    let all = load_all(&args.input, &args.filter)?;
+    match args.format {
+        SchemaFormat::Text => println!("{}", schema::to_text(&all)),
+        SchemaFormat::Json => println!("{}", schema::to_json(&all)),
+    }
+    Ok(())
+}
+
+fn init(args: &InitArgs) -> Result<(), Box<dyn Error>> {
+    if Path::new(&args.path).exists() && !args.force {
+        return Err(format!("{}已存在，加--force覆盖", args.path).into());
+    }
+    write_workbook(&args.path, &example_sheets())?;
+    println!("已生成{}", args.path);
+    Ok(())
+}
+
+/// parses the command line and runs the command, false when validation failed
+pub fn run() -> Result<bool, Box<dyn Error>> {
+    let cli = Cli::parse();
+    let ok = match &cli.command {
+        Some(Command::Check(args)) => check(args)?,
+        Some(Command::Diff(args)) => {
+            diff(args)?;
+            true
+        }
+        Some(Command::Schema(args)) => {
+            print_schema(args)?;
+            true
+        }
+        Some(Command::Init(args)) => {
+            init(args)?;
+            true
+        }
+        Some(Command::Watch { args, debounce }) => {
+            watch(args, *debounce)?;
+            true
+        }
+        Some(Command::Export(args)) => run_export(args)?,
+        None => run_export(&cli.args)?,
+    };
+    Ok(ok)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check, context, export, open_cache, Args, Cli, Command};
+    use crate::context::ReportFormat;
+    use crate::fixture::{self, sheet1, text};
+    use crate::template::{write_workbook, Sheet};
+    use crate::watch::Changes;
+    use clap::Parser;
+    use std::fs;
+    use std::time::Duration;
+
+    /// field names in the first row, conditions in the second and types in
+    /// the third
+    fn input_dir(name: &str) -> String {
+        let input = fixture::temp_dir(name);
+        fs::write(
+            format!("{}/xlsxto.toml", input),
+            "[layout]\ncn_name = 0\nfield_name = 1\ncondition = 2\nclient_server = 0\nfield_type = 3\ndata_start = 4\n",
+        )
+        .unwrap();
+        input
+    }
+
+    fn write_book(input: &str, fname: &str, sheets: Vec<Sheet>) {
+        write_workbook(&format!("{}/{}", input, fname), &sheets).unwrap();
+    }
+
+    fn export_args(argv: &[&str]) -> Args {
+        let cli = Cli::try_parse_from(argv).unwrap();
+        match cli.command {
+            Some(Command::Export(args)) => args,
+            _ => cli.args,
+        }
+    }
+
+    #[test]
+    fn test_watch_exports_dependents() {
+        let input = input_dir("xlsxto_watch");
+        let output = fixture::temp_dir("xlsxto_watch_out");
+        let item = |name: &str| {
+            sheet1(vec![
+                text(&["id", "name"]),
+                text(&["", ""]),
+                text(&["int!key", "string"]),
+                text(&["1", name]),
+            ])
+        };
+        let skill = sheet1(vec![
+            text(&["id", "item"]),
+            text(&["", "expect('item.xlsx', 'id')"]),
+            text(&["int!key", "int"]),
+            text(&["1", "1"]),
+        ]);
+        let other = sheet1(vec![
+            text(&["id"]),
+            text(&[""]),
+            text(&["int!key"]),
+            text(&["1"]),
+        ]);
+        write_book(&input, "item.xlsx", item("a"));
+        write_book(&input, "skill.xlsx", skill);
+        write_book(&input, "other.xlsx", other);
+
+        let args = export_args(&["xlsxto", "export", "--input", &input, "--output", &output]);
+        let ctx = context(&args);
+        let mut cache = open_cache(&args);
+        let exported = |cache: &mut _| export(&args, &ctx, cache).unwrap().unwrap();
+        assert_eq!(
+            exported(&mut cache),
+            vec!["item.xlsx", "other.xlsx", "skill.xlsx"]
+        );
+
+        // saving item.xlsx wakes the watcher and exports the tables expecting
+        // values in it too
+        let changes = Changes::new(&input);
+        write_book(&input, "item.xlsx", item("b"));
+        assert!(changes.wait(Duration::from_secs(10)));
+        assert_eq!(exported(&mut cache), vec!["item.xlsx", "skill.xlsx"]);
+        assert!(exported(&mut cache).is_empty());
+    }
+
+    #[test]
+    fn test_check_writes_nothing() {
+        let input = input_dir("xlsxto_check");
+        let rows = |id: &str| {
+            vec![
+                text(&["id"]),
+                text(&[""]),
+                text(&["int!key"]),
+                text(&["1"]),
+                text(&[id]),
+            ]
+        };
+        let files = |dir: &str| -> Vec<String> {
+            let mut names: Vec<String> = fs::read_dir(dir)
+                .unwrap()
+                .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+                .collect();
+            names.sort();
+            names
+        };
+
+        for (id, valid) in [("2", true), ("1", false)] {
+            write_book(&input, "item.xlsx", sheet1(rows(id)));
+            let before = (files(&input), files("."));
+            let argv = [
+                "xlsxto",
+                "check",
+                "--input",
+                &input,
+                "--error-format",
+                "json",
+            ];
+            let cli = Cli::try_parse_from(argv).unwrap();
+            let args = match &cli.command {
+                Some(Command::Check(args)) => args,
+                _ => unreachable!(),
+            };
+            assert_eq!(args.error_format, ReportFormat::Json);
+            assert_eq!(check(args).unwrap(), valid);
+            assert_eq!((files(&input), files(".")), before);
+        }
+    }
+
+    #[test]
+    fn test_unknown_values() {
+        assert!(Cli::try_parse_from(["xlsxto", "--target", "lua,go"]).is_ok());
+        assert!(Cli::try_parse_from(["xlsxto", "--target", "java"]).is_err());
+        assert!(Cli::try_parse_from(["xlsxto", "export", "--target", "lua,java"]).is_err());
+        assert!(Cli::try_parse_from(["xlsxto", "check", "--error-format", "xml"]).is_err());
+    }
+}
@@ -0,0 +1,137 @@
+/*
+Copyright (c) 2024- rickhan<rick.han@yahoo.com>
+
+Permission is hereby granted, free of charge, to any person obtaining
+a copy of this software and associated documentation files (the
+"Software"), to deal in the Software without restriction, including
+without limitation the rights to use, copy, modify, merge, publish,
+distribute, sublicense, and/or sell copies of the Software, and to
+permit persons to whom the Software is furnished to do so, subject to
+the following conditions:
+
+The above copyright notice and this permission notice shall be
+included in all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+*/
+use crate::diagnostics::Diagnostics;
+use crate::error::Error;
+use crate::notifier::Notifier;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// how a validation report is rendered
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Default)]
+pub enum ReportFormat {
+    #[default]
+    Text,
+    Markdown,
+    Json,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ExportOptions {
+    pub report_format: ReportFormat,
+    /// side every target exports for, e.g. `client`, empty exports every column
+    pub side: String,
+    /// per target side, overrides `side`
+    pub target_sides: HashMap<String, String>,
+    /// also write the enum definitions of `__enum__` sheets
+    pub emit_enums: bool,
+    /// threads used to load and generate workbooks, 0 uses one per cpu
+    pub jobs: usize,
+    /// write rows sorted by their key columns instead of in workbook order
+    pub sort_by_key: bool,
+    /// namespace of the generated c# code, empty for the global namespace
+    pub namespace: String,
+    /// package of the generated go code, empty for `config`
+    pub package: String,
+}
+
+impl ExportOptions {
+    /// parses `client`, or per target pairs like `lua=client,sql=server`
+    pub fn set_sides(&mut self, spec: &str) {
+        for item in spec.split(',') {
+            match item.split_once('=') {
+                Some((target, side)) => {
+                    self.target_sides
+                        .insert(target.trim().to_string(), side.trim().to_lowercase());
+                }
+                None => self.side = item.trim().to_lowercase(),
+            }
+        }
+    }
+}
+
+/// everything an export needs to know, passed explicitly instead of read from argv
+pub struct ExportContext {
+    pub project: String,
+    pub output: String,
+    /// generator names, `all` selects every generator
+    pub targets: Vec<String>,
+    pub notifier: Option<Box<dyn Notifier>>,
+    pub options: ExportOptions,
+}
+
+impl ExportContext {
+    pub fn new(output: &str) -> ExportContext {
+        ExportContext {
+            project: String::from(""),
+            output: output.to_string(),
+            targets: vec![],
+            notifier: None,
+            options: ExportOptions::default(),
+        }
+    }
+
+    pub fn has_target(&self, target: &str) -> bool {
+        self.targets.iter().any(|t| t == target || t == "all")
+    }
+
+    /// where `file_name` generated from the workbook `fname` is written,
+    /// workbooks in subfolders of the input go to the same subfolders
+    pub fn output_path(&self, fname: &str, file_name: &str) -> String {
+        match Path::new(fname).parent().and_then(|p| p.to_str()) {
+            Some(dir) if !dir.is_empty() => format!("{}/{}/{}", self.output, dir, file_name),
+            _ => format!("{}/{}", self.output, file_name),
+        }
+    }
+
+    /// side the columns are exported for when generating `target`
+    pub fn side_for(&self, target: &str) -> String {
+        match self.options.target_sides.get(target) {
+            Some(side) => side.clone(),
+            None => self.options.side.clone(),
+        }
+    }
+
+    pub fn notify(&self, content: &str) {
+        if let Some(notifier) = &self.notifier {
+            if let Err(e) = notifier.notify(content) {
+                log::error!("notify error occurred! {}", e);
+            }
+        }
+    }
+
+    pub fn notify_error(&self, e: &Error) {
+        self.notify(&format!(
+            "**导表校验失败**\n**项目：{}**\n{}",
+            self.project,
+            e.to_markdown()
+        ));
+    }
+
+    pub fn report(&self, diagnostics: &Diagnostics) -> String {
+        match self.options.report_format {
+            ReportFormat::Text => diagnostics.to_text(&self.project),
+            ReportFormat::Markdown => diagnostics.to_markdown(&self.project),
+            ReportFormat::Json => diagnostics.to_json(&self.project),
+        }
+    }
+}
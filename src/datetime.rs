@@ -0,0 +1,246 @@
+/*
+Copyright (c) 2024- rickhan<rick.han@yahoo.com>
+
+Permission is hereby granted, free of charge, to any person obtaining
+a copy of this software and associated documentation files (the
+"Software"), to deal in the Software without restriction, including
+without limitation the rights to use, copy, modify, merge, publish,
+distribute, sublicense, and/or sell copies of the Software, and to
+permit persons to whom the Software is furnished to do so, subject to
+the following conditions:
+
+The above copyright notice and this permission notice shall be
+included in all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+*/
+
+//! date, datetime and duration cells. times have no timezone and are
+//! converted to unix seconds as if they were UTC.
+
+const SECONDS_PER_DAY: i64 = 86400;
+// excel serial number of 1970-01-01
+const EXCEL_EPOCH_SERIAL: f64 = 25569.0;
+
+// days since 1970-01-01, see http://howardhinnant.github.io/date_algorithms.html
+fn days_from_civil(y: i64, m: i64, d: i64) -> i64 {
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = if y >= 0 { y } else { y - 399 } / 400;
+    let yoe = y - era * 400;
+    let mp = (m + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+fn civil_from_days(z: i64) -> (i64, i64, i64) {
+    let z = z + 719468;
+    let era = if z >= 0 { z } else { z - 146096 } / 146097;
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    let y = yoe + era * 400;
+    (if m <= 2 { y + 1 } else { y }, m, d)
+}
+
+fn days_in_month(y: i64, m: i64) -> i64 {
+    match m {
+        2 if (y % 4 == 0 && y % 100 != 0) || y % 400 == 0 => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+fn number(s: &str) -> Option<i64> {
+    if s.is_empty() || !s.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    s.parse::<i64>().ok()
+}
+
+// `2024-01-31` or `2024/1/31`
+fn parse_ymd(s: &str) -> Option<i64> {
+    let parts: Vec<&str> = s.split(['-', '/']).collect();
+    if parts.len() != 3 {
+        return None;
+    }
+    let (y, m, d) = (number(parts[0])?, number(parts[1])?, number(parts[2])?);
+    if !(1..=12).contains(&m) || d < 1 || d > days_in_month(y, m) {
+        return None;
+    }
+    Some(days_from_civil(y, m, d))
+}
+
+// `13:30` or `13:30:15`, seconds of the day
+fn parse_hms(s: &str) -> Option<i64> {
+    let parts: Vec<&str> = s.split(':').collect();
+    if parts.len() < 2 || parts.len() > 3 {
+        return None;
+    }
+    let h = number(parts[0])?;
+    let m = number(parts[1])?;
+    let sec = match parts.get(2) {
+        Some(p) => number(p)?,
+        None => 0,
+    };
+    if m >= 60 || sec >= 60 {
+        return None;
+    }
+    Some(h * 3600 + m * 60 + sec)
+}
+
+fn parse_serial(s: &str) -> Option<i64> {
+    let serial = s.parse::<f64>().ok().filter(|f| f.is_finite())?;
+    Some(((serial - EXCEL_EPOCH_SERIAL) * SECONDS_PER_DAY as f64).round() as i64)
+}
+
+/// a date as unix seconds at midnight, from `2024-01-31` or an excel serial number
+pub fn parse_date(s: &str) -> Result<i64, String> {
+    let s = s.trim();
+    if let Some(days) = parse_ymd(s) {
+        return Ok(days * SECONDS_PER_DAY);
+    }
+    match parse_serial(s) {
+        Some(secs) => Ok(secs.div_euclid(SECONDS_PER_DAY) * SECONDS_PER_DAY),
+        None => Err(format!("{}不是日期，应为2024-01-31", s)),
+    }
+}
+
+/// unix seconds from `2024-01-31 13:30:00`, `2024-01-31T13:30` or an excel serial number
+pub fn parse_datetime(s: &str) -> Result<i64, String> {
+    let s = s.trim();
+    let (date, time) = match s.split_once([' ', 'T']) {
+        Some((date, time)) => (date, Some(time.trim())),
+        None => (s, None),
+    };
+    if let Some(days) = parse_ymd(date) {
+        let secs = match time {
+            Some(t) => match parse_hms(t) {
+                Some(secs) if secs < SECONDS_PER_DAY => secs,
+                _ => return Err(format!("{}不是时间，应为2024-01-31 13:30:00", s)),
+            },
+            None => 0,
+        };
+        return Ok(days * SECONDS_PER_DAY + secs);
+    }
+    match parse_serial(s) {
+        Some(secs) => Ok(secs),
+        None => Err(format!("{}不是时间，应为2024-01-31 13:30:00", s)),
+    }
+}
+
+/// seconds from `1d2h30m15s`, `1.5h`, `01:30:00` or a plain number of seconds
+pub fn parse_duration(s: &str) -> Result<i64, String> {
+    let s = s.trim();
+    let err = || format!("{}不是时长，应为1h30m或秒数", s);
+    if let Ok(secs) = s.parse::<f64>() {
+        if !secs.is_finite() {
+            return Err(err());
+        }
+        return Ok(secs.round() as i64);
+    }
+    if let Some(secs) = parse_hms(s) {
+        return Ok(secs);
+    }
+
+    let mut total = 0.0;
+    let mut num = String::new();
+    let mut has_unit = false;
+    for c in s.chars() {
+        if c.is_ascii_digit() || c == '.' {
+            num.push(c);
+            continue;
+        }
+        let unit = match c.to_ascii_lowercase() {
+            'd' => SECONDS_PER_DAY,
+            'h' => 3600,
+            'm' => 60,
+            's' => 1,
+            _ => return Err(err()),
+        };
+        let n = num.parse::<f64>().map_err(|_| err())?;
+        total += n * unit as f64;
+        num.clear();
+        has_unit = true;
+    }
+    if !num.is_empty() || !has_unit {
+        return Err(err());
+    }
+    Ok(total.round() as i64)
+}
+
+/// `2024-01-31`
+pub fn format_date(secs: i64) -> String {
+    let (y, m, d) = civil_from_days(secs.div_euclid(SECONDS_PER_DAY));
+    format!("{:04}-{:02}-{:02}", y, m, d)
+}
+
+/// `2024-01-31{sep}13:30:00`
+pub fn format_datetime(secs: i64, sep: char) -> String {
+    let t = secs.rem_euclid(SECONDS_PER_DAY);
+    format!(
+        "{}{}{:02}:{:02}:{:02}",
+        format_date(secs),
+        sep,
+        t / 3600,
+        t % 3600 / 60,
+        t % 60
+    )
+}
+
+/// (year, month, day, hour, minute, second)
+pub fn to_parts(secs: i64) -> (i64, i64, i64, i64, i64, i64) {
+    let (y, m, d) = civil_from_days(secs.div_euclid(SECONDS_PER_DAY));
+    let t = secs.rem_euclid(SECONDS_PER_DAY);
+    (y, m, d, t / 3600, t % 3600 / 60, t % 60)
+}
+
+/// an excel time cell is a fraction of a day
+pub fn serial_to_seconds(serial: f64) -> i64 {
+    (serial * SECONDS_PER_DAY as f64).round() as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_date, format_datetime, parse_date, parse_datetime, parse_duration};
+
+    #[test]
+    fn test_dates() {
+        assert_eq!(parse_date("1970-01-01").unwrap(), 0);
+        assert_eq!(parse_date("2024/2/29").unwrap(), 1709164800);
+        assert_eq!(parse_date("45351").unwrap(), 1709164800);
+        assert!(parse_date("2023-02-29").is_err());
+        assert!(parse_date("hello").is_err());
+        assert_eq!(format_date(1709164800), "2024-02-29");
+
+        let t = parse_datetime("2024-02-29 13:30:15").unwrap();
+        assert_eq!(t, 1709164800 + 13 * 3600 + 30 * 60 + 15);
+        assert_eq!(parse_datetime("2024-02-29T13:30:15").unwrap(), t);
+        assert_eq!(parse_datetime("45351.5").unwrap(), 1709164800 + 12 * 3600);
+        assert_eq!(format_datetime(t, 'T'), "2024-02-29T13:30:15");
+        assert_eq!(format_datetime(-1, ' '), "1969-12-31 23:59:59");
+        assert!(parse_datetime("2024-02-29 25:00").is_err());
+    }
+
+    #[test]
+    fn test_durations() {
+        assert_eq!(parse_duration("1h30m").unwrap(), 5400);
+        assert_eq!(parse_duration("1d2h").unwrap(), 93600);
+        assert_eq!(parse_duration("1.5h").unwrap(), 5400);
+        assert_eq!(parse_duration("90").unwrap(), 90);
+        assert_eq!(parse_duration("01:30:00").unwrap(), 5400);
+        assert!(parse_duration("1h30").is_err());
+        assert!(parse_duration("1w").is_err());
+        assert!(parse_duration("h").is_err());
+    }
+}
@@ -0,0 +1,108 @@
+/*
+Copyright (c) 2024- rickhan<rick.han@yahoo.com>
+
+Permission is hereby granted, free of charge, to any person obtaining
+a copy of this software and associated documentation files (the
+"Software"), to deal in the Software without restriction, including
+without limitation the rights to use, copy, modify, merge, publish,
+distribute, sublicense, and/or sell copies of the Software, and to
+permit persons to whom the Software is furnished to do so, subject to
+the following conditions:
+
+The above copyright notice and this permission notice shall be
+included in all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+*/
+use crate::error::{json_string, Error};
+
+/// collects every failing cell so that designers get the full list in one run
+#[derive(Debug, Default)]
+pub struct Diagnostics {
+    items: Vec<Error>,
+}
+
+/// converts a 0-based column index into excel column letters
+pub fn column_letter(index: usize) -> String {
+    let mut letters = vec![];
+    let mut n = index + 1;
+    while n > 0 {
+        let rem = (n - 1) % 26;
+        letters.push((b'A' + rem as u8) as char);
+        n = (n - 1) / 26;
+    }
+
+    letters.iter().rev().collect()
+}
+
+impl Diagnostics {
+    pub fn new() -> Diagnostics {
+        Diagnostics { items: vec![] }
+    }
+
+    pub fn push(&mut self, e: Error) {
+        self.items.push(e);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn to_text(&self, project: &str) -> String {
+        let mut content = format!("导表校验失败 项目：{} 共{}处错误", project, self.len());
+        for e in &self.items {
+            content += "\n";
+            content += &e.to_text();
+        }
+
+        content
+    }
+
+    pub fn to_markdown(&self, project: &str) -> String {
+        let mut content = format!(
+            "**导表校验失败**\n**项目：{}** 共{}处错误",
+            project,
+            self.len()
+        );
+        for e in &self.items {
+            content += "\n";
+            content += &e.to_markdown();
+        }
+
+        content
+    }
+
+    pub fn to_json(&self, project: &str) -> String {
+        let items: Vec<String> = self.items.iter().map(|e| e.to_json()).collect();
+        format!(
+            "{{\"project\":{},\"errors\":[{}]}}",
+            json_string(project),
+            items.join(",")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::column_letter;
+
+    #[test]
+    fn test_column_letter() {
+        assert_eq!(column_letter(0), "A");
+        assert_eq!(column_letter(25), "Z");
+        assert_eq!(column_letter(26), "AA");
+        assert_eq!(column_letter(27), "AB");
+        assert_eq!(column_letter(701), "ZZ");
+        assert_eq!(column_letter(702), "AAA");
+    }
+}
@@ -0,0 +1,284 @@
+/*
+Copyright (c) 2024- rickhan<rick.han@yahoo.com>
+
+Permission is hereby granted, free of charge, to any person obtaining
+a copy of this software and associated documentation files (the
+"Software"), to deal in the Software without restriction, including
+without limitation the rights to use, copy, modify, merge, publish,
+distribute, sublicense, and/or sell copies of the Software, and to
+permit persons to whom the Software is furnished to do so, subject to
+the following conditions:
+
+The above copyright notice and this permission notice shall be
+included in all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+*/
+
+//! compares two loaded snapshots of the workbooks row by row, rows are
+//! matched by their key columns
+
+use crate::cell::CellValue;
+use crate::xlsx::{ALLXLSX, XLSX};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt;
+
+#[derive(Debug, PartialEq)]
+pub enum RowChange {
+    Added(String),
+    Removed(String),
+    /// key and (field, old value, new value) of each changed cell
+    Changed(String, Vec<(String, String, String)>),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum TableDiff {
+    Added(String),
+    Removed(String),
+    Changed {
+        name: String,
+        added_fields: Vec<String>,
+        removed_fields: Vec<String>,
+        rows: Vec<RowChange>,
+    },
+}
+
+/// rows keyed by the text of their key columns, or by their 1-based row
+/// number in tables without keys
+fn keyed_rows(xlsx: &XLSX) -> Vec<(String, &Vec<CellValue>)> {
+    let keys: Vec<usize> = xlsx
+        .key_fields()
+        .iter()
+        .map(|f| f.as_index() as usize)
+        .collect();
+    xlsx.value_list()
+        .iter()
+        .enumerate()
+        .filter(|(_, row)| xlsx.is_exported_row(row))
+        .map(|(i, row)| {
+            let key = if keys.is_empty() {
+                (i + 1).to_string()
+            } else {
+                let parts: Vec<String> = keys.iter().map(|k| row[*k].to_string()).collect();
+                parts.join(",")
+            };
+            (key, row)
+        })
+        .collect()
+}
+
+/// changes from `old` to `new` of one table, None if they hold the same data
+pub fn diff_table(name: &str, old: &XLSX, new: &XLSX) -> Option<TableDiff> {
+    let names = |x: &XLSX| -> Vec<String> {
+        x.fields_list()
+            .iter()
+            .map(|f| f.get_field_name().to_string())
+            .collect()
+    };
+    let (old_fields, new_fields) = (names(old), names(new));
+    let added_fields: Vec<String> = new_fields
+        .iter()
+        .filter(|f| !old_fields.contains(f))
+        .cloned()
+        .collect();
+    let removed_fields: Vec<String> = old_fields
+        .iter()
+        .filter(|f| !new_fields.contains(f))
+        .cloned()
+        .collect();
+    // fields of both tables with their columns in the old and the new one
+    let common: Vec<(&str, usize, usize)> = new
+        .fields_list()
+        .iter()
+        .filter_map(|f| {
+            let before = old
+                .fields_list()
+                .iter()
+                .find(|o| o.get_field_name() == f.get_field_name())?;
+            Some((
+                f.get_field_name(),
+                before.as_index() as usize,
+                f.as_index() as usize,
+            ))
+        })
+        .collect();
+    let cell_text =
+        |row: &[CellValue], i: usize| row.get(i).map(|c| c.to_string()).unwrap_or_default();
+
+    let old_rows = keyed_rows(old);
+    let new_rows = keyed_rows(new);
+    // the first row of a repeated key is the one compared
+    let mut old_by_key: HashMap<&str, &Vec<CellValue>> = HashMap::new();
+    for (key, row) in &old_rows {
+        old_by_key.entry(key.as_str()).or_insert(row);
+    }
+    let new_keys: HashSet<&str> = new_rows.iter().map(|(key, _)| key.as_str()).collect();
+
+    let mut rows = vec![];
+    for (key, row) in &new_rows {
+        let Some(old_row) = old_by_key.get(key.as_str()) else {
+            rows.push(RowChange::Added(key.clone()));
+            continue;
+        };
+        let mut cells = vec![];
+        for (field, before, after) in &common {
+            let before = cell_text(old_row, *before);
+            let after = cell_text(row, *after);
+            if before != after {
+                cells.push((field.to_string(), before, after));
+            }
+        }
+        if !cells.is_empty() {
+            rows.push(RowChange::Changed(key.clone(), cells));
+        }
+    }
+    for (key, _) in &old_rows {
+        if !new_keys.contains(key.as_str()) {
+            rows.push(RowChange::Removed(key.clone()));
+        }
+    }
+
+    if added_fields.is_empty() && removed_fields.is_empty() && rows.is_empty() {
+        return None;
+    }
+    Some(TableDiff::Changed {
+        name: name.to_string(),
+        added_fields,
+        removed_fields,
+        rows,
+    })
+}
+
+/// changes from the `old` snapshot to the `new` one, by table name
+pub fn diff_all(old: &ALLXLSX, new: &ALLXLSX) -> Vec<TableDiff> {
+    let before: BTreeMap<&String, &XLSX> = old.tables().into_iter().collect();
+    let after: BTreeMap<&String, &XLSX> = new.tables().into_iter().collect();
+    let names: BTreeSet<&String> = before.keys().chain(after.keys()).copied().collect();
+    let mut diffs = vec![];
+    for name in names {
+        match (before.get(name), after.get(name)) {
+            (None, Some(_)) => diffs.push(TableDiff::Added(name.clone())),
+            (Some(_), None) => diffs.push(TableDiff::Removed(name.clone())),
+            (Some(a), Some(b)) => diffs.extend(diff_table(name, a, b)),
+            (None, None) => {}
+        }
+    }
+    diffs
+}
+
+impl fmt::Display for TableDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TableDiff::Added(name) => write!(f, "新增表 {}", name),
+            TableDiff::Removed(name) => write!(f, "删除表 {}", name),
+            TableDiff::Changed {
+                name,
+                added_fields,
+                removed_fields,
+                rows,
+            } => {
+                write!(f, "修改表 {}", name)?;
+                for field in added_fields {
+                    write!(f, "\n  新增字段 {}", field)?;
+                }
+                for field in removed_fields {
+                    write!(f, "\n  删除字段 {}", field)?;
+                }
+                for row in rows {
+                    match row {
+                        RowChange::Added(key) => write!(f, "\n  新增行 {}", key)?,
+                        RowChange::Removed(key) => write!(f, "\n  删除行 {}", key)?,
+                        RowChange::Changed(key, cells) => {
+                            write!(f, "\n  修改行 {}", key)?;
+                            for (field, before, after) in cells {
+                                write!(f, "\n    {}: {} -> {}", field, before, after)?;
+                            }
+                        }
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{diff_all, RowChange, TableDiff};
+    use crate::cell::CellValue;
+    use crate::xlsx::{XlsTabField, ALLXLSX, XLSX};
+
+    fn table(fields: &[&str], rows: &[&[i64]]) -> XLSX {
+        let mut xlsx = XLSX::new();
+        for (i, name) in fields.iter().enumerate() {
+            let mut f = XlsTabField::new();
+            f.set_field_name(name.to_string());
+            f.set_field_type(
+                if name.starts_with("KEY") {
+                    "int!key"
+                } else {
+                    "int"
+                }
+                .to_string(),
+            );
+            f.set_field_index(i);
+            xlsx.add_field(f);
+        }
+        for (i, row) in rows.iter().enumerate() {
+            xlsx.add_row(
+                row.iter().map(|v| CellValue::Int(*v)).collect(),
+                "Sheet1",
+                i + 5,
+            );
+        }
+        xlsx
+    }
+
+    #[test]
+    fn test_diff_all() {
+        let mut old = ALLXLSX::new();
+        old.add(
+            "item.xlsx",
+            table(&["KEY_id", "price"], &[&[1, 10], &[2, 20], &[3, 30]]),
+        );
+        old.add("old.xlsx", table(&["KEY_id"], &[&[1]]));
+        let mut new = ALLXLSX::new();
+        new.add(
+            "item.xlsx",
+            table(
+                &["KEY_id", "price", "weight"],
+                &[&[1, 10, 0], &[2, 25, 0], &[4, 40, 0]],
+            ),
+        );
+        new.add("new.xlsx", table(&["KEY_id"], &[&[1]]));
+        new.add("old.xlsx", table(&["KEY_id"], &[&[1]]));
+
+        let diffs = diff_all(&old, &new);
+        assert_eq!(diffs.len(), 2);
+        assert_eq!(
+            diffs[0],
+            TableDiff::Changed {
+                name: "item.xlsx".to_string(),
+                added_fields: vec!["weight".to_string()],
+                removed_fields: vec![],
+                rows: vec![
+                    RowChange::Changed(
+                        "2".to_string(),
+                        vec![("price".to_string(), "20".to_string(), "25".to_string())]
+                    ),
+                    RowChange::Added("4".to_string()),
+                    RowChange::Removed("3".to_string()),
+                ],
+            }
+        );
+        assert_eq!(diffs[1], TableDiff::Added("new.xlsx".to_string()));
+        assert!(diffs[0].to_string().contains("price: 20 -> 25"));
+        assert!(diff_all(&new, &old).contains(&TableDiff::Removed("new.xlsx".to_string())));
+    }
+}
@@ -0,0 +1,159 @@
+/*
+Copyright (c) 2024- rickhan<rick.han@yahoo.com>
+
+Permission is hereby granted, free of charge, to any person obtaining
+a copy of this software and associated documentation files (the
+"Software"), to deal in the Software without restriction, including
+without limitation the rights to use, copy, modify, merge, publish,
+distribute, sublicense, and/or sell copies of the Software, and to
+permit persons to whom the Software is furnished to do so, subject to
+the following conditions:
+
+The above copyright notice and this permission notice shall be
+included in all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+*/
+
+/// name of the sheet holding enum definitions, columns are
+/// enum name, label, value and an optional comment, the first row is the header
+pub const ENUM_SHEET_NAME: &str = "__enum__";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnumItem {
+    pub label: String,
+    pub value: i64,
+    pub comment: String,
+}
+
+/// labels allowed in an `enum<Name>` column
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnumDef {
+    pub name: String,
+    pub items: Vec<EnumItem>,
+}
+
+impl EnumDef {
+    pub fn value_of(&self, label: &str) -> Option<i64> {
+        self.items
+            .iter()
+            .find(|item| item.label == label)
+            .map(|item| item.value)
+    }
+}
+
+/// resolves `enum<Name>` labels to their numeric values
+pub trait EnumLookup {
+    fn enum_value(&self, name: &str, label: &str) -> Result<i64, String>;
+}
+
+/// accepts every label as 0, for checks that only need the shape of a value
+pub struct AnyEnum;
+
+impl EnumLookup for AnyEnum {
+    fn enum_value(&self, _name: &str, _label: &str) -> Result<i64, String> {
+        Ok(0)
+    }
+}
+
+/// labels are used as names in generated code, so they must be identifiers
+fn is_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(c) if c.is_alphabetic() || c == '_' => {}
+        _ => return false,
+    }
+    chars.all(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// parses the rows of an enum sheet, an empty name continues the enum above
+pub fn parse_enum_rows(rows: &[Vec<String>]) -> Result<Vec<EnumDef>, String> {
+    let mut enums: Vec<EnumDef> = vec![];
+    for (i, row) in rows.iter().enumerate().skip(1) {
+        let cell = |c: usize| row.get(c).map(|s| s.trim().to_string()).unwrap_or_default();
+        let (name, label, value) = (cell(0), cell(1), cell(2));
+        if label.is_empty() && value.is_empty() {
+            continue;
+        }
+
+        let name = if name.is_empty() {
+            match enums.last() {
+                Some(e) => e.name.clone(),
+                None => return Err(format!("第{}行缺少枚举名", i + 1)),
+            }
+        } else {
+            name
+        };
+        if !is_identifier(&name) || !is_identifier(&label) {
+            return Err(format!(
+                "第{}行枚举名和标签只能包含字母、数字和下划线",
+                i + 1
+            ));
+        }
+        let value = match value.parse::<i64>() {
+            Ok(v) => v,
+            Err(_) => return Err(format!("第{}行枚举值{}不是整数", i + 1, value)),
+        };
+
+        let pos = match enums.iter().position(|e| e.name == name) {
+            Some(pos) => pos,
+            None => {
+                enums.push(EnumDef {
+                    name: name.clone(),
+                    items: vec![],
+                });
+                enums.len() - 1
+            }
+        };
+        let def = &mut enums[pos];
+        if def.value_of(&label).is_some() {
+            return Err(format!("第{}行枚举{}的标签{}重复", i + 1, name, label));
+        }
+        def.items.push(EnumItem {
+            label,
+            value,
+            comment: cell(3),
+        });
+    }
+
+    Ok(enums)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_enum_rows;
+
+    fn rows(data: &[&[&str]]) -> Vec<Vec<String>> {
+        data.iter()
+            .map(|r| r.iter().map(|c| c.to_string()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_enum_rows() {
+        let enums = parse_enum_rows(&rows(&[
+            &["枚举名", "标签", "值", "说明"],
+            &["Quality", "WHITE", "1", "白色"],
+            &["", "GREEN", "2"],
+            &[],
+            &["Element", "FIRE", "1"],
+        ]))
+        .unwrap();
+        assert_eq!(enums.len(), 2);
+        assert_eq!(enums[0].value_of("GREEN"), Some(2));
+        assert_eq!(enums[0].items[0].comment, "白色");
+        assert_eq!(enums[1].value_of("FIRE"), Some(1));
+        assert_eq!(enums[1].value_of("WHITE"), None);
+
+        assert!(parse_enum_rows(&rows(&[&["h"], &["Quality", "WHITE", "x"]])).is_err());
+        assert!(parse_enum_rows(&rows(&[&["h"], &["Quality", "A B", "1"]])).is_err());
+        assert!(parse_enum_rows(&rows(&[&["h"], &["", "WHITE", "1"]])).is_err());
+        assert!(parse_enum_rows(&rows(&[&["h"], &["Q", "A", "1"], &["Q", "A", "2"]])).is_err());
+    }
+}
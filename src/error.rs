@@ -0,0 +1,495 @@
+/*
+Copyright (c) 2024- rickhan<rick.han@yahoo.com>
+
+Permission is hereby granted, free of charge, to any person obtaining
+a copy of this software and associated documentation files (the
+"Software"), to deal in the Software without restriction, including
+without limitation the rights to use, copy, modify, merge, publish,
+distribute, sublicense, and/or sell copies of the Software, and to
+permit persons to whom the Software is furnished to do so, subject to
+the following conditions:
+
+The above copyright notice and this permission notice shall be
+included in all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+*/
+use std::fmt;
+
+/// where a cell lives in the workbook
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Location {
+    pub file: String,
+    pub sheet: String,
+    /// 1-based row number as shown by excel, 0 if unknown
+    pub row: usize,
+    /// column letter as shown by excel, e.g. `A`, `AB`
+    pub column: String,
+    pub field: String,
+}
+
+#[derive(Debug)]
+pub enum Error {
+    /// the check condition row can not be parsed
+    InvalidCondition {
+        location: Box<Location>,
+        condition: String,
+    },
+    /// a value does not satisfy the check condition
+    InvalidValue {
+        location: Box<Location>,
+        value: String,
+        condition: String,
+    },
+    /// a key value does not satisfy the check condition
+    InvalidKey {
+        location: Box<Location>,
+        value: String,
+        condition: String,
+    },
+    /// two rows have the same key values
+    DuplicateKey {
+        location: Box<Location>,
+        /// the row that has the key first
+        first: Box<Location>,
+        value: String,
+    },
+    /// two rows have the same value in a `unique` column
+    DuplicateValue {
+        location: Box<Location>,
+        first: Box<Location>,
+        value: String,
+        condition: String,
+    },
+    /// the type row can not be parsed
+    InvalidType {
+        location: Box<Location>,
+        field_type: String,
+        message: String,
+    },
+    /// a value can not be parsed as the field type
+    TypeMismatch {
+        location: Box<Location>,
+        value: String,
+        field_type: String,
+        message: String,
+    },
+    /// a value referenced by `expect` is missing in the other table
+    MissingReference {
+        location: Box<Location>,
+        value: String,
+        condition: String,
+    },
+    /// the generated lua code can not be loaded
+    LuaSyntax {
+        file: String,
+        key: String,
+        code: String,
+    },
+    /// two tables export files or types of the same name
+    DuplicateOutput {
+        file: String,
+        /// the table exporting the name first
+        first: String,
+        name: String,
+    },
+    /// the workbook can not be opened or read
+    Workbook { file: String, message: String },
+    /// the header layout config is invalid
+    Layout { file: String, message: String },
+    Io {
+        path: String,
+        source: std::io::Error,
+    },
+}
+
+/// quotes and escapes `s` as a json string
+pub fn json_string(s: &str) -> String {
+    let mut ret = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => ret += "\\\"",
+            '\\' => ret += "\\\\",
+            '\n' => ret += "\\n",
+            '\r' => ret += "\\r",
+            '\t' => ret += "\\t",
+            c if (c as u32) < 0x20 => ret += &format!("\\u{:04x}", c as u32),
+            c => ret.push(c),
+        }
+    }
+    ret += "\"";
+    ret
+}
+
+impl Location {
+    fn to_text(&self) -> String {
+        let mut s = self.file.clone();
+        if !self.sheet.is_empty() {
+            s += &format!(" 工作表：{}", self.sheet);
+        }
+        if self.row > 0 {
+            s += &format!(" 第{}行", self.row);
+        }
+        if !self.column.is_empty() {
+            s += &format!(" {}列", self.column);
+        }
+        s
+    }
+
+    fn to_json(&self) -> String {
+        format!(
+            "\"file\":{},\"sheet\":{},\"row\":{},\"column\":{},\"field\":{}",
+            json_string(&self.file),
+            json_string(&self.sheet),
+            self.row,
+            json_string(&self.column),
+            json_string(&self.field)
+        )
+    }
+}
+
+impl Error {
+    pub fn io(path: &str, source: std::io::Error) -> Error {
+        Error::Io {
+            path: path.to_string(),
+            source,
+        }
+    }
+
+    /// short machine readable name of the variant
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Error::InvalidCondition { .. } => "invalid_condition",
+            Error::InvalidValue { .. } => "invalid_value",
+            Error::InvalidKey { .. } => "invalid_key",
+            Error::DuplicateKey { .. } => "duplicate_key",
+            Error::DuplicateValue { .. } => "duplicate_value",
+            Error::InvalidType { .. } => "invalid_type",
+            Error::TypeMismatch { .. } => "type_mismatch",
+            Error::MissingReference { .. } => "missing_reference",
+            Error::LuaSyntax { .. } => "lua_syntax",
+            Error::DuplicateOutput { .. } => "duplicate_output",
+            Error::Workbook { .. } => "workbook",
+            Error::Layout { .. } => "layout",
+            Error::Io { .. } => "io",
+        }
+    }
+
+    pub fn file(&self) -> &str {
+        match self {
+            Error::InvalidCondition { location, .. }
+            | Error::InvalidValue { location, .. }
+            | Error::InvalidKey { location, .. }
+            | Error::DuplicateKey { location, .. }
+            | Error::DuplicateValue { location, .. }
+            | Error::InvalidType { location, .. }
+            | Error::TypeMismatch { location, .. }
+            | Error::MissingReference { location, .. } => &location.file,
+            Error::LuaSyntax { file, .. }
+            | Error::DuplicateOutput { file, .. }
+            | Error::Workbook { file, .. }
+            | Error::Layout { file, .. } => file,
+            Error::Io { path, .. } => path,
+        }
+    }
+
+    fn title(&self) -> &'static str {
+        match self {
+            Error::InvalidCondition { .. } => "校验条件不合法",
+            Error::InvalidValue { .. } => "字段值不合要求",
+            Error::InvalidKey { .. } => "键不合法",
+            Error::DuplicateKey { .. } => "键重复",
+            Error::DuplicateValue { .. } => "字段值重复",
+            Error::InvalidType { .. } => "字段类型不合法",
+            Error::TypeMismatch { .. } => "字段值与类型不符",
+            Error::MissingReference { .. } => "引用的值不存在",
+            Error::LuaSyntax { .. } => "lua语法错误",
+            Error::DuplicateOutput { .. } => "导出名重复",
+            Error::Workbook { .. } => "无法读取文件",
+            Error::Layout { .. } => "表头布局不合法",
+            Error::Io { .. } => "读写文件失败",
+        }
+    }
+
+    /// details without the file name, one item per line
+    fn details(&self) -> Vec<String> {
+        match self {
+            Error::InvalidCondition {
+                location,
+                condition,
+            } => vec![format!("字段名：{}, 条件：{}", location.field, condition)],
+            Error::InvalidValue {
+                location,
+                value,
+                condition,
+            }
+            | Error::InvalidKey {
+                location,
+                value,
+                condition,
+            }
+            | Error::MissingReference {
+                location,
+                value,
+                condition,
+            } => vec![
+                format!("字段名：{}, 字段值：{}", location.field, value),
+                format!("字段要求：{}", condition),
+            ],
+            Error::DuplicateKey {
+                location,
+                first,
+                value,
+            } => vec![
+                format!("字段名：{}, 键值：{}", location.field, value),
+                format!("与{}重复", first.to_text()),
+            ],
+            Error::DuplicateValue {
+                location,
+                first,
+                value,
+                condition,
+            } => vec![
+                format!("字段名：{}, 字段值：{}", location.field, value),
+                format!("字段要求：{}, 与{}重复", condition, first.to_text()),
+            ],
+            Error::InvalidType {
+                location,
+                field_type,
+                message,
+            } => vec![
+                format!("字段名：{}, 类型：{}", location.field, field_type),
+                message.clone(),
+            ],
+            Error::TypeMismatch {
+                location,
+                value,
+                field_type,
+                message,
+            } => vec![
+                format!("字段名：{}, 字段值：{}", location.field, value),
+                format!("字段类型：{}, {}", field_type, message),
+            ],
+            Error::LuaSyntax { key, code, .. } => {
+                vec![format!("键值：{}", key), format!("错误行：{}", code)]
+            }
+            Error::DuplicateOutput { first, name, .. } => {
+                vec![format!("导出名：{}, 与{}重复", name, first)]
+            }
+            Error::Workbook { message, .. } | Error::Layout { message, .. } => {
+                vec![message.clone()]
+            }
+            Error::Io { source, .. } => vec![source.to_string()],
+        }
+    }
+
+    fn position(&self) -> String {
+        match self {
+            Error::InvalidCondition { location, .. }
+            | Error::InvalidValue { location, .. }
+            | Error::InvalidKey { location, .. }
+            | Error::DuplicateKey { location, .. }
+            | Error::DuplicateValue { location, .. }
+            | Error::InvalidType { location, .. }
+            | Error::TypeMismatch { location, .. }
+            | Error::MissingReference { location, .. } => location.to_text(),
+            _ => self.file().to_string(),
+        }
+    }
+
+    pub fn to_text(&self) -> String {
+        format!(
+            "{} {}: {}",
+            self.position(),
+            self.title(),
+            self.details().join(", ")
+        )
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut s = format!("**{}** {}", self.position(), self.title());
+        for d in self.details() {
+            s += "\n";
+            s += &d;
+        }
+        s
+    }
+
+    pub fn to_json(&self) -> String {
+        let mut s = format!("{{\"kind\":{}", json_string(self.kind()));
+        match self {
+            Error::InvalidCondition {
+                location,
+                condition,
+            } => {
+                s += &format!(
+                    ",{},\"condition\":{}",
+                    location.to_json(),
+                    json_string(condition)
+                );
+            }
+            Error::InvalidValue {
+                location,
+                value,
+                condition,
+            }
+            | Error::InvalidKey {
+                location,
+                value,
+                condition,
+            }
+            | Error::MissingReference {
+                location,
+                value,
+                condition,
+            } => {
+                s += &format!(
+                    ",{},\"value\":{},\"condition\":{}",
+                    location.to_json(),
+                    json_string(value),
+                    json_string(condition)
+                );
+            }
+            Error::DuplicateKey {
+                location,
+                first,
+                value,
+            } => {
+                s += &format!(
+                    ",{},\"value\":{},\"first\":{{{}}}",
+                    location.to_json(),
+                    json_string(value),
+                    first.to_json()
+                );
+            }
+            Error::DuplicateValue {
+                location,
+                first,
+                value,
+                condition,
+            } => {
+                s += &format!(
+                    ",{},\"value\":{},\"condition\":{},\"first\":{{{}}}",
+                    location.to_json(),
+                    json_string(value),
+                    json_string(condition),
+                    first.to_json()
+                );
+            }
+            Error::InvalidType {
+                location,
+                field_type,
+                message,
+            } => {
+                s += &format!(
+                    ",{},\"field_type\":{},\"message\":{}",
+                    location.to_json(),
+                    json_string(field_type),
+                    json_string(message)
+                );
+            }
+            Error::TypeMismatch {
+                location,
+                value,
+                field_type,
+                message,
+            } => {
+                s += &format!(
+                    ",{},\"value\":{},\"field_type\":{},\"message\":{}",
+                    location.to_json(),
+                    json_string(value),
+                    json_string(field_type),
+                    json_string(message)
+                );
+            }
+            Error::LuaSyntax { file, key, code } => {
+                s += &format!(
+                    ",\"file\":{},\"key\":{},\"code\":{}",
+                    json_string(file),
+                    json_string(key),
+                    json_string(code)
+                );
+            }
+            Error::DuplicateOutput { file, first, name } => {
+                s += &format!(
+                    ",\"file\":{},\"name\":{},\"first\":{}",
+                    json_string(file),
+                    json_string(name),
+                    json_string(first)
+                );
+            }
+            Error::Workbook { file, message } | Error::Layout { file, message } => {
+                s += &format!(
+                    ",\"file\":{},\"message\":{}",
+                    json_string(file),
+                    json_string(message)
+                );
+            }
+            Error::Io { path, source } => {
+                s += &format!(
+                    ",\"file\":{},\"message\":{}",
+                    json_string(path),
+                    json_string(&source.to_string())
+                );
+            }
+        }
+        s += "}";
+        s
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_text())
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{json_string, Error, Location};
+
+    #[test]
+    fn test_json_string() {
+        assert_eq!(json_string("abc"), "\"abc\"");
+        assert_eq!(json_string("a\"b'c"), "\"a\\\"b'c\"");
+        assert_eq!(json_string("a\\b\nc"), "\"a\\\\b\\nc\"");
+    }
+
+    #[test]
+    fn test_render() {
+        let e = Error::InvalidValue {
+            location: Box::new(Location {
+                file: "item.xlsx".to_string(),
+                sheet: "Sheet1".to_string(),
+                row: 7,
+                column: "C".to_string(),
+                field: "name".to_string(),
+            }),
+            value: "say \"hi\"".to_string(),
+            condition: "len(1, 4)".to_string(),
+        };
+        assert_eq!(
+            e.to_text(),
+            "item.xlsx 工作表：Sheet1 第7行 C列 字段值不合要求: 字段名：name, 字段值：say \"hi\", 字段要求：len(1, 4)"
+        );
+        assert_eq!(
+            e.to_json(),
+            "{\"kind\":\"invalid_value\",\"file\":\"item.xlsx\",\"sheet\":\"Sheet1\",\"row\":7,\"column\":\"C\",\"field\":\"name\",\"value\":\"say \\\"hi\\\"\",\"condition\":\"len(1, 4)\"}"
+        );
+    }
+}
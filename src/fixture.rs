@@ -0,0 +1,84 @@
+/*
+Copyright (c) 2024- rickhan<rick.han@yahoo.com>
+
+Permission is hereby granted, free of charge, to any person obtaining
+a copy of this software and associated documentation files (the
+"Software"), to deal in the Software without restriction, including
+without limitation the rights to use, copy, modify, merge, publish,
+distribute, sublicense, and/or sell copies of the Software, and to
+permit persons to whom the Software is furnished to do so, subject to
+the following conditions:
+
+The above copyright notice and this permission notice shall be
+included in all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+*/
+//! workbooks the unit tests write to a temp folder and parse back
+use crate::cell::CellValue;
+use crate::layout::HeaderLayout;
+use crate::template::{write_workbook, Sheet};
+use crate::xlsx::{ALLXLSX, XLSX};
+use std::fs;
+
+/// a row of text cells
+pub fn text(cells: &[&str]) -> Vec<CellValue> {
+    cells.iter().map(|c| CellValue::from(*c)).collect()
+}
+
+/// a workbook with the single sheet `Sheet1`
+pub fn sheet1(rows: Vec<Vec<CellValue>>) -> Vec<Sheet> {
+    vec![("Sheet1".to_string(), rows)]
+}
+
+/// field names in the first row, types in the second and data after them
+pub fn layout() -> HeaderLayout {
+    HeaderLayout {
+        cn_name: 0,
+        field_name: 1,
+        condition: 0,
+        client_server: 0,
+        field_type: 2,
+        data_start: 3,
+        ..HeaderLayout::default()
+    }
+}
+
+/// an empty folder called `name` under the temp dir
+pub fn temp_dir(name: &str) -> String {
+    let dir = std::env::temp_dir().join(name);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir.to_str().unwrap().to_string()
+}
+
+/// writes `books` to the temp folder `name` and parses their tables with
+/// `layout`, in the order of `books`
+pub fn parse(
+    name: &str,
+    books: &[(&str, Vec<Sheet>)],
+    layout: &HeaderLayout,
+) -> Vec<(String, XLSX)> {
+    let input = temp_dir(name);
+    let mut tables = vec![];
+    for (fname, sheets) in books {
+        write_workbook(&format!("{}/{}", input, fname), sheets).unwrap();
+        tables.extend(XLSX::parse_tables(&input, fname, layout.clone()).unwrap());
+    }
+    tables
+}
+
+/// the tables of `parse` in one `ALLXLSX`
+pub fn load(name: &str, books: &[(&str, Vec<Sheet>)], layout: &HeaderLayout) -> ALLXLSX {
+    let mut all = ALLXLSX::new();
+    for (table, xlsx) in parse(name, books, layout) {
+        all.add(&table, xlsx);
+    }
+    all
+}
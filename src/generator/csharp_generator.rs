@@ -0,0 +1,862 @@
+/*
+Copyright (c) 2024- rickhan<rick.han@yahoo.com>
+
+Permission is hereby granted, free of charge, to any person obtaining
+a copy of this software and associated documentation files (the
+"Software"), to deal in the Software without restriction, including
+without limitation the rights to use, copy, modify, merge, publish,
+distribute, sublicense, and/or sell copies of the Software, and to
+permit persons to whom the Software is furnished to do so, subject to
+the following conditions:
+
+The above copyright notice and this permission notice shall be
+included in all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+*/
+use crate::context::ExportContext;
+use crate::error::Error;
+use crate::types::FieldType;
+use crate::xlsx::{table_stem, IndexKind};
+use crate::{ALLXLSX, XLSX};
+
+use super::{pascal_case, write_file, Generator};
+
+pub struct CSharpGenerator<'a> {
+    xlsx: &'a XLSX,
+}
+
+const HEADER: &str = "// this file is generated by tools, do NOT edit this file!\n";
+
+/// file of the json reader the generated loaders share
+pub const READER_FILE_NAME: &str = "TableReader.cs";
+
+const KEYWORDS: [&str; 77] = [
+    "abstract",
+    "as",
+    "base",
+    "bool",
+    "break",
+    "byte",
+    "case",
+    "catch",
+    "char",
+    "checked",
+    "class",
+    "const",
+    "continue",
+    "decimal",
+    "default",
+    "delegate",
+    "do",
+    "double",
+    "else",
+    "enum",
+    "event",
+    "explicit",
+    "extern",
+    "false",
+    "finally",
+    "fixed",
+    "float",
+    "for",
+    "foreach",
+    "goto",
+    "if",
+    "implicit",
+    "in",
+    "int",
+    "interface",
+    "internal",
+    "is",
+    "lock",
+    "long",
+    "namespace",
+    "new",
+    "null",
+    "object",
+    "operator",
+    "out",
+    "override",
+    "params",
+    "private",
+    "protected",
+    "public",
+    "readonly",
+    "ref",
+    "return",
+    "sbyte",
+    "sealed",
+    "short",
+    "sizeof",
+    "stackalloc",
+    "static",
+    "string",
+    "struct",
+    "switch",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "typeof",
+    "uint",
+    "ulong",
+    "unchecked",
+    "unsafe",
+    "ushort",
+    "using",
+    "virtual",
+    "void",
+    "volatile",
+    "while",
+];
+
+/// `name` as a c# identifier, keywords are prefixed with `@`
+fn identifier(name: &str) -> String {
+    if KEYWORDS.contains(&name) {
+        return format!("@{}", name);
+    }
+    name.to_string()
+}
+
+fn summary(text: &str) -> String {
+    let text = text
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace(['\r', '\n'], " ");
+    format!("/// <summary>{}</summary>\n", text.trim())
+}
+
+fn indent(text: &str) -> String {
+    text.lines()
+        .map(|line| {
+            if line.is_empty() {
+                "\n".to_string()
+            } else {
+                format!("    {}\n", line)
+            }
+        })
+        .collect()
+}
+
+/// class declared by `READER_FILE_NAME`
+pub const READER_CLASS: &str = "TableReader";
+
+/// classes generated for the table, every table is in the same namespace so
+/// none of them may repeat. the lookup maps of the table class come as
+/// `ItemTable.ByName`, an index on a `key` column would repeat `ByKey`
+pub fn declared_names(fname: &str, xlsx: &XLSX, side: &str) -> Vec<String> {
+    let class_name = pascal_case(&table_stem(fname));
+    let table_name = format!("{}Table", class_name);
+    let mut names = vec![class_name, table_name.clone()];
+    let fields = xlsx.side_fields(side);
+    if fields.iter().any(|f| f.is_key_field()) {
+        names.push(format!("{}.ByKey", table_name));
+        for field in fields.iter().filter(|f| f.get_index().is_some()) {
+            names.push(format!(
+                "{}.By{}",
+                table_name,
+                pascal_case(field.get_field_name())
+            ));
+        }
+    }
+    names
+}
+
+/// header, `usings` and `body` wrapped in the namespace of `ctx`, if any
+fn source_file(ctx: &ExportContext, usings: &[&str], body: &str) -> String {
+    let mut content = String::from(HEADER);
+    for using in usings {
+        content += &format!("using {};\n", using);
+    }
+    content += "\n";
+    if ctx.options.namespace.is_empty() {
+        return content + body;
+    }
+    content
+        + &format!(
+            "namespace {}\n{{\n{}}}\n",
+            ctx.options.namespace,
+            indent(body)
+        )
+}
+
+/// a member of a generated class, `read` reads its value from `reader`
+struct Member {
+    name: String,
+    cn_name: String,
+    ty: String,
+    read: String,
+}
+
+/// a class with public fields and a `Read` method, `nested` are the sources
+/// of classes declared inside it
+fn class_source(name: &str, serializable: bool, members: &[Member], nested: &[String]) -> String {
+    let mut content = String::new();
+    if serializable {
+        content += "[Serializable]\n";
+    }
+    content += &format!("public class {}\n{{\n", name);
+    for class in nested {
+        content += &indent(class);
+        content += "\n";
+    }
+    for member in members {
+        if !member.cn_name.trim().is_empty() {
+            content += &indent(&summary(&member.cn_name));
+        }
+        let init = if member.ty == "string" {
+            " = \"\"".to_string()
+        } else if member.ty.starts_with("List<") {
+            format!(" = new {}()", member.ty)
+        } else {
+            String::new()
+        };
+        content += &format!(
+            "    public {} {}{};\n",
+            member.ty,
+            identifier(&member.name),
+            init
+        );
+    }
+    content += &format!(
+        "
+    public static {0} Read(TableReader reader)
+    {{
+        var value = new {0}();
+        reader.Expect('{{');
+        while (reader.HasNext('}}'))
+        {{
+            switch (reader.ReadName())
+            {{
+",
+        name
+    );
+    for member in members {
+        content += &format!(
+            "                case \"{}\":\n                    value.{} = {};\n                    break;\n",
+            member.name,
+            identifier(&member.name),
+            member.read
+        );
+    }
+    content += "                default:
+                    reader.Skip();
+                    break;
+            }
+        }
+        return value;
+    }
+}
+";
+    content
+}
+
+impl<'a> CSharpGenerator<'a> {
+    /// the c# type of `ty` and the expression reading it from `reader`,
+    /// classes of struct columns are named after `hint` and pushed to `nested`
+    fn type_of(
+        &self,
+        ty: &FieldType,
+        hint: &str,
+        reader: &str,
+        ctx: &ExportContext,
+        nested: &mut Vec<String>,
+    ) -> (String, String) {
+        let scalar = |t: &str, read: &str| (t.to_string(), format!("{}.{}()", reader, read));
+        match ty {
+            FieldType::Int => scalar("int", "ReadInt"),
+            FieldType::Long => scalar("long", "ReadLong"),
+            FieldType::Float => scalar("float", "ReadFloat"),
+            FieldType::String => scalar("string", "ReadString"),
+            FieldType::Bool => scalar("bool", "ReadBool"),
+            FieldType::Date | FieldType::DateTime => scalar("DateTime?", "ReadDate"),
+            FieldType::Duration => scalar("TimeSpan", "ReadDuration"),
+            // verbatim values are kept as their json text
+            FieldType::Custom(_) => scalar("string", "ReadRaw"),
+            FieldType::Enum(name) => {
+                if ctx.options.emit_enums {
+                    (name.clone(), format!("({}){}.ReadInt()", name, reader))
+                } else {
+                    scalar("int", "ReadInt")
+                }
+            }
+            FieldType::Array(inner) => {
+                let (t, read) = self.type_of(inner, hint, "reader", ctx, nested);
+                (
+                    format!("List<{}>", t),
+                    format!("{}.ReadList(() => {})", reader, read),
+                )
+            }
+            FieldType::Tuple(types) => {
+                let mut names = vec![];
+                let mut reads = vec![];
+                for (i, t) in types.iter().enumerate() {
+                    let hint = format!("{}{}", hint, i + 1);
+                    let (t, read) = self.type_of(t, &hint, "reader.Item()", ctx, nested);
+                    names.push(t);
+                    reads.push(read);
+                }
+                // c# has no literal for a tuple of one
+                if names.len() == 1 {
+                    return (
+                        format!("ValueTuple<{}>", names[0]),
+                        format!(
+                            "{}.ReadTuple(() => ValueTuple.Create({}))",
+                            reader, reads[0]
+                        ),
+                    );
+                }
+                (
+                    format!("({})", names.join(", ")),
+                    format!("{}.ReadTuple(() => ({}))", reader, reads.join(", ")),
+                )
+            }
+            FieldType::Struct(fields) => {
+                let class_name = format!("{}Data", pascal_case(hint));
+                let mut members = vec![];
+                for (name, t) in fields {
+                    let hint = format!("{}_{}", hint, name);
+                    let (ty, read) = self.type_of(t, &hint, "reader", ctx, nested);
+                    members.push(Member {
+                        name: name.clone(),
+                        cn_name: String::new(),
+                        ty,
+                        read,
+                    });
+                }
+                nested.push(class_source(&class_name, true, &members, &[]));
+                (
+                    class_name.clone(),
+                    format!("{}.Read({})", class_name, reader),
+                )
+            }
+        }
+    }
+
+    fn table_source(
+        &self,
+        class_name: &str,
+        keys: &[&Member],
+        indexes: &[(&Member, IndexKind)],
+    ) -> String {
+        let table_name = format!("{}Table", class_name);
+        let mut content = format!(
+            "public class {}\n{{\n    public readonly List<{1}> Rows = new List<{1}>();\n",
+            table_name, class_name
+        );
+
+        let key_type = match keys {
+            [key] => key.ty.clone(),
+            _ => format!(
+                "({})",
+                keys.iter()
+                    .map(|k| k.ty.clone())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+        };
+        let key_value = |prefix: &str| -> String {
+            let values: Vec<String> = keys
+                .iter()
+                .map(|k| format!("{}{}", prefix, identifier(&k.name)))
+                .collect();
+            match keys {
+                [_] => values[0].clone(),
+                _ => format!("({})", values.join(", ")),
+            }
+        };
+        if !keys.is_empty() {
+            content += &format!(
+                "    public readonly Dictionary<{0}, {1}> ByKey = new Dictionary<{0}, {1}>();\n",
+                key_type, class_name
+            );
+        }
+        for (member, kind) in indexes {
+            let value = match kind {
+                IndexKind::Unique => class_name.to_string(),
+                IndexKind::Group => format!("List<{}>", class_name),
+            };
+            content += &format!(
+                "    public readonly Dictionary<{0}, {1}> By{2} = new Dictionary<{0}, {1}>();\n",
+                member.ty,
+                value,
+                pascal_case(&member.name)
+            );
+        }
+
+        if !keys.is_empty() {
+            let params: Vec<String> = keys
+                .iter()
+                .map(|k| format!("{} {}", k.ty, identifier(&k.name)))
+                .collect();
+            content += &format!(
+                "
+    public {} Get({})
+    {{
+        return ByKey.TryGetValue({}, out var found) ? found : null;
+    }}
+",
+                class_name,
+                params.join(", "),
+                key_value("")
+            );
+        }
+
+        content += &format!(
+            "
+    public static {0} Load(string json)
+    {{
+        var table = new {0}();
+        var reader = new TableReader(json);
+        reader.Expect('[');
+        while (reader.HasNext(']'))
+        {{
+            var row = {1}.Read(reader);
+            table.Rows.Add(row);
+",
+            table_name, class_name
+        );
+        if !keys.is_empty() {
+            content += &format!("            table.ByKey[{}] = row;\n", key_value("row."));
+        }
+        for (member, kind) in indexes {
+            let name = pascal_case(&member.name);
+            let value = format!("row.{}", identifier(&member.name));
+            // empty cells are not indexed
+            let guard = if member.ty == "string" {
+                format!("!string.IsNullOrEmpty({})", value)
+            } else if member.ty.ends_with('?') {
+                format!("{} != null", value)
+            } else {
+                String::new()
+            };
+            let add = match kind {
+                IndexKind::Unique => {
+                    let guard = if guard.is_empty() {
+                        String::new()
+                    } else {
+                        format!("{} && ", guard)
+                    };
+                    format!(
+                        "if ({0}!table.By{1}.ContainsKey({2}))\n{{\n    table.By{1}[{2}] = row;\n}}\n",
+                        guard, name, value
+                    )
+                }
+                IndexKind::Group => format!(
+                    "if (!table.By{0}.TryGetValue({1}, out var by{0}))\n{{\n    by{0} = new List<{2}>();\n    table.By{0}[{1}] = by{0};\n}}\nby{0}.Add(row);\n",
+                    name, value, class_name
+                ),
+            };
+            let add = if guard.is_empty() || matches!(kind, IndexKind::Unique) {
+                add
+            } else {
+                format!("if ({})\n{{\n{}}}\n", guard, indent(&add))
+            };
+            content += &indent(&indent(&indent(&add)));
+        }
+        content += "        }
+        return table;
+    }
+}
+";
+        content
+    }
+}
+
+impl<'a> Generator<'a> for CSharpGenerator<'a> {
+    fn new(xlsx: &'a XLSX) -> Self {
+        CSharpGenerator { xlsx }
+    }
+
+    fn generate(
+        &mut self,
+        fname: &str,
+        ctx: &ExportContext,
+        _allxlsx: &ALLXLSX,
+    ) -> Result<(), Error> {
+        let class_name = pascal_case(&table_stem(fname));
+        let cs_file_name = ctx.output_path(fname, &format!("{}.cs", class_name));
+
+        let fields = self.xlsx.side_fields(&ctx.side_for("csharp"));
+        let mut nested = vec![];
+        let mut members = vec![];
+        for field in &fields {
+            let (ty, read) = match field.get_value_type() {
+                Some(t) => self.type_of(t, field.get_field_name(), "reader", ctx, &mut nested),
+                None => ("string".to_string(), "reader.ReadRaw()".to_string()),
+            };
+            members.push(Member {
+                name: field.get_field_name().to_string(),
+                cn_name: field.get_field_cn_name().to_string(),
+                ty,
+                read,
+            });
+        }
+
+        let keys: Vec<&Member> = fields
+            .iter()
+            .zip(&members)
+            .filter(|(f, _)| f.is_key_field())
+            .map(|(_, m)| m)
+            .collect();
+        let indexes: Vec<(&Member, IndexKind)> = fields
+            .iter()
+            .zip(&members)
+            .filter_map(|(f, m)| f.get_index().map(|kind| (m, kind)))
+            .collect();
+        // an index needs the key columns, which the checks make sure of
+        let indexes = if keys.is_empty() { vec![] } else { indexes };
+
+        let body = class_source(&class_name, true, &members, &nested)
+            + "\n"
+            + &self.table_source(&class_name, &keys, &indexes);
+        let content = source_file(ctx, &["System", "System.Collections.Generic"], &body);
+        write_file(&cs_file_name, &content)
+    }
+
+    fn generate_enums(&mut self, fname: &str, ctx: &ExportContext) -> Result<(), Error> {
+        let cs_file_name = ctx.output_path(
+            fname,
+            &format!("{}Enum.cs", pascal_case(&table_stem(fname))),
+        );
+        let mut body = String::new();
+        for def in self.xlsx.enum_list() {
+            if !body.is_empty() {
+                body += "\n";
+            }
+            body += &format!("public enum {}\n{{\n", def.name);
+            for item in &def.items {
+                if !item.comment.is_empty() {
+                    body += &indent(&summary(&item.comment));
+                }
+                body += &format!("    {} = {},\n", identifier(&item.label), item.value);
+            }
+            body += "}\n";
+        }
+
+        write_file(&cs_file_name, &source_file(ctx, &[], &body))
+    }
+}
+
+/// writes the json reader used by the `Load` methods of the generated tables
+pub fn write_reader(ctx: &ExportContext) -> Result<(), Error> {
+    let path = format!("{}/{}", ctx.output, READER_FILE_NAME);
+    let usings = [
+        "System",
+        "System.Collections.Generic",
+        "System.Globalization",
+        "System.Text",
+    ];
+    write_file(&path, &source_file(ctx, &usings, READER_SOURCE))
+}
+
+const READER_SOURCE: &str = r#"/// <summary>reads the json tables exported by xlsxto</summary>
+public sealed class TableReader
+{
+    private readonly string text;
+    private int pos;
+
+    public TableReader(string text)
+    {
+        this.text = text;
+    }
+
+    public void Expect(char c)
+    {
+        SkipSpace();
+        if (pos >= text.Length || text[pos] != c)
+        {
+            throw Error("expected '" + c + "'");
+        }
+        pos++;
+    }
+
+    /// <summary>true when another element follows, false after reading `close`</summary>
+    public bool HasNext(char close)
+    {
+        SkipSpace();
+        if (pos < text.Length && text[pos] == close)
+        {
+            pos++;
+            return false;
+        }
+        if (pos < text.Length && text[pos] == ',')
+        {
+            pos++;
+        }
+        return true;
+    }
+
+    /// <summary>moves to the next element of a tuple</summary>
+    public TableReader Item()
+    {
+        SkipSpace();
+        if (pos < text.Length && text[pos] == ',')
+        {
+            pos++;
+        }
+        return this;
+    }
+
+    public string ReadName()
+    {
+        var name = ReadString();
+        Expect(':');
+        return name;
+    }
+
+    public int ReadInt()
+    {
+        return int.Parse(ReadNumber(), NumberStyles.Float, CultureInfo.InvariantCulture);
+    }
+
+    public long ReadLong()
+    {
+        return long.Parse(ReadNumber(), NumberStyles.Float, CultureInfo.InvariantCulture);
+    }
+
+    public float ReadFloat()
+    {
+        return float.Parse(ReadNumber(), NumberStyles.Float, CultureInfo.InvariantCulture);
+    }
+
+    public bool ReadBool()
+    {
+        if (Match("true"))
+        {
+            return true;
+        }
+        if (Match("false"))
+        {
+            return false;
+        }
+        throw Error("expected a bool");
+    }
+
+    public string ReadString()
+    {
+        Expect('"');
+        var sb = new StringBuilder();
+        while (pos < text.Length)
+        {
+            char c = text[pos++];
+            if (c == '"')
+            {
+                return sb.ToString();
+            }
+            if (c != '\\')
+            {
+                sb.Append(c);
+                continue;
+            }
+            if (pos >= text.Length)
+            {
+                break;
+            }
+            c = text[pos++];
+            switch (c)
+            {
+                case 'b': sb.Append('\b'); break;
+                case 'f': sb.Append('\f'); break;
+                case 'n': sb.Append('\n'); break;
+                case 'r': sb.Append('\r'); break;
+                case 't': sb.Append('\t'); break;
+                case 'u':
+                    if (pos + 4 > text.Length)
+                    {
+                        throw Error("bad escape");
+                    }
+                    sb.Append((char)Convert.ToInt32(text.Substring(pos, 4), 16));
+                    pos += 4;
+                    break;
+                default: sb.Append(c); break;
+            }
+        }
+        throw Error("unterminated string");
+    }
+
+    /// <summary>dates and datetimes, null for empty cells</summary>
+    public DateTime? ReadDate()
+    {
+        if (Match("null"))
+        {
+            return null;
+        }
+        return DateTime.Parse(ReadString(), CultureInfo.InvariantCulture, DateTimeStyles.None);
+    }
+
+    public TimeSpan ReadDuration()
+    {
+        return TimeSpan.FromSeconds(ReadLong());
+    }
+
+    /// <summary>the json text of the next value, null for null</summary>
+    public string ReadRaw()
+    {
+        if (Match("null"))
+        {
+            return null;
+        }
+        int start = pos;
+        Skip();
+        return text.Substring(start, pos - start);
+    }
+
+    public List<T> ReadList<T>(Func<T> item)
+    {
+        var list = new List<T>();
+        Expect('[');
+        while (HasNext(']'))
+        {
+            list.Add(item());
+        }
+        return list;
+    }
+
+    /// <summary>`items` reads the elements with `Item()`, extra elements are skipped</summary>
+    public T ReadTuple<T>(Func<T> items)
+    {
+        Expect('[');
+        var value = items();
+        while (HasNext(']'))
+        {
+            Skip();
+        }
+        return value;
+    }
+
+    public void Skip()
+    {
+        SkipSpace();
+        if (pos >= text.Length)
+        {
+            throw Error("unexpected end");
+        }
+        char c = text[pos];
+        if (c == '"')
+        {
+            ReadString();
+        }
+        else if (c == '[' || c == '{')
+        {
+            char close = c == '[' ? ']' : '}';
+            pos++;
+            while (HasNext(close))
+            {
+                if (close == '}')
+                {
+                    ReadName();
+                }
+                Skip();
+            }
+        }
+        else
+        {
+            while (pos < text.Length && ",]}".IndexOf(text[pos]) < 0 && !char.IsWhiteSpace(text[pos]))
+            {
+                pos++;
+            }
+        }
+    }
+
+    private string ReadNumber()
+    {
+        SkipSpace();
+        int start = pos;
+        while (pos < text.Length && "+-.0123456789eE".IndexOf(text[pos]) >= 0)
+        {
+            pos++;
+        }
+        if (start == pos)
+        {
+            throw Error("expected a number");
+        }
+        return text.Substring(start, pos - start);
+    }
+
+    private bool Match(string word)
+    {
+        SkipSpace();
+        if (string.CompareOrdinal(text, pos, word, 0, word.Length) != 0)
+        {
+            return false;
+        }
+        pos += word.Length;
+        return true;
+    }
+
+    private void SkipSpace()
+    {
+        while (pos < text.Length && char.IsWhiteSpace(text[pos]))
+        {
+            pos++;
+        }
+    }
+
+    private FormatException Error(string message)
+    {
+        return new FormatException(message + " at " + pos);
+    }
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::CSharpGenerator;
+    use crate::context::ExportContext;
+    use crate::fixture::{self, sheet1, text};
+    use std::fs;
+
+    #[test]
+    fn test_csharp_output() {
+        let rows = vec![
+            text(&["type", "id", "name", "tags", "info"]),
+            text(&[
+                "int!key",
+                "int!key",
+                "string!index",
+                "int[]",
+                "{count:int,tag:string}",
+            ]),
+            text(&["1", "1", "a", "[1,2]", "{1,x}"]),
+        ];
+        let all = fixture::load(
+            "xlsxto_csharp",
+            &[("item.xlsx", sheet1(rows))],
+            &fixture::layout(),
+        );
+        let out = fixture::temp_dir("xlsxto_csharp_out");
+        let mut ctx = ExportContext::new(&out);
+        ctx.options.namespace = "Game.Config".to_string();
+        all.gen_table::<CSharpGenerator>("item.xlsx", &ctx).unwrap();
+
+        let cs = fs::read_to_string(format!("{}/Item.cs", out)).unwrap();
+        for expected in [
+            "namespace Game.Config\n{",
+            "    [Serializable]\n    public class Item\n    {",
+            "        [Serializable]\n        public class InfoData\n",
+            "
+        public int type;
+        public int id;
+        public string name = \"\";
+        public List<int> tags = new List<int>();
+        public InfoData info;
+",
+            "    public class ItemTable\n    {",
+            "        public readonly Dictionary<(int, int), Item> ByKey = new Dictionary<(int, int), Item>();",
+            "        public readonly Dictionary<string, Item> ByName = new Dictionary<string, Item>();",
+            "        public Item Get(int type, int id)",
+            "                table.ByKey[(row.type, row.id)] = row;",
+        ] {
+            assert!(cs.contains(expected), "{} not in\n{}", expected, cs);
+        }
+    }
+}
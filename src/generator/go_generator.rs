@@ -0,0 +1,701 @@
+/*
+Copyright (c) 2024- rickhan<rick.han@yahoo.com>
+
+Permission is hereby granted, free of charge, to any person obtaining
+a copy of this software and associated documentation files (the
+"Software"), to deal in the Software without restriction, including
+without limitation the rights to use, copy, modify, merge, publish,
+distribute, sublicense, and/or sell copies of the Software, and to
+permit persons to whom the Software is furnished to do so, subject to
+the following conditions:
+
+The above copyright notice and this permission notice shall be
+included in all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+*/
+use crate::context::ExportContext;
+use crate::error::Error;
+use crate::types::FieldType;
+use crate::xlsx::{table_stem, IndexKind};
+use crate::{ALLXLSX, XLSX};
+use std::path::Path;
+
+use super::{pascal_case, write_file, Generator};
+
+pub struct GoGenerator<'a> {
+    xlsx: &'a XLSX,
+}
+
+const HEADER: &str = "// this file is generated by tools, do NOT edit this file!\n";
+
+/// file of the helpers the generated loaders share
+pub const RUNTIME_FILE_NAME: &str = "xlsxto.go";
+
+const DEFAULT_PACKAGE: &str = "config";
+
+fn package(ctx: &ExportContext) -> &str {
+    if ctx.options.package.is_empty() {
+        return DEFAULT_PACKAGE;
+    }
+    &ctx.options.package
+}
+
+/// exported go name of a column, names not starting with an upper case
+/// letter get an `X` in front
+fn go_name(name: &str) -> String {
+    let name = pascal_case(name);
+    match name.chars().next() {
+        Some(c) if c.is_uppercase() => name,
+        _ => format!("X{}", name.trim_start_matches('_')),
+    }
+}
+
+/// go files of one package live in one folder, so `battle/skill.xlsx`
+/// becomes `battle_skill_{suffix}.go` in the output folder
+fn go_file_name(fname: &str, suffix: &str) -> String {
+    let mut name = String::new();
+    if let Some(dir) = Path::new(fname).parent().and_then(|p| p.to_str()) {
+        if !dir.is_empty() {
+            name = dir.replace(['/', '\\'], "_") + "_";
+        }
+    }
+    format!("{}{}_{}.go", name, table_stem(fname), suffix)
+}
+
+fn go_file(ctx: &ExportContext, fname: &str, suffix: &str) -> String {
+    format!("{}/{}", ctx.output, go_file_name(fname, suffix))
+}
+
+/// names `RUNTIME_FILE_NAME` declares that a table could declare too
+pub const RUNTIME_NAMES: [&str; 3] = ["Time", "Duration", "Load"];
+
+/// files and package level names generated for the table, every table is in
+/// the same package so none of them may repeat. fields of the row and table
+/// structs come as `Item.ItemId` and `ItemTable.ByName`, since `item_id` and
+/// `itemId` are both `ItemId` and an index on `key` would repeat `ByKey`
+pub fn declared_names(fname: &str, xlsx: &XLSX, side: &str) -> Vec<String> {
+    let type_name = go_name(&table_stem(fname));
+    let mut names = vec![
+        type_name.clone(),
+        go_file_name(fname, "table"),
+        format!("{}Table", type_name),
+        format!("T{}", type_name),
+        format!("Load{}", type_name),
+    ];
+    if xlsx.key_num() > 1 {
+        names.push(format!("{}Key", type_name));
+    }
+    for field in xlsx.fields_list() {
+        if let Some(FieldType::Tuple(_) | FieldType::Struct(_)) = field.get_value_type() {
+            names.push(pascal_case(&format!(
+                "{}_{}",
+                type_name,
+                field.get_field_name()
+            )));
+        }
+    }
+    let fields = xlsx.side_fields(side);
+    for field in &fields {
+        names.push(format!("{}.{}", type_name, go_name(field.get_field_name())));
+    }
+    if fields.iter().any(|f| f.is_key_field()) {
+        names.push(format!("{}Table.ByKey", type_name));
+        for field in fields.iter().filter(|f| f.get_index().is_some()) {
+            names.push(format!(
+                "{}Table.By{}",
+                type_name,
+                go_name(field.get_field_name())
+            ));
+        }
+    }
+    names
+}
+
+fn source_file(ctx: &ExportContext, imports: &[&str], body: &str) -> String {
+    let mut content = format!("{}\npackage {}\n\n", HEADER, package(ctx));
+    match imports {
+        [] => {}
+        [one] => content += &format!("import \"{}\"\n\n", one),
+        _ => {
+            content += "import (\n";
+            for import in imports {
+                content += &format!("\t\"{}\"\n", import);
+            }
+            content += ")\n\n";
+        }
+    }
+    content + body
+}
+
+fn comment(text: &str, tabs: &str) -> String {
+    text.lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+        .map(|line| format!("{}// {}\n", tabs, line))
+        .collect()
+}
+
+fn width(s: &str) -> usize {
+    s.chars().count()
+}
+
+/// a go type and whether it can be a map key
+struct GoType {
+    name: String,
+    comparable: bool,
+}
+
+struct GoField {
+    name: String,
+    ty: String,
+    tag: String,
+    comment: String,
+}
+
+/// a struct with its columns aligned the way gofmt does
+fn struct_source(name: &str, fields: &[GoField]) -> String {
+    let name_width = fields.iter().map(|f| width(&f.name)).max().unwrap_or(0);
+    let ty_width = fields.iter().map(|f| width(&f.ty)).max().unwrap_or(0);
+    let mut content = format!("type {} struct {{\n", name);
+    for field in fields {
+        content += &comment(&field.comment, "\t");
+        let mut line = format!(
+            "\t{}{} {}",
+            field.name,
+            " ".repeat(name_width - width(&field.name)),
+            field.ty
+        );
+        if !field.tag.is_empty() {
+            line += &" ".repeat(ty_width - width(&field.ty) + 1);
+            line += &field.tag;
+        }
+        content += &line;
+        content += "\n";
+    }
+    content += "}\n";
+    content
+}
+
+/// the key type and the key of `value` when `ty` can be used in a map
+fn map_key(ty: &GoType, value: &str) -> Option<(String, String)> {
+    if ty.name == "json.RawMessage" {
+        return Some(("string".to_string(), format!("string({})", value)));
+    }
+    if ty.comparable {
+        return Some((ty.name.clone(), value.to_string()));
+    }
+    None
+}
+
+impl<'a> GoGenerator<'a> {
+    /// the go type of `ty`, types for tuples and structs are named after
+    /// `hint` and their declarations pushed to `decls`
+    fn type_of(
+        &self,
+        ty: &FieldType,
+        hint: &str,
+        ctx: &ExportContext,
+        decls: &mut Vec<String>,
+    ) -> GoType {
+        let scalar = |name: &str| GoType {
+            name: name.to_string(),
+            comparable: true,
+        };
+        match ty {
+            FieldType::Int => scalar("int32"),
+            FieldType::Long => scalar("int64"),
+            FieldType::Float => scalar("float64"),
+            FieldType::String => scalar("string"),
+            FieldType::Bool => scalar("bool"),
+            FieldType::Date | FieldType::DateTime => scalar("Time"),
+            FieldType::Duration => scalar("Duration"),
+            // verbatim values are kept as their json text
+            FieldType::Custom(_) => GoType {
+                name: "json.RawMessage".to_string(),
+                comparable: false,
+            },
+            FieldType::Enum(name) => {
+                if ctx.options.emit_enums {
+                    scalar(name)
+                } else {
+                    scalar("int32")
+                }
+            }
+            FieldType::Array(inner) => GoType {
+                name: format!("[]{}", self.type_of(inner, hint, ctx, decls).name),
+                comparable: false,
+            },
+            FieldType::Tuple(types) => {
+                let name = pascal_case(hint);
+                let mut fields = vec![];
+                let mut comparable = true;
+                for (i, t) in types.iter().enumerate() {
+                    let t = self.type_of(t, &format!("{}_{}", hint, i + 1), ctx, decls);
+                    comparable &= t.comparable;
+                    fields.push(GoField {
+                        name: format!("V{}", i + 1),
+                        ty: t.name,
+                        tag: String::new(),
+                        comment: String::new(),
+                    });
+                }
+                let items: Vec<String> = fields.iter().map(|f| format!("&t.{}", f.name)).collect();
+                decls.push(format!(
+                    "{}\n// UnmarshalJSON reads the tuple from a json array\nfunc (t *{}) UnmarshalJSON(data []byte) error {{\n\treturn unmarshalTuple(data, {})\n}}\n",
+                    struct_source(&name, &fields),
+                    name,
+                    items.join(", ")
+                ));
+                GoType { name, comparable }
+            }
+            FieldType::Struct(struct_fields) => {
+                let name = pascal_case(hint);
+                let mut fields = vec![];
+                let mut comparable = true;
+                for (field_name, t) in struct_fields {
+                    let t = self.type_of(t, &format!("{}_{}", hint, field_name), ctx, decls);
+                    comparable &= t.comparable;
+                    fields.push(GoField {
+                        name: go_name(field_name),
+                        ty: t.name,
+                        tag: format!("`json:\"{}\"`", field_name),
+                        comment: String::new(),
+                    });
+                }
+                decls.push(struct_source(&name, &fields));
+                GoType { name, comparable }
+            }
+        }
+    }
+}
+
+impl<'a> Generator<'a> for GoGenerator<'a> {
+    fn new(xlsx: &'a XLSX) -> Self {
+        GoGenerator { xlsx }
+    }
+
+    fn generate(
+        &mut self,
+        fname: &str,
+        ctx: &ExportContext,
+        _allxlsx: &ALLXLSX,
+    ) -> Result<(), Error> {
+        let stem = table_stem(fname);
+        let type_name = go_name(&stem);
+        let table_name = format!("{}Table", type_name);
+        let go_file_name = go_file(ctx, fname, "table");
+        // the json target writes the rows to this path under the output folder
+        let json_file = ctx.output_path(fname, &format!("{}.json", stem));
+        let json_file = json_file
+            .strip_prefix(&format!("{}/", ctx.output))
+            .unwrap_or(&json_file)
+            .to_string();
+
+        let fields = self.xlsx.side_fields(&ctx.side_for("go"));
+        let mut decls = vec![];
+        let mut columns = vec![];
+        for field in &fields {
+            let hint = format!("{}_{}", type_name, field.get_field_name());
+            let ty = match field.get_value_type() {
+                Some(t) => self.type_of(t, &hint, ctx, &mut decls),
+                None => GoType {
+                    name: "json.RawMessage".to_string(),
+                    comparable: false,
+                },
+            };
+            columns.push((*field, go_name(field.get_field_name()), ty));
+        }
+
+        let mut body = String::new();
+        for decl in &decls {
+            body += decl;
+            body += "\n";
+        }
+        let row_fields: Vec<GoField> = columns
+            .iter()
+            .map(|(field, name, ty)| GoField {
+                name: name.clone(),
+                ty: ty.name.clone(),
+                tag: format!("`json:\"{}\"`", field.get_field_name()),
+                comment: field.get_field_cn_name().to_string(),
+            })
+            .collect();
+        body += &format!("// {} is a row of {}\n", type_name, json_file);
+        body += &struct_source(&type_name, &row_fields);
+
+        // keys of a type go maps can not use leave the table without ByKey
+        let keys: Option<Vec<(String, String, String)>> = columns
+            .iter()
+            .filter(|(field, _, _)| field.is_key_field())
+            .map(|(_, name, ty)| {
+                map_key(ty, &format!("row.{}", name)).map(|(t, v)| (name.clone(), t, v))
+            })
+            .collect();
+        let keys = keys.unwrap_or_default();
+        let key_type = match keys.as_slice() {
+            [] => String::new(),
+            [(_, ty, _)] => ty.clone(),
+            _ => {
+                let key_name = format!("{}Key", type_name);
+                let key_fields: Vec<GoField> = keys
+                    .iter()
+                    .map(|(name, ty, _)| GoField {
+                        name: name.clone(),
+                        ty: ty.clone(),
+                        tag: String::new(),
+                        comment: String::new(),
+                    })
+                    .collect();
+                body += &format!("\n// {} is the primary key of {}\n", key_name, type_name);
+                body += &struct_source(&key_name, &key_fields);
+                key_name
+            }
+        };
+        let key_value = match keys.as_slice() {
+            [] => String::new(),
+            [(_, _, value)] => value.clone(),
+            _ => {
+                let values: Vec<&str> = keys.iter().map(|(_, _, v)| v.as_str()).collect();
+                format!("{}{{{}}}", key_type, values.join(", "))
+            }
+        };
+
+        // (map field, key type, key of the row, kind)
+        let mut maps: Vec<(String, String, String, Option<IndexKind>)> = vec![];
+        if !keys.is_empty() {
+            maps.push(("ByKey".to_string(), key_type.clone(), key_value, None));
+            for (field, name, ty) in &columns {
+                if let Some(kind) = field.get_index() {
+                    if let Some((t, v)) = map_key(ty, &format!("row.{}", name)) {
+                        maps.push((format!("By{}", name), t, v, Some(kind)));
+                    }
+                }
+            }
+        }
+        let map_type = |key: &str, kind: &Option<IndexKind>| match kind {
+            Some(IndexKind::Group) => format!("map[{}][]*{}", key, type_name),
+            _ => format!("map[{}]*{}", key, type_name),
+        };
+
+        let mut table_fields = vec![GoField {
+            name: "Rows".to_string(),
+            ty: format!("[]*{}", type_name),
+            tag: String::new(),
+            comment: String::new(),
+        }];
+        for (name, key, _, kind) in &maps {
+            table_fields.push(GoField {
+                name: name.clone(),
+                ty: map_type(key, kind),
+                tag: String::new(),
+                comment: String::new(),
+            });
+        }
+        body += &format!("\n// {} holds the rows of {}\n", table_name, json_file);
+        body += &struct_source(&table_name, &table_fields);
+
+        let var_name = format!("T{}", type_name);
+        body += &format!(
+            "
+// {0} holds {1} after Load
+var {0} = &{2}{{}}
+
+func init() {{
+\tregister(\"{1}\", func(dir string) error {{
+\t\tt, err := Load{3}(dir)
+\t\tif err == nil {{
+\t\t\t{0} = t
+\t\t}}
+\t\treturn err
+\t}})
+}}
+",
+            var_name, json_file, table_name, type_name
+        );
+
+        if !keys.is_empty() {
+            body += &format!(
+                "
+// Get returns the row with the key, nil if there is none
+func (t *{}) Get(key {}) *{} {{
+\treturn t.ByKey[key]
+}}
+",
+                table_name, key_type, type_name
+            );
+        }
+
+        body += &format!(
+            "
+// Load{0} reads {1} from dir
+func Load{0}(dir string) (*{2}, error) {{
+",
+            type_name, json_file, table_name
+        );
+        if maps.is_empty() {
+            body += &format!("\tt := &{}{{}}\n", table_name);
+        } else {
+            let key_width = maps.iter().map(|(name, ..)| width(name)).max().unwrap_or(0);
+            body += &format!("\tt := &{}{{\n", table_name);
+            for (name, key, _, kind) in &maps {
+                body += &format!(
+                    "\t\t{}:{} {}{{}},\n",
+                    name,
+                    " ".repeat(key_width - width(name)),
+                    map_type(key, kind)
+                );
+            }
+            body += "\t}\n";
+        }
+        body += &format!(
+            "\tif err := loadJSON(dir, \"{}\", &t.Rows); err != nil {{\n\t\treturn nil, err\n\t}}\n",
+            json_file
+        );
+        if !maps.is_empty() {
+            body += "\tfor _, row := range t.Rows {\n";
+            for (name, key, value, kind) in &maps {
+                // empty cells are not indexed
+                let guard = if kind.is_none() {
+                    String::new()
+                } else if key == "string" {
+                    format!("{} != \"\"", value)
+                } else if key == "Time" {
+                    format!("!{}.IsZero()", value)
+                } else {
+                    String::new()
+                };
+                match kind {
+                    Some(IndexKind::Unique) => {
+                        let guard = if guard.is_empty() {
+                            String::new()
+                        } else {
+                            format!(" && {}", guard)
+                        };
+                        body += &format!(
+                            "\t\tif _, ok := t.{0}[{1}]; !ok{2} {{\n\t\t\tt.{0}[{1}] = row\n\t\t}}\n",
+                            name, value, guard
+                        );
+                    }
+                    Some(IndexKind::Group) => {
+                        let add = format!("t.{0}[{1}] = append(t.{0}[{1}], row)\n", name, value);
+                        if guard.is_empty() {
+                            body += &format!("\t\t{}", add);
+                        } else {
+                            body += &format!("\t\tif {} {{\n\t\t\t{}\t\t}}\n", guard, add);
+                        }
+                    }
+                    None => body += &format!("\t\tt.{}[{}] = row\n", name, value),
+                }
+            }
+            body += "\t}\n";
+        }
+        body += "\treturn t, nil\n}\n";
+
+        let imports: &[&str] = if body.contains("json.RawMessage") {
+            &["encoding/json"]
+        } else {
+            &[]
+        };
+        write_file(&go_file_name, &source_file(ctx, imports, &body))
+    }
+
+    fn generate_enums(&mut self, fname: &str, ctx: &ExportContext) -> Result<(), Error> {
+        let go_file_name = go_file(ctx, fname, "enum");
+        let mut body = String::new();
+        for def in self.xlsx.enum_list() {
+            if !body.is_empty() {
+                body += "\n";
+            }
+            let names: Vec<String> = def
+                .items
+                .iter()
+                .map(|item| format!("{}_{}", def.name, item.label))
+                .collect();
+            let name_width = names.iter().map(|n| width(n)).max().unwrap_or(0);
+            body += &format!("type {} int32\n\nconst (\n", def.name);
+            for (item, name) in def.items.iter().zip(&names) {
+                body += &comment(&item.comment, "\t");
+                body += &format!(
+                    "\t{}{} {} = {}\n",
+                    name,
+                    " ".repeat(name_width - width(name)),
+                    def.name,
+                    item.value
+                );
+            }
+            body += ")\n";
+        }
+
+        write_file(&go_file_name, &source_file(ctx, &[], &body))
+    }
+}
+
+/// writes the helpers used by the `Load` functions of the generated tables
+pub fn write_runtime(ctx: &ExportContext) -> Result<(), Error> {
+    let path = format!("{}/{}", ctx.output, RUNTIME_FILE_NAME);
+    let imports = ["encoding/json", "fmt", "os", "path/filepath", "time"];
+    write_file(&path, &source_file(ctx, &imports, RUNTIME_SOURCE))
+}
+
+const RUNTIME_SOURCE: &str = r#"// Time is a date or datetime column, empty cells are the zero time
+type Time struct {
+	time.Time
+}
+
+// UnmarshalJSON reads the times as UTC, the way they were exported
+func (t *Time) UnmarshalJSON(data []byte) error {
+	if string(data) == "null" {
+		t.Time = time.Time{}
+		return nil
+	}
+	var s string
+	if err := json.Unmarshal(data, &s); err != nil {
+		return err
+	}
+	layout := "2006-01-02T15:04:05"
+	if len(s) == len("2006-01-02") {
+		layout = "2006-01-02"
+	}
+	v, err := time.Parse(layout, s)
+	if err != nil {
+		return err
+	}
+	t.Time = v
+	return nil
+}
+
+// Duration is a duration column, exported as seconds
+type Duration struct {
+	time.Duration
+}
+
+// UnmarshalJSON reads the seconds
+func (d *Duration) UnmarshalJSON(data []byte) error {
+	var secs int64
+	if err := json.Unmarshal(data, &secs); err != nil {
+		return err
+	}
+	d.Duration = time.Duration(secs) * time.Second
+	return nil
+}
+
+// unmarshalTuple reads a json array into the elements of a tuple
+func unmarshalTuple(data []byte, items ...interface{}) error {
+	var values []json.RawMessage
+	if err := json.Unmarshal(data, &values); err != nil {
+		return err
+	}
+	for i, item := range items {
+		if i >= len(values) {
+			break
+		}
+		if err := json.Unmarshal(values[i], item); err != nil {
+			return err
+		}
+	}
+	return nil
+}
+
+type loader struct {
+	file string
+	load func(dir string) error
+}
+
+var loaders []loader
+
+func register(file string, load func(dir string) error) {
+	loaders = append(loaders, loader{file, load})
+}
+
+func loadJSON(dir, file string, rows interface{}) error {
+	data, err := os.ReadFile(filepath.Join(dir, filepath.FromSlash(file)))
+	if err != nil {
+		return err
+	}
+	return json.Unmarshal(data, rows)
+}
+
+// Load reads every table from the json files in dir, a table that fails to
+// load keeps its old rows
+func Load(dir string) error {
+	for _, l := range loaders {
+		if err := l.load(dir); err != nil {
+			return fmt.Errorf("%s: %w", l.file, err)
+		}
+	}
+	return nil
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::{write_runtime, GoGenerator};
+    use crate::context::ExportContext;
+    use crate::fixture::{self, sheet1, text};
+    use std::fs;
+
+    #[test]
+    fn test_go_output() {
+        let rows = vec![
+            text(&["type", "id", "name", "tags", "info"]),
+            text(&[
+                "int!key",
+                "int!key",
+                "string!index",
+                "int[]",
+                "{count:int,tag:string}",
+            ]),
+            text(&["1", "1", "a", "[1,2]", "{1,x}"]),
+        ];
+        let all = fixture::load(
+            "xlsxto_go",
+            &[("item.xlsx", sheet1(rows))],
+            &fixture::layout(),
+        );
+        let out = fixture::temp_dir("xlsxto_go_out");
+        let mut ctx = ExportContext::new(&out);
+        ctx.options.package = "game".to_string();
+        all.gen_table::<GoGenerator>("item.xlsx", &ctx).unwrap();
+        write_runtime(&ctx).unwrap();
+
+        let go = fs::read_to_string(format!("{}/item_table.go", out)).unwrap();
+        for expected in [
+            "\npackage game\n",
+            "type ItemInfo struct {
+	Count int32  `json:\"count\"`
+	Tag   string `json:\"tag\"`
+}
+",
+            "type Item struct {
+	Type int32    `json:\"type\"`
+	Id   int32    `json:\"id\"`
+	Name string   `json:\"name\"`
+	Tags []int32  `json:\"tags\"`
+	Info ItemInfo `json:\"info\"`
+}
+",
+            "type ItemKey struct {
+	Type int32
+	Id   int32
+}
+",
+            "	ByKey  map[ItemKey]*Item\n	ByName map[string]*Item\n",
+            "func (t *ItemTable) Get(key ItemKey) *Item {",
+            "		t.ByKey[ItemKey{row.Type, row.Id}] = row\n",
+            "func LoadItem(dir string) (*ItemTable, error) {",
+        ] {
+            assert!(go.contains(expected), "{} not in\n{}", expected, go);
+        }
+
+        let runtime = fs::read_to_string(format!("{}/xlsxto.go", out)).unwrap();
+        assert!(runtime.contains("\npackage game\n"));
+        assert!(runtime.contains("func Load(dir string) error {"));
+    }
+}
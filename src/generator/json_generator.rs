@@ -20,86 +20,23 @@ LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
 OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
 WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
 */
-use super::Generator;
+use super::{index_value, write_file, Generator};
+use crate::context::ExportContext;
+use crate::error::{json_string, Error};
+use crate::types::Value;
+use crate::xlsx::table_stem;
 use crate::{ALLXLSX, XLSX};
-use std::fs;
-use std::io::prelude::*;
 
 pub struct JsonGenerator<'a> {
     xlsx: &'a XLSX,
 }
 
-fn replace_json_br(s: &str) -> String {
-    return s
-        .replace("\r", "")
-        .replace("\\n", "\n")
-        .replace("\n", "\\n");
-}
-
-impl<'a> JsonGenerator<'a> {
-    fn type_default_value(&self, t: &String, v: &String) -> String {
-        let trimd_str = v.trim();
-        if !trimd_str.is_empty() {
-            return v.clone();
-        }
-
-        match t.as_str() {
-            "int" | "float" | "long" => "0".to_string(),
-            "string" => "\"\"".to_string(),
-
-            _ => "nil".to_string(),
-        }
-    }
-
-    pub fn normalize_values(
-        &self,
-        field_type: &String,
-        value: &String,
-    ) -> Result<String, Box<dyn std::error::Error>> {
-        let mut ret_value = String::from("");
-        if field_type.contains("string") {
-            if field_type.contains("[]") {
-                ret_value += "[";
-                let mut prefix = "";
-                let s = value.replace("[", "").replace("]", "");
-                let content: Vec<&str> = s.split(",").collect();
-                for c in content {
-                    ret_value += prefix;
-                    if c.contains("\"") {
-                        ret_value += &replace_json_br(c);
-                    } else {
-                        ret_value += "\"";
-                        ret_value += &replace_json_br(c);
-                        ret_value += "\"";
-                    }
-                    prefix = ",";
-                }
-                ret_value += "]";
-            } else {
-                if ret_value.contains("\"") {
-                    ret_value = replace_json_br(value.as_str());
-                } else {
-                    ret_value += "\"";
-                    ret_value += &replace_json_br(value.as_str());
-                    ret_value += "\"";
-                }
-            }
-        } else if field_type.contains("[]") {
-            ret_value += "[";
-            let mut prefix = "";
-            let s = value.replace("[", "").replace("]", "");
-            let content: Vec<&str> = s.split(",").collect();
-            for c in content {
-                ret_value += prefix;
-                ret_value += c;
-                prefix = ",";
-            }
-            ret_value += "]";
-        } else {
-            ret_value = self.type_default_value(field_type, value);
-        }
-
-        Ok(ret_value)
+/// keys of json objects are strings, a value that is not one is written as
+/// the text of its json
+fn normalize_key(v: &Value) -> String {
+    match v {
+        Value::Str(_) | Value::Date(_) | Value::DateTime(_) => v.to_json(),
+        _ => json_string(&v.to_json()),
     }
 }
 
@@ -111,25 +48,25 @@ impl<'a> Generator<'a> for JsonGenerator<'a> {
     fn generate(
         &mut self,
         fname: &str,
-        out_path: &String,
-        _allxlsx: &ALLXLSX,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        let json_file_name = format!("{}/{}.json", out_path, fname[..fname.len() - 5].to_string());
+        ctx: &ExportContext,
+        allxlsx: &ALLXLSX,
+    ) -> Result<(), Error> {
+        let json_file_name = ctx.output_path(fname, &format!("{}.json", table_stem(fname)));
         let mut file_content = String::from("[");
 
         let mut line_prefix = String::from("");
-        for row_values in self.xlsx.value_list() {
+        let fields = self.xlsx.side_fields(&ctx.side_for("json"));
+        for row_values in self.xlsx.rows(ctx.options.sort_by_key) {
             let mut line_content = line_prefix + "\n{";
 
             let mut column_prefix = String::from("");
-            for field in self.xlsx.fields_list() {
-                let v = self.normalize_values(
-                    field.get_field_type(),
-                    &row_values[field.as_index() as usize],
-                )?;
+            for field in &fields {
+                let v = field
+                    .value_of(fname, &row_values[field.as_index() as usize], allxlsx)?
+                    .to_json();
                 line_content += &column_prefix;
                 line_content += "\"";
-                let name = field.get_field_name().clone();
+                let name = field.get_field_name().to_string();
                 line_content += &name;
                 line_content += "\":";
                 line_content += &v;
@@ -143,8 +80,94 @@ impl<'a> Generator<'a> for JsonGenerator<'a> {
 
         file_content += "\n]";
 
-        let mut f = fs::File::create(json_file_name)?;
-        f.write(file_content.as_bytes())?;
+        write_file(&json_file_name, &file_content)?;
+
+        // lookup maps go to files of their own, keys of json objects are strings
+        let side = ctx.side_for("json");
+        for index in allxlsx.indexes(fname, self.xlsx, &side, ctx.options.sort_by_key)? {
+            let index_file_name = ctx.output_path(
+                fname,
+                &format!(
+                    "{}_by_{}.json",
+                    table_stem(fname),
+                    index.field.get_field_name()
+                ),
+            );
+            let mut index_content = String::from("{");
+            let mut line_prefix = "";
+            for (value, keys) in &index.entries {
+                index_content += &format!(
+                    "{}\n{}:{}",
+                    line_prefix,
+                    normalize_key(value),
+                    index_value(&index, keys, ("[", "]"), ("[", "]"), Value::to_json)
+                );
+                line_prefix = ",";
+            }
+            index_content += "\n}";
+            write_file(&index_file_name, &index_content)?;
+        }
         Ok(())
     }
+
+    fn generate_enums(&mut self, fname: &str, ctx: &ExportContext) -> Result<(), Error> {
+        let json_file_name = ctx.output_path(fname, &format!("{}_enum.json", table_stem(fname)));
+        let mut file_content = String::from("{");
+
+        let mut line_prefix = "";
+        for def in self.xlsx.enum_list() {
+            let items: Vec<String> = def
+                .items
+                .iter()
+                .map(|item| format!("{}:{}", json_string(&item.label), item.value))
+                .collect();
+            file_content += &format!(
+                "{}\n{}:{{{}}}",
+                line_prefix,
+                json_string(&def.name),
+                items.join(",")
+            );
+            line_prefix = ",";
+        }
+
+        file_content += "\n}";
+        write_file(&json_file_name, &file_content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::JsonGenerator;
+    use crate::context::ExportContext;
+    use crate::fixture::{self, sheet1, text};
+    use std::fs;
+
+    #[test]
+    fn test_json_indexes() {
+        let rows = vec![
+            text(&["id", "open", "day"]),
+            text(&["int!key", "bool!group", "date!index"]),
+            text(&["1", "是", "2024-01-31"]),
+            text(&["2", "否", "2024-02-01"]),
+            text(&["3", "1", ""]),
+        ];
+        let all = fixture::load(
+            "xlsxto_json_indexes",
+            &[("item.xlsx", sheet1(rows))],
+            &fixture::layout(),
+        );
+        let out = fixture::temp_dir("xlsxto_json_indexes_out");
+        all.gen_table::<JsonGenerator>("item.xlsx", &ExportContext::new(&out))
+            .unwrap();
+
+        let read = |name: &str| fs::read_to_string(format!("{}/{}", out, name)).unwrap();
+        assert_eq!(
+            read("item_by_open.json"),
+            "{\n\"true\":[1, 3],\n\"false\":[2]\n}"
+        );
+        assert_eq!(
+            read("item_by_day.json"),
+            "{\n\"2024-01-31\":1,\n\"2024-02-01\":2\n}"
+        );
+    }
 }
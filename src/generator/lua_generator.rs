@@ -20,20 +20,21 @@ LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
 OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
 WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
 */
-use super::Generator;
-use crate::{get_project_name, ALLXLSX, XLSX};
+use super::{index_value, write_file, Generator};
+use crate::context::ExportContext;
+use crate::error::Error;
+use crate::types::Value;
+use crate::xlsx::table_stem;
+use crate::{ALLXLSX, XLSX};
 use hlua::Lua;
-use std::fs;
-use std::io::prelude::*;
-use std::io::{Error, ErrorKind};
 
 pub struct LuaGenerator<'a> {
     xlsx: &'a XLSX,
 }
 
 impl<'a> LuaGenerator<'a> {
-    fn transfer_lua_keyword(&self, word: &'a String) -> &'a str {
-        match word.as_str() {
+    fn transfer_lua_keyword<'b>(&self, word: &'b str) -> &'b str {
+        match word {
             "function" => "['function']",
             "end" => "['end']",
             "do" => "['do']",
@@ -43,21 +44,7 @@ impl<'a> LuaGenerator<'a> {
             "elseif" => "['elseif']",
             "then" => "['then']",
             "while" => "['while']",
-            _ => word.as_str(),
-        }
-    }
-
-    fn type_default_value(&self, t: &String, v: &'a String) -> String {
-        let trimd_str = v.trim();
-        if !trimd_str.is_empty() {
-            return v.clone();
-        }
-
-        match t.as_str() {
-            "int" | "float" => "0".to_string(),
-            "string" => "''".to_string(),
-
-            _ => "nil".to_string(),
+            _ => word,
         }
     }
 
@@ -72,88 +59,104 @@ impl<'a> LuaGenerator<'a> {
         space
     }
 
-    fn normalize_key_value(&self, t: &String, v: &String) -> String {
-        if t.contains("string") {
-            return format!(" [[{}]] ", v);
+    /// a key of the table or of a lookup map
+    fn normalize_key(&self, v: &Value) -> String {
+        match v {
+            Value::Str(s) => format!(" [[{}]] ", s),
+            _ => self.normalize_nonkey_value(v),
         }
-
-        return v.clone();
     }
 
-    fn normalize_nonkey_value(
-        &self,
-        t: &String,
-        v: &String,
-    ) -> Result<String, Box<dyn std::error::Error>> {
-        let type_name = t.as_str();
-        if type_name.contains("[]") {
-            let value = v.replace("[", "{").replace("]", "}");
-            return Ok(self.type_default_value(t, &value));
-        }
-
-        if type_name.contains("string") {
-            let mut double_quote = false;
-            let mut single_quote = false;
-            let mut right_brace = false;
-            let mut has_new_line = false;
-            if v.contains("'") {
-                single_quote = true;
-            }
-            if v.contains("\"") {
-                double_quote = true;
+    fn normalize_nonkey_value(&self, v: &Value) -> String {
+        match v {
+            Value::Number(n) => n.clone(),
+            Value::Str(s) => self.quote_string(s),
+            Value::Bool(b) => b.to_string(),
+            // times are exported as unix seconds
+            Value::Date(secs) | Value::DateTime(secs) | Value::Duration(secs) => secs.to_string(),
+            Value::Nil => "nil".to_string(),
+            Value::Raw(r) => {
+                if r.trim().is_empty() {
+                    return "nil".to_string();
+                }
+                r.clone()
             }
-            if v.contains("]]") || v.ends_with("]") {
-                right_brace = true;
+            Value::List(items) | Value::Tuple(items) => {
+                let items: Vec<String> = items
+                    .iter()
+                    .map(|i| self.normalize_nonkey_value(i))
+                    .collect();
+                format!("{{{}}}", items.join(","))
             }
-            if v.contains("\n") || v.contains("\r") {
-                has_new_line = true;
+            Value::Struct(fields) => {
+                let fields: Vec<String> = fields
+                    .iter()
+                    .map(|(name, v)| {
+                        format!(
+                            "{} = {}",
+                            self.transfer_lua_keyword(name),
+                            self.normalize_nonkey_value(v)
+                        )
+                    })
+                    .collect();
+                format!("{{{}}}", fields.join(","))
             }
+        }
+    }
 
-            if !double_quote && !has_new_line {
-                return Ok(format!("\"{}\"", v));
-            }
+    fn quote_string(&self, v: &str) -> String {
+        let mut double_quote = false;
+        let mut single_quote = false;
+        let mut right_brace = false;
+        let mut has_new_line = false;
+        if v.contains("'") {
+            single_quote = true;
+        }
+        if v.contains("\"") {
+            double_quote = true;
+        }
+        if v.contains("]]") || v.ends_with("]") {
+            right_brace = true;
+        }
+        if v.contains("\n") || v.contains("\r") {
+            has_new_line = true;
+        }
 
-            if !single_quote && !has_new_line {
-                return Ok(format!("'{}'", v));
-            }
+        if !double_quote && !has_new_line {
+            return format!("\"{}\"", v);
+        }
 
-            if !right_brace {
-                return Ok(format!("[[{}]]", v));
-            }
+        if !single_quote && !has_new_line {
+            return format!("'{}'", v);
+        }
 
-            panic!("string is invalid!{}", v)
+        if !right_brace {
+            return format!("[[{}]]", v);
         }
 
-        return Ok(self.type_default_value(t, &v));
+        panic!("string is invalid!{}", v)
     }
 
     pub fn check_lua_file_valid(
         &mut self,
-        luastr: &String,
-        fname: &String,
-        key: &String,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        let p = std::panic::catch_unwind(move || {
-            let mut lua = Lua::new();
-            lua.execute::<()>(luastr)
-        })
-        .is_err();
+        luastr: &str,
+        fname: &str,
+        key: &str,
+    ) -> Result<(), Error> {
+        let p = !matches!(
+            std::panic::catch_unwind(move || {
+                let mut lua = Lua::new();
+                lua.execute::<()>(luastr)
+            }),
+            Ok(Ok(_))
+        );
 
         if p {
-            let err_info = luastr
-                .replace("\"", "\\\"")
-                .replace("'", "\\\'")
-                .replace("local _ =", "");
-            return Err(Box::new(Error::new(
-                ErrorKind::InvalidData,
-                format!(
-                    "**导表校验失败**\n**项目：{} 文件名：{}**\n  键值：{} 错误行：{}",
-                    get_project_name(),
-                    fname,
-                    key,
-                    err_info
-                ),
-            )));
+            return Err(Error::LuaSyntax {
+                file: fname.to_string(),
+                key: key.to_string(),
+                code: luastr.replace("local _ =", ""),
+            });
         }
         Ok(())
     }
@@ -167,15 +170,14 @@ impl<'a> Generator<'a> for LuaGenerator<'a> {
     fn generate(
         &mut self,
         fname: &str,
-        out_path: &String,
-        _allxlsx: &ALLXLSX,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        let lua_file_name = format!(
-            "{}/t_{}.lua",
-            out_path,
-            fname[..fname.len() - 5].to_lowercase()
+        ctx: &ExportContext,
+        allxlsx: &ALLXLSX,
+    ) -> Result<(), Error> {
+        let lua_file_name = ctx.output_path(
+            fname,
+            &format!("t_{}.lua", table_stem(fname).to_lowercase()),
         );
-        let lua_table_name = format!("t_{}", fname[..fname.len() - 5].to_lowercase());
+        let lua_table_name = format!("t_{}", table_stem(fname).to_lowercase());
         let file_name = format!("{}.lua", &lua_table_name);
         let mut file_content = format!(
             "-----------------------------------------------------------------------
@@ -190,14 +192,15 @@ impl<'a> Generator<'a> for LuaGenerator<'a> {
 
         file_content += "{\n";
         let xlsfilename = fname.to_string();
+        let fields = self.xlsx.side_fields(&ctx.side_for("lua"));
 
-        for row_values in self.xlsx.value_list() {
+        for row_values in self.xlsx.rows(ctx.options.sort_by_key) {
             let mut line_str = String::from("");
             let mut key_num = 0;
             let mut valid_line = true;
             let mut short_line = String::from("");
             let mut line_key: String = String::from("");
-            for field in self.xlsx.fields_list() {
+            for field in &fields {
                 let value = &row_values[field.as_index() as usize];
                 if field.is_key_field() {
                     if value.is_empty() {
@@ -206,27 +209,8 @@ impl<'a> Generator<'a> for LuaGenerator<'a> {
                     }
 
                     key_num += 1;
-                    let key = self.normalize_key_value(field.get_field_type(), value);
-                    if !field.need_full_load() && !field.is_valid(&key) {
-                        let err_info = format!(
-                            "键不合法\n字段名：{}, 字段值：{}\n字段要求：{}",
-                            field.get_field_name(),
-                            key,
-                            field.get_condition()
-                        )
-                        .replace("\"", "\\\"")
-                        .replace("'", "\\\'");
-                        return Err(Box::new(Error::new(
-                            ErrorKind::InvalidData,
-                            format!(
-                                "**导表校验失败**\n**项目：{} 文件名：{}**\n{}",
-                                get_project_name(),
-                                fname,
-                                err_info
-                            ),
-                        )));
-                    }
-                    line_key = format!("{}", &key);
+                    let key = self.normalize_key(&field.key_of(fname, value, allxlsx)?);
+                    line_key = key.clone();
                     let mut key_str = format!("[{}] = ", &key);
                     if key_num == 1 {
                         key_str = self.get_space_str(key_num * 2);
@@ -248,26 +232,8 @@ impl<'a> Generator<'a> for LuaGenerator<'a> {
                 } else {
                     let key_str =
                         format!("{} = ", self.transfer_lua_keyword(field.get_field_name()));
-                    let value_str = self.normalize_nonkey_value(field.get_field_type(), value)?;
-                    if !field.need_full_load() && !field.is_valid(&value_str) {
-                        let err_info = format!(
-                            "字段值不合要求\n字段名:{}, 字段值:{}\n字段要求: {}",
-                            field.get_field_name(),
-                            value_str,
-                            field.get_condition()
-                        )
-                        .replace("\"", "\\\"")
-                        .replace("'", "\\\'");
-                        return Err(Box::new(Error::new(
-                            ErrorKind::InvalidData,
-                            format!(
-                                "**导表校验失败**\n**项目：{} 文件名：{}**\n{}",
-                                get_project_name(),
-                                fname,
-                                err_info
-                            ),
-                        )));
-                    }
+                    let value_str =
+                        self.normalize_nonkey_value(&field.value_of(fname, value, allxlsx)?);
                     if short_line.len() + key_str.len() + value_str.len() >= 100 {
                         line_str += &short_line;
                         line_str += "\n";
@@ -307,8 +273,107 @@ impl<'a> Generator<'a> for LuaGenerator<'a> {
         }
 
         file_content += "}";
-        let mut f = fs::File::create(lua_file_name)?;
-        f.write(file_content.as_bytes())?;
-        Ok(())
+
+        let side = ctx.side_for("lua");
+        for index in allxlsx.indexes(fname, self.xlsx, &side, ctx.options.sort_by_key)? {
+            file_content += &format!(
+                "\n\n{}_by_{} = \n{{\n",
+                &lua_table_name,
+                index.field.get_field_name()
+            );
+            for (value, keys) in &index.entries {
+                let render = |v: &Value| self.normalize_key(v);
+                file_content += &format!(
+                    "  [{}] = {},\n",
+                    self.normalize_key(value),
+                    index_value(&index, keys, ("{", "}"), ("{", "}"), render)
+                );
+            }
+            file_content += "}";
+        }
+        write_file(&lua_file_name, &file_content)
+    }
+
+    fn generate_enums(&mut self, fname: &str, ctx: &ExportContext) -> Result<(), Error> {
+        let file_name = format!("e_{}.lua", table_stem(fname).to_lowercase());
+        let mut file_content = format!(
+            "-----------------------------------------------------------------------
+-- file: {}
+-- desc: this file is generated by tools, do NOT edit this file!
+-----------------------------------------------------------------------
+",
+            &file_name
+        );
+
+        for def in self.xlsx.enum_list() {
+            file_content += &format!("\n{} = \n{{\n", def.name);
+            for item in &def.items {
+                file_content += &format!(
+                    "  {} = {},",
+                    self.transfer_lua_keyword(&item.label),
+                    item.value
+                );
+                if !item.comment.is_empty() {
+                    file_content += &format!(" -- {}", item.comment.replace('\n', " "));
+                }
+                file_content += "\n";
+            }
+            file_content += "}\n";
+        }
+
+        write_file(&ctx.output_path(fname, &file_name), &file_content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LuaGenerator;
+    use crate::context::ExportContext;
+    use crate::fixture::{self, sheet1, text};
+    use std::fs;
+
+    #[test]
+    fn test_lua_scalar_keys() {
+        let rows = vec![
+            text(&["open", "day", "cd", "name"]),
+            text(&["bool!key", "date!key", "duration!key", "string"]),
+            text(&["是", "2024-01-31", "1m", "a"]),
+        ];
+        let all = fixture::load(
+            "xlsxto_lua_keys",
+            &[("item.xlsx", sheet1(rows))],
+            &fixture::layout(),
+        );
+        let out = fixture::temp_dir("xlsxto_lua_keys_out");
+        all.gen_table::<LuaGenerator>("item.xlsx", &ExportContext::new(&out))
+            .unwrap();
+
+        let content = fs::read_to_string(format!("{}/t_item.lua", out)).unwrap();
+        assert!(content.contains("  [true] = {\n    [1706659200] = {\n      [60] = {\n"));
+    }
+
+    #[test]
+    fn test_lua_indexes() {
+        let rows = vec![
+            text(&["id", "open", "day"]),
+            text(&["int!key", "bool!group", "date!index"]),
+            text(&["1", "是", "2024-01-31"]),
+            text(&["2", "否", ""]),
+            text(&["3", "1", ""]),
+        ];
+        let all = fixture::load(
+            "xlsxto_lua_indexes",
+            &[("item.xlsx", sheet1(rows))],
+            &fixture::layout(),
+        );
+        let out = fixture::temp_dir("xlsxto_lua_indexes_out");
+        all.gen_table::<LuaGenerator>("item.xlsx", &ExportContext::new(&out))
+            .unwrap();
+
+        let content = fs::read_to_string(format!("{}/t_item.lua", out)).unwrap();
+        assert!(content.ends_with(
+            "t_item_by_open = \n{\n  [true] = {1, 3},\n  [false] = {2},\n}\n\n\
+             t_item_by_day = \n{\n  [1706659200] = 1,\n}"
+        ));
     }
 }
@@ -20,12 +20,22 @@ LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
 OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
 WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
 */
-use crate::xlsx::XLSX;
+use crate::context::ExportContext;
+use crate::error::Error;
+use crate::pool::par_map;
+use crate::types::Value;
+use crate::xlsx::{output_name, table_stem, IndexKind, TableIndex, ALLXLSX, XLSX};
+use std::fs;
+use std::io::prelude::*;
+use std::path::Path;
 
+pub mod csharp_generator;
+pub mod go_generator;
 pub mod json_generator;
 pub mod lua_generator;
 pub mod python_generator;
 pub mod sql_generator;
+pub mod yaml_generator;
 
 pub trait Generator<'a> {
     fn new(xlsx: &'a XLSX) -> Self;
@@ -33,7 +43,192 @@ pub trait Generator<'a> {
     fn generate(
         &mut self,
         file_name: &str,
-        out_path: &String,
-        all: &super::ALLXLSX,
-    ) -> Result<(), Box<dyn std::error::Error>>;
+        ctx: &ExportContext,
+        all: &ALLXLSX,
+    ) -> Result<(), Error>;
+
+    /// writes the enums defined by the workbook, targets without enum
+    /// support write nothing
+    fn generate_enums(&mut self, _file_name: &str, _ctx: &ExportContext) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+/// writes `content` to `path`, a file that already holds exactly these
+/// bytes is left alone so its mtime does not change
+pub fn write_file(path: &str, content: &str) -> Result<(), Error> {
+    if let Ok(old) = fs::read(path) {
+        if old == content.as_bytes() {
+            return Ok(());
+        }
+    }
+    if let Some(dir) = Path::new(path).parent() {
+        fs::create_dir_all(dir).map_err(|e| Error::io(path, e))?;
+    }
+    let mut f = fs::File::create(path).map_err(|e| Error::io(path, e))?;
+    f.write_all(content.as_bytes())
+        .map_err(|e| Error::io(path, e))
+}
+
+/// the value of a lookup map entry: the key of the row, or the keys of the
+/// rows for a group. `tuple` wraps composite keys, `list` groups, and
+/// `render` writes each key value
+pub fn index_value<F>(
+    index: &TableIndex,
+    keys: &[Vec<Value>],
+    tuple: (&str, &str),
+    list: (&str, &str),
+    render: F,
+) -> String
+where
+    F: Fn(&Value) -> String,
+{
+    let key_value = |key: &Vec<Value>| {
+        let parts: Vec<String> = key.iter().map(&render).collect();
+        if parts.len() == 1 {
+            return parts[0].clone();
+        }
+        format!("{}{}{}", tuple.0, parts.join(", "), tuple.1)
+    };
+    match index.kind {
+        IndexKind::Unique => key_value(&keys[0]),
+        IndexKind::Group => {
+            let items: Vec<String> = keys.iter().map(key_value).collect();
+            format!("{}{}{}", list.0, items.join(", "), list.1)
+        }
+    }
+}
+
+/// `item_drop` -> `ItemDrop`, used for type and member names
+pub fn pascal_case(name: &str) -> String {
+    let mut out = String::new();
+    for part in name.split(|c: char| !c.is_alphanumeric()) {
+        let mut chars = part.chars();
+        if let Some(first) = chars.next() {
+            out.extend(first.to_uppercase());
+            out.push_str(chars.as_str());
+        }
+    }
+    match out.chars().next() {
+        None => "Table".to_string(),
+        Some(c) if c.is_ascii_digit() => format!("_{}", out),
+        _ => out,
+    }
+}
+
+/// names of the generators, in the order they run
+pub const TARGETS: [&str; 7] = ["lua", "json", "python", "sql", "yaml", "csharp", "go"];
+
+/// generates the table `name` for `target`, unknown targets do nothing
+pub fn generate_table(
+    target: &str,
+    all: &ALLXLSX,
+    name: &str,
+    ctx: &ExportContext,
+) -> Result<(), Error> {
+    match target {
+        "lua" => all.gen_table::<lua_generator::LuaGenerator>(name, ctx),
+        "json" => all.gen_table::<json_generator::JsonGenerator>(name, ctx),
+        "python" => all.gen_table::<python_generator::PyGenerator>(name, ctx),
+        "sql" => all.gen_table::<sql_generator::SQLGenerator>(name, ctx),
+        "yaml" => all.gen_table::<yaml_generator::YamlGenerator>(name, ctx),
+        "csharp" => all.gen_table::<csharp_generator::CSharpGenerator>(name, ctx),
+        "go" => all.gen_table::<go_generator::GoGenerator>(name, ctx),
+        _ => Ok(()),
+    }
+}
+
+/// names the table `name` exports that another table could export too, as
+/// (key, name) pairs: its output files and those of its lookup maps, and the
+/// type names of the targets putting every table in one namespace
+pub fn exported_names(name: &str, xlsx: &XLSX, ctx: &ExportContext) -> Vec<(String, String)> {
+    let mut names = vec![(output_name(name), table_stem(name))];
+    // `item.xlsx` indexed on `name` writes what `item_by_name.xlsx` writes
+    if ["lua", "json", "python", "yaml"]
+        .iter()
+        .any(|t| ctx.has_target(t))
+        && xlsx.key_num() > 0
+    {
+        for field in xlsx
+            .fields_list()
+            .iter()
+            .filter(|f| f.get_index().is_some())
+        {
+            let suffix = format!("_by_{}", field.get_field_name());
+            names.push((
+                format!("{}{}", output_name(name), suffix.to_lowercase()),
+                format!("{}{}", table_stem(name), suffix),
+            ));
+        }
+    }
+    if ctx.has_target("csharp") {
+        for n in csharp_generator::declared_names(name, xlsx, &ctx.side_for("csharp")) {
+            names.push((format!("csharp:{}", n), n));
+        }
+    }
+    if ctx.has_target("go") {
+        for n in go_generator::declared_names(name, xlsx, &ctx.side_for("go")) {
+            names.push((format!("go:{}", n), n));
+        }
+    }
+    names
+}
+
+/// names declared by the files every table of a target shares, as
+/// (key, file) pairs
+pub fn shared_names(ctx: &ExportContext) -> Vec<(String, String)> {
+    let mut names = vec![];
+    if ctx.has_target("csharp") {
+        names.push((
+            format!("csharp:{}", csharp_generator::READER_CLASS),
+            csharp_generator::READER_FILE_NAME.to_string(),
+        ));
+    }
+    if ctx.has_target("go") {
+        for n in go_generator::RUNTIME_NAMES {
+            names.push((
+                format!("go:{}", n),
+                go_generator::RUNTIME_FILE_NAME.to_string(),
+            ));
+        }
+    }
+    names
+}
+
+/// generates every table for every target of `ctx` on `ctx.options.jobs`
+/// threads, the error of the first failing target and table is returned
+pub fn generate_all(all: &ALLXLSX, ctx: &ExportContext) -> Result<(), Error> {
+    let names: Vec<String> = all
+        .tables()
+        .into_iter()
+        .map(|(name, _)| name.clone())
+        .collect();
+    let mut tasks: Vec<(&str, &String)> = vec![];
+    for target in TARGETS.iter().filter(|t| ctx.has_target(t)) {
+        tasks.extend(names.iter().map(|name| (*target, name)));
+    }
+
+    // files shared by the tables of a target
+    let mut shared = Ok(());
+    if ctx.has_target("csharp") {
+        shared = shared.and_then(|_| csharp_generator::write_reader(ctx));
+    }
+    if ctx.has_target("go") {
+        shared = shared.and_then(|_| go_generator::write_runtime(ctx));
+    }
+    if let Err(e) = shared {
+        ctx.notify_error(&e);
+        return Err(e);
+    }
+
+    let results = par_map(&tasks, ctx.options.jobs, |(target, name)| {
+        generate_table(target, all, name, ctx)
+    });
+    for ret in results {
+        if let Err(e) = ret {
+            ctx.notify_error(&e);
+            return Err(e);
+        }
+    }
+    Ok(())
 }
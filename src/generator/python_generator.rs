@@ -20,32 +20,20 @@ LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
 OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
 WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
 */
-use crate::{get_project_name, ALLXLSX, XLSX};
-use std::fs;
-use std::io::prelude::*;
-use std::io::{Error, ErrorKind};
+use crate::context::ExportContext;
+use crate::datetime;
+use crate::error::Error;
+use crate::types::Value;
+use crate::xlsx::table_stem;
+use crate::{ALLXLSX, XLSX};
 
-use super::Generator;
+use super::{index_value, write_file, Generator};
 
 pub struct PyGenerator<'a> {
     xlsx: &'a XLSX,
 }
 
 impl<'a> PyGenerator<'a> {
-    fn type_default_value(&self, t: &String, v: &'a String) -> String {
-        let trimd_str = v.trim();
-        if !trimd_str.is_empty() {
-            return v.clone();
-        }
-
-        match t.as_str() {
-            "int" | "float" => "0".to_string(),
-            "string" => "''".to_string(),
-
-            _ => "None".to_string(),
-        }
-    }
-
     fn get_space_str(&self, space_num: u32) -> String {
         let mut space = String::from("");
         let mut num = space_num;
@@ -57,51 +45,94 @@ impl<'a> PyGenerator<'a> {
         space
     }
 
-    fn normalize_key_value(&self, t: &String, v: &String) -> String {
-        if t.contains("string") {
-            return format!(" '''{}''' ", v);
+    /// a key of the table or of a lookup map
+    fn normalize_key(&self, v: &Value) -> String {
+        match v {
+            Value::Str(s) => format!(" '''{}''' ", s),
+            _ => self.normalize_nonkey_value(v),
         }
-
-        return v.clone();
     }
 
-    fn normalize_nonkey_value(
-        &self,
-        t: &String,
-        v: &String,
-    ) -> Result<String, Box<dyn std::error::Error>> {
-        let type_name = t.as_str();
-        if type_name.contains("[]") {
-            return Ok(self.type_default_value(t, &v));
-        }
-
-        if type_name.contains("string") {
-            let mut double_quote = false;
-            let mut single_quote = false;
-            let mut has_new_line = false;
-            if v.contains("'") {
-                single_quote = true;
+    fn normalize_nonkey_value(&self, v: &Value) -> String {
+        match v {
+            Value::Number(n) => n.clone(),
+            Value::Str(s) => self.quote_string(s),
+            Value::Bool(b) => {
+                if *b {
+                    return "True".to_string();
+                }
+                "False".to_string()
             }
-            if v.contains("\"") {
-                double_quote = true;
+            Value::Date(secs) => {
+                let (y, m, d, _, _, _) = datetime::to_parts(*secs);
+                format!("datetime.date({}, {}, {})", y, m, d)
             }
-
-            if v.contains("\n") || v.contains("\r") {
-                has_new_line = true;
+            Value::DateTime(secs) => {
+                let (y, m, d, h, mi, s) = datetime::to_parts(*secs);
+                format!(
+                    "datetime.datetime({}, {}, {}, {}, {}, {})",
+                    y, m, d, h, mi, s
+                )
             }
-
-            if !double_quote && !has_new_line {
-                return Ok(format!("\"{}\"", v));
+            Value::Duration(secs) => format!("datetime.timedelta(seconds={})", secs),
+            Value::Nil => "None".to_string(),
+            Value::Raw(r) => {
+                if r.trim().is_empty() {
+                    return "None".to_string();
+                }
+                r.clone()
             }
-
-            if !single_quote && !has_new_line {
-                return Ok(format!("'{}'", v));
+            Value::List(items) => {
+                let items: Vec<String> = items
+                    .iter()
+                    .map(|i| self.normalize_nonkey_value(i))
+                    .collect();
+                format!("[{}]", items.join(","))
+            }
+            Value::Tuple(items) => {
+                let items: Vec<String> = items
+                    .iter()
+                    .map(|i| self.normalize_nonkey_value(i))
+                    .collect();
+                if items.len() == 1 {
+                    return format!("({},)", items[0]);
+                }
+                format!("({})", items.join(","))
             }
+            Value::Struct(fields) => {
+                let fields: Vec<String> = fields
+                    .iter()
+                    .map(|(name, v)| format!("'{}' : {}", name, self.normalize_nonkey_value(v)))
+                    .collect();
+                format!("{{{}}}", fields.join(","))
+            }
+        }
+    }
 
-            return Ok(format!("'''{}'''", v));
+    fn quote_string(&self, v: &str) -> String {
+        let mut double_quote = false;
+        let mut single_quote = false;
+        let mut has_new_line = false;
+        if v.contains("'") {
+            single_quote = true;
+        }
+        if v.contains("\"") {
+            double_quote = true;
+        }
+
+        if v.contains("\n") || v.contains("\r") {
+            has_new_line = true;
         }
 
-        return Ok(self.type_default_value(t, &v));
+        if !double_quote && !has_new_line {
+            return format!("\"{}\"", v);
+        }
+
+        if !single_quote && !has_new_line {
+            return format!("'{}'", v);
+        }
+
+        format!("'''{}'''", v)
     }
 }
 
@@ -113,29 +144,28 @@ impl<'a> Generator<'a> for PyGenerator<'a> {
     fn generate(
         &mut self,
         fname: &str,
-        out_path: &String,
-        _allxlsx: &ALLXLSX,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        let py_file_name = format!(
-            "{}/t_{}.py",
-            out_path,
-            fname[..fname.len() - 5].to_lowercase()
-        );
-        let py_dict_name = format!("t_{}", fname[..fname.len() - 5].to_lowercase());
-        let mut file_content = format!(
-            "# this file is generated by tools, do NOT edit this file!
-
-{} = {{
-",
-            &py_dict_name
-        );
-
-        for row_values in self.xlsx.value_list() {
+        ctx: &ExportContext,
+        allxlsx: &ALLXLSX,
+    ) -> Result<(), Error> {
+        let py_file_name =
+            ctx.output_path(fname, &format!("t_{}.py", table_stem(fname).to_lowercase()));
+        let py_dict_name = format!("t_{}", table_stem(fname).to_lowercase());
+        let fields = self.xlsx.side_fields(&ctx.side_for("python"));
+        let mut file_content =
+            String::from("# this file is generated by tools, do NOT edit this file!\n\n");
+        if fields
+            .iter()
+            .any(|f| f.get_value_type().is_some_and(|t| t.has_time()))
+        {
+            file_content += "import datetime\n\n";
+        }
+        file_content += &format!("{} = {{\n", &py_dict_name);
+        for row_values in self.xlsx.rows(ctx.options.sort_by_key) {
             let mut line_str = String::from("");
             let mut key_num = 0;
             let mut valid_line = true;
             let mut short_line = String::from("");
-            for field in self.xlsx.fields_list() {
+            for field in &fields {
                 let value = &row_values[field.as_index() as usize];
                 if field.is_key_field() {
                     if value.is_empty() {
@@ -144,26 +174,7 @@ impl<'a> Generator<'a> for PyGenerator<'a> {
                     }
 
                     key_num += 1;
-                    let key = self.normalize_key_value(field.get_field_type(), value);
-                    if !field.need_full_load() && !field.is_valid(&key) {
-                        let err_info = format!(
-                            "键不合法\n字段名：{}, 字段值：{}\n字段要求：{}",
-                            field.get_field_name(),
-                            key,
-                            field.get_condition()
-                        )
-                        .replace("\"", "\\\"")
-                        .replace("'", "\\\'");
-                        return Err(Box::new(Error::new(
-                            ErrorKind::InvalidData,
-                            format!(
-                                "**导表校验失败**\n**项目：{} 文件名：{}**\n{}",
-                                get_project_name(),
-                                fname,
-                                err_info
-                            ),
-                        )));
-                    }
+                    let key = self.normalize_key(&field.key_of(fname, value, allxlsx)?);
 
                     let mut key_str = format!("{} : ", &key);
                     if key_num == 1 {
@@ -185,26 +196,8 @@ impl<'a> Generator<'a> for PyGenerator<'a> {
                     }
                 } else {
                     let key_str = format!("'{}' : ", field.get_field_name());
-                    let value_str = self.normalize_nonkey_value(field.get_field_type(), value)?;
-                    if !field.need_full_load() && !field.is_valid(&value_str) {
-                        let err_info = format!(
-                            "字段值不合要求\n字段名:{}, 字段值:{}\n字段要求: {}",
-                            field.get_field_name(),
-                            value_str,
-                            field.get_condition()
-                        )
-                        .replace("\"", "\\\"")
-                        .replace("'", "\\\'");
-                        return Err(Box::new(Error::new(
-                            ErrorKind::InvalidData,
-                            format!(
-                                "**导表校验失败**\n**项目：{} 文件名：{}**\n{}",
-                                get_project_name(),
-                                fname,
-                                err_info
-                            ),
-                        )));
-                    }
+                    let value_str =
+                        self.normalize_nonkey_value(&field.value_of(fname, value, allxlsx)?);
                     if short_line.len() + key_str.len() + value_str.len() >= 100 {
                         line_str += &short_line;
                         line_str += "\n";
@@ -236,8 +229,48 @@ impl<'a> Generator<'a> for PyGenerator<'a> {
         }
 
         file_content += "}";
-        let mut f = fs::File::create(py_file_name)?;
-        f.write(file_content.as_bytes())?;
-        Ok(())
+
+        let side = ctx.side_for("python");
+        for index in allxlsx.indexes(fname, self.xlsx, &side, ctx.options.sort_by_key)? {
+            file_content += &format!(
+                "\n\n{}_by_{} = {{\n",
+                &py_dict_name,
+                index.field.get_field_name()
+            );
+            for (value, keys) in &index.entries {
+                let render = |v: &Value| self.normalize_key(v);
+                file_content += &format!(
+                    "  {} : {},\n",
+                    self.normalize_key(value),
+                    index_value(&index, keys, ("(", ")"), ("[", "]"), render)
+                );
+            }
+            file_content += "}";
+        }
+        write_file(&py_file_name, &file_content)
+    }
+
+    fn generate_enums(&mut self, fname: &str, ctx: &ExportContext) -> Result<(), Error> {
+        let py_file_name =
+            ctx.output_path(fname, &format!("e_{}.py", table_stem(fname).to_lowercase()));
+        let mut file_content = String::from(
+            "# this file is generated by tools, do NOT edit this file!
+
+from enum import IntEnum
+",
+        );
+
+        for def in self.xlsx.enum_list() {
+            file_content += &format!("\n\nclass {}(IntEnum):\n", def.name);
+            for item in &def.items {
+                file_content += &format!("    {} = {}", item.label, item.value);
+                if !item.comment.is_empty() {
+                    file_content += &format!("  # {}", item.comment.replace('\n', " "));
+                }
+                file_content += "\n";
+            }
+        }
+
+        write_file(&py_file_name, &file_content)
     }
 }
@@ -20,87 +20,80 @@ LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
 OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
 WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
 */
-use crate::{get_project_name, ALLXLSX, XLSX};
-use std::fs;
-use std::io::prelude::*;
-use std::io::{Error, ErrorKind};
+use crate::context::ExportContext;
+use crate::datetime;
+use crate::error::Error;
+use crate::types::{FieldType, Value};
+use crate::xlsx::{table_stem, IndexKind};
+use crate::{XlsTabField, ALLXLSX, XLSX};
 
-use super::Generator;
+use super::{write_file, Generator};
 
 pub struct SQLGenerator<'a> {
     xlsx: &'a XLSX,
 }
 
 impl<'a> SQLGenerator<'a> {
-    fn type_default_value(&self, t: &String, v: &'a String) -> String {
-        let trimd_str = v.trim();
-        if !trimd_str.is_empty() {
-            return v.clone();
-        }
-
-        match t.as_str() {
-            "int" | "float" => "0".to_string(),
-            "string" => "''".to_string(),
-
-            _ => "None".to_string(),
-        }
-    }
-
-    fn normalize_key_value(&self, t: &String, v: &String) -> String {
-        if t.contains("string") {
-            return format!(" '''{}''' ", v);
-        }
-
-        return v.clone();
-    }
-
-    fn normalize_nonkey_value(
-        &self,
-        t: &String,
-        v: &String,
-    ) -> Result<String, Box<dyn std::error::Error>> {
-        let type_name = t.as_str();
-        if type_name.contains("[]") {
-            return Ok(format!("'{}'", v));
-        }
+    fn normalize_nonkey_value(&self, v: &Value) -> String {
+        match v {
+            Value::Number(n) => n.clone(),
+            Value::Str(s) => {
+                if !s.contains('"') {
+                    return format!("\"{}\"", s);
+                }
 
-        if type_name.contains("string") {
-            let mut double_quote = false;
-            let mut single_quote = false;
-            if v.contains("'") {
-                single_quote = true;
+                format!("'{}'", s)
             }
-            if v.contains("\"") {
-                double_quote = true;
+            Value::Bool(b) => {
+                if *b {
+                    return "1".to_string();
+                }
+                "0".to_string()
             }
-
-            if !double_quote  {
-                return Ok(format!("\"{}\"", v));
+            Value::Date(secs) => format!("'{}'", datetime::format_date(*secs)),
+            Value::DateTime(secs) => format!("'{}'", datetime::format_datetime(*secs, ' ')),
+            Value::Duration(secs) => secs.to_string(),
+            Value::Nil => "NULL".to_string(),
+            Value::Raw(r) => {
+                if r.trim().is_empty() {
+                    return "NULL".to_string();
+                }
+                r.clone()
             }
-
-            if !single_quote {
-                return Ok(format!("'{}'", v));
+            // arrays and structs are stored in json columns
+            Value::List(_) | Value::Tuple(_) | Value::Struct(_) => {
+                // mysql reads backslashes in strings as escapes, double them to keep the json
+                let json = v.to_json().replace('\\', "\\\\");
+                format!("'{}'", json.replace('\'', "''"))
             }
-
-            return Ok(format!("'{}'", v));
         }
-
-        return Ok(self.type_default_value(t, &v));
     }
 
-    fn xlsxtype_to_mysqltype(&self, type_name: &String) -> String {
-        match type_name.as_str() {
-            "int" => "INT(11)".to_string(),
-            "long" => "BIGINT(20)".to_string(),
-            "float" => "DOUBLE".to_string(),
+    fn xlsxtype_to_mysqltype(&self, field: &XlsTabField) -> String {
+        match field.get_value_type() {
+            Some(FieldType::Int | FieldType::Enum(_) | FieldType::Duration) => {
+                "INT(11)".to_string()
+            }
+            Some(FieldType::Bool) => "TINYINT(1)".to_string(),
+            Some(FieldType::Date) => "DATE".to_string(),
+            Some(FieldType::DateTime) => "DATETIME".to_string(),
+            Some(FieldType::Long) => "BIGINT(20)".to_string(),
+            Some(FieldType::Float) => "DOUBLE".to_string(),
+            Some(FieldType::Array(_) | FieldType::Tuple(_) | FieldType::Struct(_)) => {
+                "JSON".to_string()
+            }
+            // text columns can only be indexed by a prefix, which would not
+            // keep `!index` values unique
+            _ if field.get_index().is_some() => "VARCHAR(255)".to_string(),
             _ => "text".to_string(),
         }
     }
 
     fn get_create_table_sql(
         &self,
-        table_name: &String,
-    ) -> Result<String, Box<dyn std::error::Error>> {
+        table_name: &str,
+        fields: &[&XlsTabField],
+    ) -> Result<String, Error> {
         let mut create_sql = format!("DROP TABLE IF EXISTS `{}`;\n", table_name);
         create_sql += &format!("CREATE TABLE `{}` (\n", table_name);
         let key_num = self.xlsx.key_num();
@@ -110,12 +103,17 @@ impl<'a> SQLGenerator<'a> {
         }
         let mut prifx = "  ".to_string();
         let mut key_prefix = "".to_string();
-        for field in self.xlsx.fields_list() {
+        for field in fields {
             let field_sql = format!(
-                "{}`{}` {}{}", prifx,
+                "{}`{}` {}{}",
+                prifx,
                 field.get_field_name(),
-                self.xlsxtype_to_mysqltype(field.get_field_type()),
-                if field.is_key_field() { " NOT NULL" } else { "" }
+                self.xlsxtype_to_mysqltype(field),
+                if field.is_key_field() {
+                    " NOT NULL"
+                } else {
+                    ""
+                }
             );
             create_sql += &field_sql;
             prifx = ",\n  ".to_string();
@@ -128,9 +126,22 @@ impl<'a> SQLGenerator<'a> {
             create_sql += ",\n";
             prikey_str += ")";
             create_sql += &prikey_str;
+
+            for field in fields {
+                let kind = match field.get_index() {
+                    Some(IndexKind::Unique) => "UNIQUE KEY",
+                    Some(IndexKind::Group) => "KEY",
+                    None => continue,
+                };
+                create_sql += &format!(
+                    ",\n  {} `idx_{}` (`{}`)",
+                    kind,
+                    field.get_field_name(),
+                    field.get_field_name()
+                );
+            }
         }
 
-        
         create_sql += "\n);\n";
         Ok(create_sql)
     }
@@ -144,84 +155,106 @@ impl<'a> Generator<'a> for SQLGenerator<'a> {
     fn generate(
         &mut self,
         fname: &str,
-        out_path: &String,
-        _allxlsx: &ALLXLSX,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        let sql_file_name = format!(
-            "{}/t_{}.sql",
-            out_path,
-            fname[..fname.len() - 5].to_lowercase()
+        ctx: &ExportContext,
+        allxlsx: &ALLXLSX,
+    ) -> Result<(), Error> {
+        let sql_file_name = ctx.output_path(
+            fname,
+            &format!("t_{}.sql", table_stem(fname).to_lowercase()),
         );
 
-        let table_name = format!("t_{}", fname[..fname.len() - 5].to_lowercase());
-        let mut file_content = self.get_create_table_sql(&table_name)?;
-        
+        let table_name = format!("t_{}", table_stem(fname).to_lowercase());
+        let fields = self.xlsx.side_fields(&ctx.side_for("sql"));
+        let mut file_content = self.get_create_table_sql(&table_name, &fields)?;
+
+        for row_values in self.xlsx.rows(ctx.options.sort_by_key) {
+            if !self.xlsx.is_exported_row(row_values) {
+                continue;
+            }
 
-        for row_values in self.xlsx.value_list() {
             let mut key_part = format!("INSERT INTO `{}`(", table_name);
             let mut value_part = " VALUES(".to_string();
             let mut prefix = "".to_string();
-            for field in self.xlsx.fields_list() {
+            for field in &fields {
                 let value = &row_values[field.as_index() as usize];
-                if field.is_key_field() && value.is_empty() {
-                    break;
-                }
-
                 key_part += &format!("{}`{}`", prefix, field.get_field_name());
-                value_part += &format!("{}{}", prefix, self.normalize_nonkey_value(field.get_field_type(), value)?);
-                
-                prefix = ",".to_string();
+                value_part += &format!(
+                    "{}{}",
+                    prefix,
+                    self.normalize_nonkey_value(&field.value_of(fname, value, allxlsx)?)
+                );
 
-                if field.is_key_field() {
-                    let key = self.normalize_key_value(field.get_field_type(), value);
-                    if !field.need_full_load() && !field.is_valid(&key) {
-                        let err_info = format!(
-                            "键不合法\n字段名：{}, 字段值：{}\n字段要求：{}",
-                            field.get_field_name(),
-                            key,
-                            field.get_condition()
-                        )
-                        .replace("\"", "\\\"")
-                        .replace("'", "\\\'");
-                        return Err(Box::new(Error::new(
-                            ErrorKind::InvalidData,
-                            format!(
-                                "**导表校验失败**\n**项目：{} 文件名：{}**\n{}",
-                                get_project_name(),
-                                fname,
-                                err_info
-                            ),
-                        )));
-                    }
-                } else {
-                    let value_str = self.normalize_nonkey_value(field.get_field_type(), value)?;
-                    if !field.need_full_load() && !field.is_valid(&value_str) {
-                        let err_info = format!(
-                            "字段值不合要求\n字段名:{}, 字段值:{}\n字段要求: {}",
-                            field.get_field_name(),
-                            value_str,
-                            field.get_condition()
-                        )
-                        .replace("\"", "\\\"")
-                        .replace("'", "\\\'");
-                        return Err(Box::new(Error::new(
-                            ErrorKind::InvalidData,
-                            format!(
-                                "**导表校验失败**\n**项目：{} 文件名：{}**\n{}",
-                                get_project_name(),
-                                fname,
-                                err_info
-                            ),
-                        )));
-                    }
-                }
+                prefix = ",".to_string();
             }
 
             file_content += &format!("{}) {});\n", key_part, value_part);
         }
 
-        let mut f = fs::File::create(sql_file_name)?;
-        f.write(file_content.as_bytes())?;
-        Ok(())
+        write_file(&sql_file_name, &file_content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SQLGenerator;
+    use crate::context::ExportContext;
+    use crate::fixture::{self, sheet1, text};
+    use std::fs;
+
+    #[test]
+    fn test_sql_json_escape() {
+        let rows = vec![
+            text(&["id", "info"]),
+            text(&["int!key", "{name:string}"]),
+            text(&["1", "{name:'say \"hi\"\nit\\'s'}"]),
+        ];
+        let all = fixture::load(
+            "xlsxto_sql",
+            &[("item.xlsx", sheet1(rows))],
+            &fixture::layout(),
+        );
+        let out = fixture::temp_dir("xlsxto_sql_out");
+        all.gen_table::<SQLGenerator>("item.xlsx", &ExportContext::new(&out))
+            .unwrap();
+
+        let sql = fs::read_to_string(format!("{}/t_item.sql", out)).unwrap();
+        assert!(sql.ends_with(
+            r#"INSERT INTO `t_item`(`id`,`info`)  VALUES(1,'{"name":"say \\"hi\\"\\nit''s"}');
+"#
+        ));
+    }
+
+    #[test]
+    fn test_sql_indexes() {
+        let rows = vec![
+            text(&["id", "name", "type"]),
+            text(&["int!key", "string!index", "int!group"]),
+            text(&["1", "a", "3"]),
+            text(&["", "b", "4"]),
+        ];
+        let all = fixture::load(
+            "xlsxto_sql_indexes",
+            &[("item.xlsx", sheet1(rows))],
+            &fixture::layout(),
+        );
+        let out = fixture::temp_dir("xlsxto_sql_indexes_out");
+        all.gen_table::<SQLGenerator>("item.xlsx", &ExportContext::new(&out))
+            .unwrap();
+
+        let sql = fs::read_to_string(format!("{}/t_item.sql", out)).unwrap();
+        assert!(sql.starts_with(
+            "DROP TABLE IF EXISTS `t_item`;
+CREATE TABLE `t_item` (
+  `id` INT(11) NOT NULL,
+  `name` VARCHAR(255),
+  `type` INT(11),
+  PRIMARY KEY(`id`),
+  UNIQUE KEY `idx_name` (`name`),
+  KEY `idx_type` (`type`)
+);
+"
+        ));
+        // rows without a key are left out
+        assert!(sql.ends_with(");\nINSERT INTO `t_item`(`id`,`name`,`type`)  VALUES(1,\"a\",3);\n"));
     }
 }
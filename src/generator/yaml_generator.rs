@@ -0,0 +1,371 @@
+/*
+Copyright (c) 2024- rickhan<rick.han@yahoo.com>
+
+Permission is hereby granted, free of charge, to any person obtaining
+a copy of this software and associated documentation files (the
+"Software"), to deal in the Software without restriction, including
+without limitation the rights to use, copy, modify, merge, publish,
+distribute, sublicense, and/or sell copies of the Software, and to
+permit persons to whom the Software is furnished to do so, subject to
+the following conditions:
+
+The above copyright notice and this permission notice shall be
+included in all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+*/
+use crate::context::ExportContext;
+use crate::datetime;
+use crate::error::Error;
+use crate::types::Value;
+use crate::xlsx::table_stem;
+use crate::{ALLXLSX, XLSX};
+
+use super::{index_value, write_file, Generator};
+
+pub struct YamlGenerator<'a> {
+    xlsx: &'a XLSX,
+}
+
+// rows sharing the leading KEY values are merged under the same mapping,
+// yaml does not allow duplicated keys in one mapping.
+enum YamlNode {
+    Branch(Vec<(String, YamlNode)>),
+    Leaf(Vec<(String, String)>),
+}
+
+fn insert_node(nodes: &mut Vec<(String, YamlNode)>, keys: &[String], leaf: Vec<(String, String)>) {
+    let key = &keys[0];
+    let pos = nodes.iter().position(|(k, _)| k == key);
+    if keys.len() == 1 {
+        match pos {
+            Some(i) => nodes[i].1 = YamlNode::Leaf(leaf),
+            None => nodes.push((key.clone(), YamlNode::Leaf(leaf))),
+        }
+        return;
+    }
+
+    let i = match pos {
+        Some(i) => i,
+        None => {
+            nodes.push((key.clone(), YamlNode::Branch(vec![])));
+            nodes.len() - 1
+        }
+    };
+    if let YamlNode::Leaf(_) = nodes[i].1 {
+        nodes[i].1 = YamlNode::Branch(vec![]);
+    }
+    if let YamlNode::Branch(childs) = &mut nodes[i].1 {
+        insert_node(childs, &keys[1..], leaf);
+    }
+}
+
+fn replace_yaml_str(s: &str) -> String {
+    let s = s.replace('\r', "").replace("\\n", "\n");
+    format!(
+        "\"{}\"",
+        s.replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n")
+    )
+}
+
+impl<'a> YamlGenerator<'a> {
+    fn get_space_str(&self, space_num: u32) -> String {
+        let mut space = String::from("");
+        let mut num = space_num;
+        while num > 0 {
+            space += " ";
+            num -= 1;
+        }
+
+        space
+    }
+
+    /// a key of the table or of a lookup map. mapping keys are always
+    /// quoted, yaml 1.1 loads `on`, `yes` or `null` as bools and null
+    fn mapping_key(&self, v: &Value) -> String {
+        match v {
+            Value::Str(s) => replace_yaml_str(s),
+            _ => replace_yaml_str(&self.normalize_nonkey_value(v)),
+        }
+    }
+
+    fn normalize_nonkey_value(&self, v: &Value) -> String {
+        match v {
+            Value::Number(n) => n.clone(),
+            Value::Str(s) => replace_yaml_str(s),
+            Value::Bool(b) => b.to_string(),
+            Value::Date(secs) => datetime::format_date(*secs),
+            Value::DateTime(secs) => datetime::format_datetime(*secs, 'T'),
+            Value::Duration(secs) => secs.to_string(),
+            Value::Nil => "~".to_string(),
+            // raw text may hold `:`, `#` or brackets, so it is always quoted
+            Value::Raw(r) => {
+                if r.trim().is_empty() {
+                    return "~".to_string();
+                }
+                replace_yaml_str(r.trim())
+            }
+            Value::List(items) | Value::Tuple(items) => {
+                let items: Vec<String> = items
+                    .iter()
+                    .map(|i| self.normalize_nonkey_value(i))
+                    .collect();
+                format!("[{}]", items.join(", "))
+            }
+            Value::Struct(fields) => {
+                let fields: Vec<String> = fields
+                    .iter()
+                    .map(|(name, v)| {
+                        format!(
+                            "{}: {}",
+                            replace_yaml_str(name),
+                            self.normalize_nonkey_value(v)
+                        )
+                    })
+                    .collect();
+                format!("{{{}}}", fields.join(", "))
+            }
+        }
+    }
+
+    fn write_nodes(&self, content: &mut String, nodes: &[(String, YamlNode)], depth: u32) {
+        for (key, node) in nodes {
+            *content += &self.get_space_str(depth * 2);
+            *content += key;
+            *content += ":\n";
+            match node {
+                YamlNode::Branch(childs) => self.write_nodes(content, childs, depth + 1),
+                YamlNode::Leaf(fields) => self.write_fields(content, fields, depth + 1, ""),
+            }
+        }
+    }
+
+    fn write_fields(
+        &self,
+        content: &mut String,
+        fields: &[(String, String)],
+        depth: u32,
+        first_prefix: &str,
+    ) {
+        if fields.is_empty() {
+            *content += &self.get_space_str(depth * 2);
+            *content += first_prefix;
+            *content += "{}\n";
+            return;
+        }
+
+        let mut prefix = first_prefix;
+        for (name, value) in fields {
+            *content += &self.get_space_str(depth * 2);
+            *content += prefix;
+            *content += &format!("{}: {}\n", replace_yaml_str(name), value);
+            prefix = if first_prefix.is_empty() { "" } else { "  " };
+        }
+    }
+}
+
+impl<'a> Generator<'a> for YamlGenerator<'a> {
+    fn new(xlsx: &'a XLSX) -> Self {
+        YamlGenerator { xlsx }
+    }
+
+    fn generate(
+        &mut self,
+        fname: &str,
+        ctx: &ExportContext,
+        allxlsx: &ALLXLSX,
+    ) -> Result<(), Error> {
+        let yaml_file_name = ctx.output_path(
+            fname,
+            &format!("t_{}.yaml", table_stem(fname).to_lowercase()),
+        );
+        let mut file_content =
+            String::from("# this file is generated by tools, do NOT edit this file!\n\n");
+
+        let mut keyed_rows: Vec<(String, YamlNode)> = vec![];
+        let mut plain_rows: Vec<Vec<(String, String)>> = vec![];
+        let key_num = self.xlsx.key_num();
+        let side_fields = self.xlsx.side_fields(&ctx.side_for("yaml"));
+        for row_values in self.xlsx.rows(ctx.options.sort_by_key) {
+            let mut keys: Vec<String> = vec![];
+            let mut fields: Vec<(String, String)> = vec![];
+            let mut valid_line = true;
+            for field in &side_fields {
+                let value = &row_values[field.as_index() as usize];
+                if field.is_key_field() {
+                    if value.is_empty() {
+                        valid_line = false;
+                        break;
+                    }
+
+                    keys.push(self.mapping_key(&field.key_of(fname, value, allxlsx)?));
+                } else {
+                    fields.push((
+                        field.get_field_name().to_string(),
+                        self.normalize_nonkey_value(&field.value_of(fname, value, allxlsx)?),
+                    ));
+                }
+            }
+
+            if !valid_line {
+                continue;
+            }
+
+            if key_num > 0 {
+                insert_node(&mut keyed_rows, &keys, fields);
+            } else {
+                plain_rows.push(fields);
+            }
+        }
+
+        if key_num > 0 {
+            self.write_nodes(&mut file_content, &keyed_rows, 0);
+        } else {
+            for fields in &plain_rows {
+                self.write_fields(&mut file_content, fields, 0, "- ");
+            }
+        }
+
+        write_file(&yaml_file_name, &file_content)?;
+
+        // lookup maps go to files of their own, the table is a single mapping
+        let side = ctx.side_for("yaml");
+        for index in allxlsx.indexes(fname, self.xlsx, &side, ctx.options.sort_by_key)? {
+            let index_file_name = ctx.output_path(
+                fname,
+                &format!(
+                    "t_{}_by_{}.yaml",
+                    table_stem(fname).to_lowercase(),
+                    index.field.get_field_name()
+                ),
+            );
+            let mut index_content =
+                String::from("# this file is generated by tools, do NOT edit this file!\n\n");
+            for (value, keys) in &index.entries {
+                let render = |v: &Value| self.normalize_nonkey_value(v);
+                index_content += &format!(
+                    "{}: {}\n",
+                    self.mapping_key(value),
+                    index_value(&index, keys, ("[", "]"), ("[", "]"), render)
+                );
+            }
+            write_file(&index_file_name, &index_content)?;
+        }
+        Ok(())
+    }
+
+    fn generate_enums(&mut self, fname: &str, ctx: &ExportContext) -> Result<(), Error> {
+        let yaml_file_name = ctx.output_path(
+            fname,
+            &format!("e_{}.yaml", table_stem(fname).to_lowercase()),
+        );
+        let mut file_content =
+            String::from("# this file is generated by tools, do NOT edit this file!\n\n");
+
+        for def in self.xlsx.enum_list() {
+            file_content += &format!("{}:\n", replace_yaml_str(&def.name));
+            for item in &def.items {
+                file_content += &format!("  {}: {}", replace_yaml_str(&item.label), item.value);
+                if !item.comment.is_empty() {
+                    file_content += &format!(" # {}", item.comment.replace('\n', " "));
+                }
+                file_content += "\n";
+            }
+        }
+
+        write_file(&yaml_file_name, &file_content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::YamlGenerator;
+    use crate::context::ExportContext;
+    use crate::fixture::{self, sheet1, text};
+    use std::fs;
+
+    #[test]
+    fn test_yaml_output() {
+        let rows = vec![
+            text(&["type", "id", "note", "info", "on"]),
+            text(&[
+                "int!key",
+                "int!key",
+                "localize",
+                "{count:int,tag:string}",
+                "bool",
+            ]),
+            text(&["1", "1", "a: b", "{count:1,tag:x}", "1"]),
+            text(&["1", "2", "#x", "{2,'y: z'}", ""]),
+            text(&["2", "1", "[1", "", "否"]),
+        ];
+        let all = fixture::load(
+            "xlsxto_yaml",
+            &[("item.xlsx", sheet1(rows))],
+            &fixture::layout(),
+        );
+        let out = fixture::temp_dir("xlsxto_yaml_out");
+        all.gen_table::<YamlGenerator>("item.xlsx", &ExportContext::new(&out))
+            .unwrap();
+
+        assert_eq!(
+            fs::read_to_string(format!("{}/t_item.yaml", out)).unwrap(),
+            r##"# this file is generated by tools, do NOT edit this file!
+
+"1":
+  "1":
+    "note": "a: b"
+    "info": {"count": 1, "tag": "x"}
+    "on": true
+  "2":
+    "note": "#x"
+    "info": {"count": 2, "tag": "y: z"}
+    "on": false
+"2":
+  "1":
+    "note": "[1"
+    "info": {"count": 0, "tag": ""}
+    "on": false
+"##
+        );
+    }
+
+    #[test]
+    fn test_yaml_scalar_keys() {
+        let rows = vec![
+            text(&["open", "at", "name"]),
+            text(&["bool!key", "datetime!key", "string"]),
+            text(&["1", "2024-01-31 08:00:00", "a"]),
+            text(&["否", "2024-01-31 08:00:00", "b"]),
+        ];
+        let all = fixture::load(
+            "xlsxto_yaml_keys",
+            &[("item.xlsx", sheet1(rows))],
+            &fixture::layout(),
+        );
+        let out = fixture::temp_dir("xlsxto_yaml_keys_out");
+        all.gen_table::<YamlGenerator>("item.xlsx", &ExportContext::new(&out))
+            .unwrap();
+
+        assert_eq!(
+            fs::read_to_string(format!("{}/t_item.yaml", out)).unwrap(),
+            r##"# this file is generated by tools, do NOT edit this file!
+
+"true":
+  "2024-01-31T08:00:00":
+    "name": "a"
+"false":
+  "2024-01-31T08:00:00":
+    "name": "b"
+"##
+        );
+    }
+}
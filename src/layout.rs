@@ -0,0 +1,279 @@
+/*
+Copyright (c) 2024- rickhan<rick.han@yahoo.com>
+
+Permission is hereby granted, free of charge, to any person obtaining
+a copy of this software and associated documentation files (the
+"Software"), to deal in the Software without restriction, including
+without limitation the rights to use, copy, modify, merge, publish,
+distribute, sublicense, and/or sell copies of the Software, and to
+permit persons to whom the Software is furnished to do so, subject to
+the following conditions:
+
+The above copyright notice and this permission notice shall be
+included in all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+*/
+use crate::error::Error;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// name of the per-project config file looked up in the input directory
+pub const CONFIG_FILE_NAME: &str = "xlsxto.toml";
+/// name of the optional sheet holding per-workbook settings
+pub const META_SHEET_NAME: &str = "__meta__";
+
+/// which excel row (1-based) holds each header role, 0 means the row is absent
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeaderLayout {
+    pub cn_name: usize,
+    pub field_name: usize,
+    pub condition: usize,
+    pub client_server: usize,
+    pub field_type: usize,
+    pub data_start: usize,
+    /// every sheet is a table of its own instead of being appended to the first
+    pub per_sheet: bool,
+    /// keys are guessed from field names like `KEY_id` or `id` instead of
+    /// being declared with `int!key`, see `xlsx::is_legacy_key_name`
+    pub legacy_keys: bool,
+}
+
+impl Default for HeaderLayout {
+    fn default() -> Self {
+        HeaderLayout {
+            cn_name: 1,
+            field_name: 2,
+            condition: 3,
+            client_server: 4,
+            field_type: 5,
+            data_start: 6,
+            per_sheet: false,
+            legacy_keys: false,
+        }
+    }
+}
+
+impl HeaderLayout {
+    pub fn set(&mut self, key: &str, value: i64) -> Result<(), String> {
+        if value < 0 {
+            return Err(format!("{}不能为负数", key));
+        }
+
+        let value = value as usize;
+        match key {
+            "cn_name" => self.cn_name = value,
+            "field_name" => self.field_name = value,
+            "condition" => self.condition = value,
+            "client_server" => self.client_server = value,
+            "field_type" => self.field_type = value,
+            "data_start" => self.data_start = value,
+            "per_sheet" => self.per_sheet = value != 0,
+            "legacy_keys" => self.legacy_keys = value != 0,
+            _ => return Err(format!("未知的表头配置{}", key)),
+        }
+        Ok(())
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.field_name == 0 || self.field_type == 0 {
+            return Err("必须配置field_name和field_type所在的行".to_string());
+        }
+
+        let rows = [
+            ("cn_name", self.cn_name),
+            ("field_name", self.field_name),
+            ("condition", self.condition),
+            ("client_server", self.client_server),
+            ("field_type", self.field_type),
+        ];
+        for (i, (name, row)) in rows.iter().enumerate() {
+            if *row == 0 {
+                continue;
+            }
+            for (other, other_row) in &rows[i + 1..] {
+                if row == other_row {
+                    return Err(format!("{}和{}都在第{}行", name, other, row));
+                }
+            }
+            if *row >= self.data_start {
+                return Err(format!(
+                    "{}在第{}行，必须在data_start第{}行之前",
+                    name, row, self.data_start
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn apply_table(&mut self, table: &toml::Table) -> Result<(), String> {
+        for (key, value) in table {
+            match value.as_integer().or(value.as_bool().map(|b| b as i64)) {
+                Some(v) => self.set(key, v)?,
+                None => return Err(format!("{}必须是整数", key)),
+            }
+        }
+        Ok(())
+    }
+
+    /// reads `key | value` rows of the `__meta__` sheet on top of this layout
+    pub fn apply_meta_sheet(&mut self, rows: &[(String, String)]) -> Result<(), String> {
+        for (key, value) in rows {
+            let key = key.trim();
+            if key.is_empty() {
+                continue;
+            }
+            let value = value.trim().to_lowercase();
+            match value
+                .parse::<f64>()
+                .or(value.parse::<bool>().map(|b| b as i64 as f64))
+            {
+                Ok(v) if v.fract() == 0.0 => self.set(key, v as i64)?,
+                _ => return Err(format!("{}必须是整数", key)),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// settings read from `xlsxto.toml`
+///
+/// ```toml
+/// [layout]
+/// field_name = 1
+/// field_type = 2
+/// data_start = 3
+///
+/// [layouts."item.xlsx"]
+/// data_start = 8
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ProjectConfig {
+    pub layout: HeaderLayout,
+    pub layouts: HashMap<String, HeaderLayout>,
+}
+
+impl ProjectConfig {
+    /// loads `xlsxto.toml` from `dir`, a missing file yields the default config
+    pub fn load(dir: &str) -> Result<ProjectConfig, Error> {
+        let path = Path::new(dir).join(CONFIG_FILE_NAME);
+        if !path.exists() {
+            return Ok(ProjectConfig::default());
+        }
+
+        let path_name = path.to_string_lossy().to_string();
+        let content = fs::read_to_string(&path).map_err(|e| Error::io(&path_name, e))?;
+        ProjectConfig::parse(&content).map_err(|message| Error::Layout {
+            file: path_name,
+            message,
+        })
+    }
+
+    pub fn parse(content: &str) -> Result<ProjectConfig, String> {
+        let table = content.parse::<toml::Table>().map_err(|e| e.to_string())?;
+        let mut config = ProjectConfig::default();
+        if let Some(layout) = table.get("layout") {
+            let layout = layout.as_table().ok_or("layout必须是表")?;
+            config.layout.apply_table(layout)?;
+        }
+        config.layout.validate()?;
+
+        if let Some(layouts) = table.get("layouts") {
+            let layouts = layouts.as_table().ok_or("layouts必须是表")?;
+            for (name, layout) in layouts {
+                let layout = layout
+                    .as_table()
+                    .ok_or(format!("layouts.{}必须是表", name))?;
+                let mut one = config.layout.clone();
+                one.apply_table(layout)
+                    .map_err(|e| format!("layouts.{}: {}", name, e))?;
+                one.validate()
+                    .map_err(|e| format!("layouts.{}: {}", name, e))?;
+                config.layouts.insert(name.clone(), one);
+            }
+        }
+
+        Ok(config)
+    }
+
+    /// `fname` is the path under the input directory, `[layouts]` may name
+    /// either that path or just the file name
+    pub fn layout_for(&self, fname: &str) -> &HeaderLayout {
+        let name = fname.rsplit('/').next().unwrap_or(fname);
+        self.layouts
+            .get(fname)
+            .or_else(|| self.layouts.get(name))
+            .unwrap_or(&self.layout)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HeaderLayout, ProjectConfig};
+
+    #[test]
+    fn test_parse_config() {
+        let config = ProjectConfig::parse(
+            r#"
+[layout]
+cn_name = 0
+field_name = 1
+condition = 0
+client_server = 0
+field_type = 2
+data_start = 3
+legacy_keys = true
+
+[layouts."item.xlsx"]
+cn_name = 3
+data_start = 5
+per_sheet = true
+"#,
+        )
+        .unwrap();
+        assert_eq!(config.layout_for("other.xlsx").field_type, 2);
+        assert_eq!(config.layout_for("other.xlsx").data_start, 3);
+        assert_eq!(config.layout_for("item.xlsx").cn_name, 3);
+        assert_eq!(config.layout_for("item.xlsx").data_start, 5);
+        assert_eq!(config.layout_for("bag/item.xlsx").data_start, 5);
+        assert!(config.layout_for("item.xlsx").per_sheet);
+        assert!(!config.layout_for("other.xlsx").per_sheet);
+        assert!(config.layout_for("item.xlsx").legacy_keys);
+
+        let default = ProjectConfig::parse("").unwrap();
+        assert_eq!(default.layout, HeaderLayout::default());
+    }
+
+    #[test]
+    fn test_invalid_layout() {
+        assert!(ProjectConfig::parse("[layout]\nfield_type = 2").is_err());
+        assert!(ProjectConfig::parse("[layout]\ndata_start = 4").is_err());
+        assert_eq!(
+            ProjectConfig::parse("[layout]\nfield_name = 0").unwrap_err(),
+            "必须配置field_name和field_type所在的行"
+        );
+        assert_eq!(
+            ProjectConfig::parse("[layout]\nunknown = 1").unwrap_err(),
+            "未知的表头配置unknown"
+        );
+        assert!(ProjectConfig::parse("[layout]\ndata_start = \"6\"").is_err());
+
+        let mut layout = HeaderLayout::default();
+        let rows = vec![("data_start".to_string(), "abc".to_string())];
+        assert!(layout.apply_meta_sheet(&rows).is_err());
+        let rows = vec![("data_start".to_string(), "8".to_string())];
+        assert!(layout.apply_meta_sheet(&rows).is_ok());
+        assert_eq!(layout.data_start, 8);
+        let rows = vec![("per_sheet".to_string(), "TRUE".to_string())];
+        assert!(layout.apply_meta_sheet(&rows).is_ok());
+        assert!(layout.per_sheet);
+    }
+}
@@ -0,0 +1,64 @@
+/*
+Copyright (c) 2024- rickhan<rick.han@yahoo.com>
+
+Permission is hereby granted, free of charge, to any person obtaining
+a copy of this software and associated documentation files (the
+"Software"), to deal in the Software without restriction, including
+without limitation the rights to use, copy, modify, merge, publish,
+distribute, sublicense, and/or sell copies of the Software, and to
+permit persons to whom the Software is furnished to do so, subject to
+the following conditions:
+
+The above copyright notice and this permission notice shall be
+included in all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+*/
+pub(crate) mod cache;
+pub(crate) mod cell;
+pub(crate) mod checker;
+mod cli;
+pub(crate) mod context;
+pub(crate) mod datetime;
+pub(crate) mod diagnostics;
+pub(crate) mod diff;
+pub(crate) mod enums;
+pub(crate) mod error;
+#[cfg(test)]
+mod fixture;
+pub(crate) mod generator;
+pub(crate) mod layout;
+pub(crate) mod notifier;
+pub(crate) mod pool;
+pub(crate) mod scan;
+pub(crate) mod schema;
+pub(crate) mod template;
+pub(crate) mod types;
+pub(crate) mod watch;
+pub(crate) mod xlsx;
+
+pub use cell::CellValue;
+pub use checker::{generate_checker, CheckObj, Checker};
+pub use cli::run;
+pub use context::{ExportContext, ExportOptions, ReportFormat};
+pub use diagnostics::Diagnostics;
+pub use enums::{EnumDef, EnumItem, EnumLookup};
+pub use error::{Error, Location};
+pub use generator::csharp_generator::CSharpGenerator;
+pub use generator::go_generator::GoGenerator;
+pub use generator::json_generator::JsonGenerator;
+pub use generator::lua_generator::LuaGenerator;
+pub use generator::python_generator::PyGenerator;
+pub use generator::sql_generator::SQLGenerator;
+pub use generator::yaml_generator::YamlGenerator;
+pub use generator::Generator;
+pub use layout::HeaderLayout;
+pub use notifier::Notifier;
+pub use types::{FieldType, Value};
+pub use xlsx::{IndexKind, TableIndex, XlsTabField, ALLXLSX, XLSX};
@@ -0,0 +1,73 @@
+/*
+Copyright (c) 2024- rickhan<rick.han@yahoo.com>
+
+Permission is hereby granted, free of charge, to any person obtaining
+a copy of this software and associated documentation files (the
+"Software"), to deal in the Software without restriction, including
+without limitation the rights to use, copy, modify, merge, publish,
+distribute, sublicense, and/or sell copies of the Software, and to
+permit persons to whom the Software is furnished to do so, subject to
+the following conditions:
+
+The above copyright notice and this permission notice shall be
+included in all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+*/
+use crate::error::json_string;
+use curl::easy::{Easy, List};
+use std::io::Read;
+
+/// receives the markdown report when an export fails
+pub trait Notifier: Send + Sync {
+    fn notify(&self, content: &str) -> Result<(), Box<dyn std::error::Error>>;
+}
+
+/// posts the report to a chat webhook as a markdown message
+pub struct WebhookNotifier {
+    url: String,
+}
+
+impl WebhookNotifier {
+    pub fn new(url: &str) -> WebhookNotifier {
+        WebhookNotifier {
+            url: url.to_string(),
+        }
+    }
+}
+
+impl Notifier for WebhookNotifier {
+    fn notify(&self, content: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let url = &self.url;
+        if url.is_empty() || !url.starts_with("http") {
+            log::error!("invalid notifiy url {}! error {}", url, content);
+            return Ok(());
+        }
+
+        let mut easy = Easy::new();
+        easy.url(url)?;
+
+        let mut list = List::new();
+        list.append("Content-Type: application/json")?;
+        easy.http_headers(list)?;
+        easy.post(true)?;
+
+        let post_info = format!(
+            "{{\"msgtype\":\"markdown\", \"markdown\":{{\"content\":{}}}}}",
+            json_string(content)
+        );
+        let mut data = post_info.as_bytes();
+
+        easy.post_field_size(data.len() as u64)?;
+        let mut transfer = easy.transfer();
+        transfer.read_function(|buf| Ok(data.read(buf).unwrap_or(0)))?;
+        transfer.perform()?;
+        Ok(())
+    }
+}
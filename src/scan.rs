@@ -0,0 +1,169 @@
+/*
+Copyright (c) 2024- rickhan<rick.han@yahoo.com>
+
+Permission is hereby granted, free of charge, to any person obtaining
+a copy of this software and associated documentation files (the
+"Software"), to deal in the Software without restriction, including
+without limitation the rights to use, copy, modify, merge, publish,
+distribute, sublicense, and/or sell copies of the Software, and to
+permit persons to whom the Software is furnished to do so, subject to
+the following conditions:
+
+The above copyright notice and this permission notice shall be
+included in all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+*/
+
+//! finds the workbooks under the input directory
+
+use crate::xlsx::is_workbook;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// excel keeps a `~$name.xlsx` lock file next to every open workbook
+fn is_lock_file(name: &str) -> bool {
+    name.starts_with("~$")
+}
+
+fn match_from(p: &[char], s: &[char]) -> bool {
+    match p.first() {
+        None => s.is_empty(),
+        Some('*') if p.get(1) == Some(&'*') => {
+            let rest = &p[2..];
+            // `a/**/b` also matches `a/b`
+            if let Some(after) = rest.strip_prefix(&['/'][..]) {
+                if match_from(after, s) {
+                    return true;
+                }
+            }
+            (0..=s.len()).any(|i| match_from(rest, &s[i..]))
+        }
+        Some('*') => {
+            let mut i = 0;
+            loop {
+                if match_from(&p[1..], &s[i..]) {
+                    return true;
+                }
+                if i == s.len() || s[i] == '/' {
+                    return false;
+                }
+                i += 1;
+            }
+        }
+        Some('?') => !s.is_empty() && s[0] != '/' && match_from(&p[1..], &s[1..]),
+        Some(c) => s.first() == Some(c) && match_from(&p[1..], &s[1..]),
+    }
+}
+
+/// `*` and `?` do not cross folders, `**` matches any number of folders.
+/// a pattern without `/` is matched against the file name only
+pub fn glob_match(pattern: &str, path: &str) -> bool {
+    let target = if pattern.contains('/') {
+        path
+    } else {
+        path.rsplit('/').next().unwrap_or(path)
+    };
+    let p: Vec<char> = pattern.chars().collect();
+    let s: Vec<char> = target.chars().collect();
+    match_from(&p, &s)
+}
+
+/// a path is selected when it matches one of `include`, or `include` is
+/// empty, and it matches none of `exclude`
+pub fn is_selected(path: &str, include: &[String], exclude: &[String]) -> bool {
+    (include.is_empty() || include.iter().any(|p| glob_match(p, path)))
+        && !exclude.iter().any(|p| glob_match(p, path))
+}
+
+fn walk(root: &Path, dir: &str, files: &mut Vec<String>) -> io::Result<()> {
+    for entry in fs::read_dir(root.join(dir))? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        let rel = if dir.is_empty() {
+            name.clone()
+        } else {
+            format!("{}/{}", dir, name)
+        };
+        if entry.file_type()?.is_dir() {
+            walk(root, &rel, files)?;
+        } else if is_workbook(&name) && !is_lock_file(&name) {
+            files.push(rel);
+        }
+    }
+    Ok(())
+}
+
+/// workbooks under `input` as sorted `/` separated paths relative to it
+pub fn find_workbooks(
+    input: &str,
+    include: &[String],
+    exclude: &[String],
+) -> io::Result<Vec<String>> {
+    let mut files = vec![];
+    walk(Path::new(input), "", &mut files)?;
+    files.retain(|f| is_selected(f, include, exclude));
+    files.sort();
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_workbooks, glob_match, is_selected};
+    use std::fs;
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("*.xlsx", "item.xlsx"));
+        assert!(glob_match("*.xlsx", "battle/skill.xlsx"));
+        assert!(!glob_match("*.xlsx", "item.xls"));
+        assert!(glob_match("battle/*", "battle/skill.xlsx"));
+        assert!(!glob_match("battle/*", "battle/boss/skill.xlsx"));
+        assert!(glob_match("battle/**", "battle/boss/skill.xlsx"));
+        assert!(glob_match("**/skill.xlsx", "skill.xlsx"));
+        assert!(glob_match("a/**/b.xlsx", "a/b.xlsx"));
+        assert!(glob_match("a/**/b.xlsx", "a/x/y/b.xlsx"));
+        assert!(glob_match("item?.xlsx", "item1.xlsx"));
+        assert!(!glob_match("item?.xlsx", "item.xlsx"));
+
+        let include = vec!["battle/**".to_string()];
+        let exclude = vec!["test_*".to_string()];
+        assert!(is_selected("battle/skill.xlsx", &include, &exclude));
+        assert!(!is_selected("battle/test_skill.xlsx", &include, &exclude));
+        assert!(!is_selected("item.xlsx", &include, &exclude));
+        assert!(is_selected("item.xlsx", &[], &exclude));
+    }
+
+    #[test]
+    fn test_find_workbooks() {
+        let root = std::env::temp_dir().join("xlsxto_find_workbooks");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("battle/boss")).unwrap();
+        for f in [
+            "item.xlsx",
+            "~$item.xlsx",
+            "notes.txt",
+            "battle/skill.xls",
+            "battle/boss/boss.ods",
+        ] {
+            fs::write(root.join(f), "").unwrap();
+        }
+
+        let input = root.to_str().unwrap();
+        assert_eq!(
+            find_workbooks(input, &[], &[]).unwrap(),
+            vec!["battle/boss/boss.ods", "battle/skill.xls", "item.xlsx"]
+        );
+        assert_eq!(
+            find_workbooks(input, &[], &["battle/boss/**".to_string()]).unwrap(),
+            vec!["battle/skill.xls", "item.xlsx"]
+        );
+    }
+}
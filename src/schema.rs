@@ -0,0 +1,110 @@
+/*
+Copyright (c) 2024- rickhan<rick.han@yahoo.com>
+
+Permission is hereby granted, free of charge, to any person obtaining
+a copy of this software and associated documentation files (the
+"Software"), to deal in the Software without restriction, including
+without limitation the rights to use, copy, modify, merge, publish,
+distribute, sublicense, and/or sell copies of the Software, and to
+permit persons to whom the Software is furnished to do so, subject to
+the following conditions:
+
+The above copyright notice and this permission notice shall be
+included in all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+*/
+
+//! the field definitions parsed from the header rows, as printed by
+//! `xlsxto schema`
+
+use crate::error::json_string;
+use crate::xlsx::{XlsTabField, ALLXLSX};
+
+fn field_json(f: &XlsTabField) -> String {
+    format!(
+        "{{\"name\":{},\"type\":{},\"cn_name\":{},\"condition\":{},\"side\":{},\"key\":{}}}",
+        json_string(f.get_field_name()),
+        json_string(f.get_field_type()),
+        json_string(f.get_field_cn_name()),
+        json_string(f.get_condition()),
+        json_string(f.get_client_or_server()),
+        f.is_key_field()
+    )
+}
+
+/// one block per table, one line per field
+pub fn to_text(all: &ALLXLSX) -> String {
+    let mut blocks = vec![];
+    for (name, xlsx) in all.tables() {
+        let mut block = name.clone();
+        for f in xlsx.fields_list() {
+            block += &format!("\n  {} {}", f.get_field_name(), f.get_field_type());
+            if f.is_key_field() {
+                block += " KEY";
+            }
+            for (label, v) in [
+                ("中文名", f.get_field_cn_name()),
+                ("校验", f.get_condition()),
+                ("导出", f.get_client_or_server()),
+            ] {
+                if !v.is_empty() {
+                    block += &format!(" {}:{}", label, v);
+                }
+            }
+        }
+        blocks.push(block);
+    }
+    blocks.join("\n\n")
+}
+
+/// `{"table": [{"name": .., "type": .., ..}]}`
+pub fn to_json(all: &ALLXLSX) -> String {
+    let tables: Vec<String> = all
+        .tables()
+        .into_iter()
+        .map(|(name, xlsx)| {
+            let fields: Vec<String> = xlsx.fields_list().iter().map(field_json).collect();
+            format!(
+                "  {}: [\n    {}\n  ]",
+                json_string(name),
+                fields.join(",\n    ")
+            )
+        })
+        .collect();
+    format!("{{\n{}\n}}", tables.join(",\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{to_json, to_text};
+    use crate::xlsx::{XlsTabField, ALLXLSX, XLSX};
+
+    #[test]
+    fn test_schema() {
+        let mut xlsx = XLSX::new();
+        for (name, t, cn) in [("KEY_id", "int!key", "编号"), ("name", "string", "")] {
+            let mut f = XlsTabField::new();
+            f.set_field_name(name.to_string());
+            f.set_field_type(t.to_string());
+            f.set_field_cn_name(cn);
+            xlsx.add_field(f);
+        }
+        let mut all = ALLXLSX::new();
+        all.add("item.xlsx", xlsx);
+
+        assert_eq!(
+            to_text(&all),
+            "item.xlsx\n  KEY_id int KEY 中文名:编号\n  name string"
+        );
+        assert!(to_json(&all).contains(
+            r#"{"name":"KEY_id","type":"int","cn_name":"编号","condition":"","side":"","key":true}"#
+        ));
+    }
+}
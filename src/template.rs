@@ -0,0 +1,235 @@
+/*
+Copyright (c) 2024- rickhan<rick.han@yahoo.com>
+
+Permission is hereby granted, free of charge, to any person obtaining
+a copy of this software and associated documentation files (the
+"Software"), to deal in the Software without restriction, including
+without limitation the rights to use, copy, modify, merge, publish,
+distribute, sublicense, and/or sell copies of the Software, and to
+permit persons to whom the Software is furnished to do so, subject to
+the following conditions:
+
+The above copyright notice and this permission notice shall be
+included in all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+*/
+
+//! the example workbook written by `xlsxto init`, and a minimal xlsx writer
+//! for it since calamine only reads workbooks
+
+use crate::cell::CellValue;
+use crate::diagnostics::column_letter;
+use crate::enums::ENUM_SHEET_NAME;
+use crate::error::Error;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use zip::write::FileOptions;
+use zip::ZipWriter;
+
+/// a sheet name and its rows
+pub type Sheet = (String, Vec<Vec<CellValue>>);
+
+fn row(cells: &[&str]) -> Vec<CellValue> {
+    cells.iter().map(|c| CellValue::from(*c)).collect()
+}
+
+/// a table in the default header layout showing the common field types,
+/// checks and sides, with the enum it uses
+pub fn example_sheets() -> Vec<Sheet> {
+    let data = vec![
+        row(&["编号", "名字", "品质", "价格", "奖励", "备注"]),
+        row(&["id", "name", "quality", "price", "rewards", "remark"]),
+        row(&["gt(0)", "len(1,20)", "", "ge(0)", "", ""]),
+        row(&["", "", "c", "s", "", "none"]),
+        row(&[
+            "int!key",
+            "string",
+            "enum<Quality>",
+            "int",
+            "{id:int,count:int}[]",
+            "string",
+        ]),
+        vec![
+            CellValue::Int(1001),
+            CellValue::from("木剑"),
+            CellValue::from("WHITE"),
+            CellValue::Int(100),
+            CellValue::from("{id:2001,count:1}"),
+            CellValue::from("新手武器"),
+        ],
+        vec![
+            CellValue::Int(1002),
+            CellValue::from("铁剑"),
+            CellValue::from("GREEN"),
+            CellValue::Int(500),
+            CellValue::from("{2001,2},{2002,1}"),
+            CellValue::Empty,
+        ],
+    ];
+    let enums = vec![
+        row(&["枚举名", "标签", "值", "说明"]),
+        vec![
+            CellValue::from("Quality"),
+            CellValue::from("WHITE"),
+            CellValue::Int(1),
+            CellValue::from("白色"),
+        ],
+        vec![
+            CellValue::Empty,
+            CellValue::from("GREEN"),
+            CellValue::Int(2),
+            CellValue::from("绿色"),
+        ],
+    ];
+    vec![
+        ("Sheet1".to_string(), data),
+        (ENUM_SHEET_NAME.to_string(), enums),
+    ]
+}
+
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn sheet_xml(rows: &[Vec<CellValue>]) -> String {
+    let mut xml = String::from(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main"><sheetData>"#,
+    );
+    for (r, cells) in rows.iter().enumerate() {
+        xml += &format!(r#"<row r="{}">"#, r + 1);
+        for (c, cell) in cells.iter().enumerate() {
+            let at = format!("{}{}", column_letter(c), r + 1);
+            xml += &match cell {
+                CellValue::Empty => continue,
+                CellValue::Int(_) | CellValue::Float(_) | CellValue::DateTime(_) => {
+                    format!(r#"<c r="{}"><v>{}</v></c>"#, at, cell)
+                }
+                CellValue::Bool(b) => format!(r#"<c r="{}" t="b"><v>{}</v></c>"#, at, *b as u8),
+                CellValue::String(_) | CellValue::Error(_) => format!(
+                    r#"<c r="{}" t="inlineStr"><is><t>{}</t></is></c>"#,
+                    at,
+                    escape_xml(&cell.to_string())
+                ),
+            };
+        }
+        xml += "</row>";
+    }
+    xml += "</sheetData></worksheet>";
+    xml
+}
+
+fn workbook_parts(sheets: &[Sheet]) -> Vec<(String, String)> {
+    let mut overrides = String::new();
+    let mut entries = String::new();
+    let mut rels = String::new();
+    let mut parts = vec![];
+    for (i, (name, rows)) in sheets.iter().enumerate() {
+        let n = i + 1;
+        overrides += &format!(
+            r#"<Override PartName="/xl/worksheets/sheet{}.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/>"#,
+            n
+        );
+        entries += &format!(
+            r#"<sheet name="{}" sheetId="{}" r:id="rId{}"/>"#,
+            escape_xml(name),
+            n,
+            n
+        );
+        rels += &format!(
+            r#"<Relationship Id="rId{}" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet{}.xml"/>"#,
+            n, n
+        );
+        parts.push((format!("xl/worksheets/sheet{}.xml", n), sheet_xml(rows)));
+    }
+
+    let head = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#;
+    parts.push((
+        "[Content_Types].xml".to_string(),
+        format!(
+            r#"{}<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Override PartName="/xl/workbook.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml"/>{}</Types>"#,
+            head, overrides
+        ),
+    ));
+    parts.push((
+        "_rels/.rels".to_string(),
+        format!(
+            r#"{}<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="xl/workbook.xml"/></Relationships>"#,
+            head
+        ),
+    ));
+    parts.push((
+        "xl/workbook.xml".to_string(),
+        format!(
+            r#"{}<workbook xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships"><sheets>{}</sheets></workbook>"#,
+            head, entries
+        ),
+    ));
+    parts.push((
+        "xl/_rels/workbook.xml.rels".to_string(),
+        format!(
+            r#"{}<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">{}</Relationships>"#,
+            head, rels
+        ),
+    ));
+    parts
+}
+
+/// writes `sheets` as an xlsx workbook to `path`
+pub fn write_workbook(path: &str, sheets: &[Sheet]) -> Result<(), Error> {
+    if let Some(dir) = Path::new(path).parent() {
+        fs::create_dir_all(dir).map_err(|e| Error::io(path, e))?;
+    }
+    let file = fs::File::create(path).map_err(|e| Error::io(path, e))?;
+    let mut zip = ZipWriter::new(file);
+    let to_error = |e: zip::result::ZipError| Error::Workbook {
+        file: path.to_string(),
+        message: e.to_string(),
+    };
+    for (name, content) in workbook_parts(sheets) {
+        zip.start_file(name, FileOptions::default())
+            .map_err(to_error)?;
+        zip.write_all(content.as_bytes())
+            .map_err(|e| Error::io(path, e))?;
+    }
+    zip.finish().map_err(to_error)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::example_sheets;
+    use crate::fixture;
+    use crate::layout::HeaderLayout;
+    use crate::{Diagnostics, ExportContext, ALLXLSX};
+
+    #[test]
+    fn test_example_workbook() {
+        let books = [("item.xlsx", example_sheets())];
+        let tables = fixture::parse("xlsxto_template", &books, &HeaderLayout::default());
+        assert_eq!(tables.len(), 1);
+        let mut all = ALLXLSX::new();
+        for (name, xlsx) in tables {
+            // the remark column is not exported
+            assert_eq!(xlsx.field_num(), 5);
+            assert_eq!(xlsx.value_list().len(), 2);
+            assert_eq!(xlsx.enum_list()[0].value_of("GREEN"), Some(2));
+            all.add(&name, xlsx);
+        }
+
+        let mut diagnostics = Diagnostics::new();
+        all.check_xlsx_valid(&ExportContext::new(""), &mut diagnostics);
+        assert!(diagnostics.is_empty());
+    }
+}
@@ -0,0 +1,803 @@
+/*
+Copyright (c) 2024- rickhan<rick.han@yahoo.com>
+
+Permission is hereby granted, free of charge, to any person obtaining
+a copy of this software and associated documentation files (the
+"Software"), to deal in the Software without restriction, including
+without limitation the rights to use, copy, modify, merge, publish,
+distribute, sublicense, and/or sell copies of the Software, and to
+permit persons to whom the Software is furnished to do so, subject to
+the following conditions:
+
+The above copyright notice and this permission notice shall be
+included in all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+*/
+use crate::cell::{format_number, CellValue};
+use crate::datetime::{
+    format_date, format_datetime, parse_date, parse_datetime, parse_duration, serial_to_seconds,
+};
+use crate::enums::EnumLookup;
+use crate::error::json_string;
+use std::fmt;
+
+/// a parsed field type such as `int`, `string[]`, `int[][]`, `(int,float)[]`
+/// or `{id:int,count:int}[]`
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldType {
+    Int,
+    Long,
+    Float,
+    String,
+    Bool,
+    Date,
+    DateTime,
+    Duration,
+    /// unknown scalar type, e.g. `localize`, exported as typed
+    Custom(String),
+    /// `enum<Name>`, labels are exported as their numeric values
+    Enum(String),
+    Array(Box<FieldType>),
+    Tuple(Vec<FieldType>),
+    Struct(Vec<(String, FieldType)>),
+}
+
+/// a cell parsed according to its `FieldType`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Value {
+    /// number in canonical form, `007` is kept as `7` and `.5` as `0.5`
+    Number(String),
+    Str(String),
+    Bool(bool),
+    /// unix seconds at midnight
+    Date(i64),
+    /// unix seconds
+    DateTime(i64),
+    /// seconds
+    Duration(i64),
+    /// empty date or datetime cell
+    Nil,
+    /// value of a custom type, exported verbatim
+    Raw(String),
+    List(Vec<Value>),
+    Tuple(Vec<Value>),
+    /// fields in the order of the struct type
+    Struct(Vec<(String, Value)>),
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+struct TypeParser<'a> {
+    chars: Vec<char>,
+    pos: usize,
+    src: &'a str,
+}
+
+impl<'a> TypeParser<'a> {
+    fn skip_space(&mut self) {
+        while self.pos < self.chars.len() && self.chars[self.pos].is_whitespace() {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_space();
+        self.chars.get(self.pos).copied()
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            return Ok(());
+        }
+        Err(format!(
+            "类型{}的第{}个字符应为'{}'",
+            self.src,
+            self.pos + 1,
+            c
+        ))
+    }
+
+    fn ident(&mut self) -> Result<String, String> {
+        self.skip_space();
+        let start = self.pos;
+        while self.pos < self.chars.len() && is_ident_char(self.chars[self.pos]) {
+            self.pos += 1;
+        }
+        if start == self.pos {
+            return Err(format!(
+                "类型{}的第{}个字符应为字段名",
+                self.src,
+                self.pos + 1
+            ));
+        }
+        Ok(self.chars[start..self.pos].iter().collect())
+    }
+
+    fn parse_type(&mut self) -> Result<FieldType, String> {
+        let mut t = match self.peek() {
+            Some('(') => {
+                self.pos += 1;
+                let mut items = vec![self.parse_type()?];
+                while self.peek() == Some(',') {
+                    self.pos += 1;
+                    items.push(self.parse_type()?);
+                }
+                self.expect(')')?;
+                FieldType::Tuple(items)
+            }
+            Some('{') => {
+                self.pos += 1;
+                let mut fields: Vec<(String, FieldType)> = vec![];
+                loop {
+                    let name = self.ident()?;
+                    if fields.iter().any(|(n, _)| *n == name) {
+                        return Err(format!("类型{}的字段{}重复", self.src, name));
+                    }
+                    self.expect(':')?;
+                    fields.push((name, self.parse_type()?));
+                    if self.peek() != Some(',') {
+                        break;
+                    }
+                    self.pos += 1;
+                }
+                self.expect('}')?;
+                FieldType::Struct(fields)
+            }
+            Some(c) if c.is_alphabetic() || c == '_' => {
+                let name = self.ident()?;
+                match name.as_str() {
+                    "enum" => {
+                        self.expect('<')?;
+                        let name = self.ident()?;
+                        self.expect('>')?;
+                        FieldType::Enum(name)
+                    }
+                    "int" => FieldType::Int,
+                    "long" => FieldType::Long,
+                    "float" => FieldType::Float,
+                    "string" => FieldType::String,
+                    "bool" => FieldType::Bool,
+                    "date" => FieldType::Date,
+                    "datetime" => FieldType::DateTime,
+                    "duration" => FieldType::Duration,
+                    _ => FieldType::Custom(name),
+                }
+            }
+            _ => return Err(format!("类型{}的第{}个字符不合法", self.src, self.pos + 1)),
+        };
+
+        while self.peek() == Some('[') {
+            self.pos += 1;
+            self.expect(']')?;
+            t = FieldType::Array(Box::new(t));
+        }
+        Ok(t)
+    }
+}
+
+struct ValueParser<'a> {
+    chars: Vec<char>,
+    pos: usize,
+    enums: &'a dyn EnumLookup,
+}
+
+impl<'a> ValueParser<'a> {
+    fn skip_space(&mut self) {
+        while self.pos < self.chars.len() && self.chars[self.pos].is_whitespace() {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_space();
+        self.chars.get(self.pos).copied()
+    }
+
+    fn error(&self, what: &str) -> String {
+        format!("第{}个字符{}", self.pos + 1, what)
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            return Ok(());
+        }
+        Err(self.error(&format!("应为'{}'", c)))
+    }
+
+    fn at_end(&mut self) -> bool {
+        self.peek().is_none()
+    }
+
+    // `name:` in front of a struct field, the position is kept if there is none
+    fn field_name(&mut self) -> Option<String> {
+        self.skip_space();
+        let start = self.pos;
+        let mut end = start;
+        while end < self.chars.len() && is_ident_char(self.chars[end]) {
+            end += 1;
+        }
+        let mut colon = end;
+        while colon < self.chars.len() && self.chars[colon].is_whitespace() {
+            colon += 1;
+        }
+        if end == start || self.chars.get(colon) != Some(&':') {
+            return None;
+        }
+        self.pos = colon + 1;
+        Some(self.chars[start..end].iter().collect())
+    }
+
+    // text up to the next `,`, `]`, `)` or `}`, or a quoted string
+    fn token(&mut self) -> Result<(String, bool), String> {
+        match self.peek() {
+            Some(q) if q == '"' || q == '\'' => {
+                self.pos += 1;
+                let mut s = String::new();
+                while self.pos < self.chars.len() {
+                    let c = self.chars[self.pos];
+                    self.pos += 1;
+                    if c == q {
+                        return Ok((s, true));
+                    }
+                    if c == '\\' && self.pos < self.chars.len() {
+                        let n = self.chars[self.pos];
+                        if n == q || n == '\\' {
+                            self.pos += 1;
+                            s.push(n);
+                            continue;
+                        }
+                    }
+                    s.push(c);
+                }
+                Err(self.error("处字符串缺少结束引号"))
+            }
+            _ => {
+                let start = self.pos;
+                while self.pos < self.chars.len() && !",])}".contains(self.chars[self.pos]) {
+                    self.pos += 1;
+                }
+                let s: String = self.chars[start..self.pos].iter().collect();
+                Ok((s.trim().to_string(), false))
+            }
+        }
+    }
+
+    fn parse(&mut self, t: &FieldType) -> Result<Value, String> {
+        match t {
+            FieldType::Array(inner) => {
+                self.expect('[')?;
+                let mut items = vec![];
+                if self.peek() == Some(']') {
+                    self.pos += 1;
+                    return Ok(Value::List(items));
+                }
+                loop {
+                    items.push(self.parse(inner)?);
+                    match self.peek() {
+                        Some(',') => self.pos += 1,
+                        Some(']') => {
+                            self.pos += 1;
+                            return Ok(Value::List(items));
+                        }
+                        _ => return Err(self.error("应为','或']'")),
+                    }
+                }
+            }
+            FieldType::Tuple(types) => {
+                let close = match self.peek() {
+                    Some('(') => ')',
+                    Some('[') => ']',
+                    _ => return Err(self.error("应为'('")),
+                };
+                self.pos += 1;
+                let mut items = vec![];
+                for (i, inner) in types.iter().enumerate() {
+                    if i > 0 {
+                        self.expect(',')?;
+                    }
+                    items.push(self.parse(inner)?);
+                }
+                self.expect(close)?;
+                Ok(Value::Tuple(items))
+            }
+            FieldType::Struct(fields) => {
+                self.expect('{')?;
+                let mut values: Vec<Option<Value>> = fields.iter().map(|_| None).collect();
+                let mut next = 0;
+                if self.peek() != Some('}') {
+                    loop {
+                        // fields are either named like `id:1` or given in order
+                        let i = match self.field_name() {
+                            Some(name) => match fields.iter().position(|(n, _)| *n == name) {
+                                Some(i) => i,
+                                None => return Err(self.error(&format!("处没有字段{}", name))),
+                            },
+                            None => next,
+                        };
+                        if i >= fields.len() {
+                            return Err(self.error("处字段数量过多"));
+                        }
+                        if values[i].is_some() {
+                            return Err(self.error(&format!("处字段{}重复", fields[i].0)));
+                        }
+                        values[i] = Some(self.parse(&fields[i].1)?);
+                        next = i + 1;
+                        if self.peek() != Some(',') {
+                            break;
+                        }
+                        self.pos += 1;
+                    }
+                }
+                self.expect('}')?;
+                Ok(Value::Struct(
+                    fields
+                        .iter()
+                        .zip(values)
+                        .map(|((name, t), v)| {
+                            (name.clone(), v.unwrap_or_else(|| t.default_value()))
+                        })
+                        .collect(),
+                ))
+            }
+            _ => {
+                let (s, quoted) = self.token()?;
+                scalar_value(t, &s, quoted, self.enums)
+            }
+        }
+    }
+}
+
+fn scalar_value(
+    t: &FieldType,
+    s: &str,
+    quoted: bool,
+    enums: &dyn EnumLookup,
+) -> Result<Value, String> {
+    match t {
+        FieldType::Int | FieldType::Long => match s.parse::<i64>() {
+            Ok(n) if !quoted => Ok(Value::Number(n.to_string())),
+            _ => Err(format!("{}不是整数", s)),
+        },
+        FieldType::Float => match s.parse::<f64>() {
+            Ok(f) if !quoted && f.is_finite() => Ok(Value::Number(format_number(f))),
+            _ => Err(format!("{}不是数字", s)),
+        },
+        FieldType::String => Ok(Value::Str(s.to_string())),
+        FieldType::Bool => match s.to_lowercase().as_str() {
+            "true" | "1" | "yes" | "y" | "是" if !quoted => Ok(Value::Bool(true)),
+            "false" | "0" | "no" | "n" | "否" if !quoted => Ok(Value::Bool(false)),
+            _ => Err(format!("{}不是布尔值，应为true或false", s)),
+        },
+        FieldType::Date => Ok(Value::Date(parse_date(s)?)),
+        FieldType::DateTime => Ok(Value::DateTime(parse_datetime(s)?)),
+        FieldType::Duration => Ok(Value::Duration(parse_duration(s)?)),
+        FieldType::Enum(name) => {
+            if s.is_empty() {
+                return Ok(Value::Number("0".to_string()));
+            }
+            let v = enums.enum_value(name, s)?;
+            Ok(Value::Number(v.to_string()))
+        }
+        _ => Ok(Value::Raw(s.to_string())),
+    }
+}
+
+impl FieldType {
+    pub fn parse(s: &str) -> Result<FieldType, String> {
+        let mut p = TypeParser {
+            chars: s.chars().collect(),
+            pos: 0,
+            src: s,
+        };
+        let t = p.parse_type()?;
+        if p.peek().is_some() {
+            return Err(format!("类型{}的第{}个字符不合法", s, p.pos + 1));
+        }
+        Ok(t)
+    }
+
+    pub fn is_number(&self) -> bool {
+        matches!(self, FieldType::Int | FieldType::Long | FieldType::Float)
+    }
+
+    /// whether a date, datetime or duration is part of the type
+    pub fn has_time(&self) -> bool {
+        match self {
+            FieldType::Date | FieldType::DateTime | FieldType::Duration => true,
+            FieldType::Array(inner) => inner.has_time(),
+            FieldType::Tuple(types) => types.iter().any(|t| t.has_time()),
+            FieldType::Struct(fields) => fields.iter().any(|(_, t)| t.has_time()),
+            _ => false,
+        }
+    }
+
+    /// names of the enums used by this type
+    pub fn enum_names(&self) -> Vec<String> {
+        match self {
+            FieldType::Enum(name) => vec![name.clone()],
+            FieldType::Array(inner) => inner.enum_names(),
+            FieldType::Tuple(types) => types.iter().flat_map(|t| t.enum_names()).collect(),
+            FieldType::Struct(fields) => fields.iter().flat_map(|(_, t)| t.enum_names()).collect(),
+            _ => vec![],
+        }
+    }
+
+    /// parses a typed workbook cell, most cells are parsed from the text
+    /// excel shows for them, see `parse_value`
+    pub fn parse_cell(&self, cell: &CellValue, enums: &dyn EnumLookup) -> Result<Value, String> {
+        match (self, cell) {
+            (_, CellValue::Error(e)) => Err(format!("单元格的值是错误{}", e)),
+            (FieldType::Bool, CellValue::Bool(b)) => Ok(Value::Bool(*b)),
+            // a time formatted cell is a fraction of a day
+            (FieldType::Duration, CellValue::DateTime(f)) => {
+                Ok(Value::Duration(serial_to_seconds(*f)))
+            }
+            _ => self.parse_value(&cell.to_string(), enums),
+        }
+    }
+
+    /// parses a cell, the outer brackets of arrays, tuples and structs may be
+    /// left out, e.g. `1,2,3` for `int[]` or `[1,2],[3]` for `int[][]`.
+    /// enum labels are resolved by `enums`
+    pub fn parse_value(&self, cell: &str, enums: &dyn EnumLookup) -> Result<Value, String> {
+        let opener = match self {
+            FieldType::Array(_) => '[',
+            FieldType::Tuple(_) => '(',
+            FieldType::Struct(_) => '{',
+            FieldType::String | FieldType::Custom(_) => {
+                return scalar_value(self, cell, false, enums)
+            }
+            _ => {
+                let s = cell.trim();
+                if s.is_empty() {
+                    return Ok(self.default_value());
+                }
+                return scalar_value(self, s, false, enums);
+            }
+        };
+
+        let s = cell.trim();
+        if s.is_empty() {
+            return Ok(self.default_value());
+        }
+
+        let direct = parse_all(self, s, enums);
+        if direct.is_ok() {
+            return direct;
+        }
+        let wrapped = parse_all(self, &format!("{}{}{}", opener, s, closer(opener)), enums);
+        if wrapped.is_ok() || !s.starts_with(opener) {
+            return wrapped;
+        }
+        direct
+    }
+
+    /// value exported for an empty cell
+    pub fn default_value(&self) -> Value {
+        match self {
+            FieldType::Int | FieldType::Long | FieldType::Float | FieldType::Enum(_) => {
+                Value::Number("0".to_string())
+            }
+            FieldType::String => Value::Str(String::new()),
+            FieldType::Bool => Value::Bool(false),
+            FieldType::Date | FieldType::DateTime => Value::Nil,
+            FieldType::Duration => Value::Duration(0),
+            FieldType::Custom(_) => Value::Raw(String::new()),
+            FieldType::Array(_) => Value::List(vec![]),
+            FieldType::Tuple(types) => {
+                Value::Tuple(types.iter().map(|t| t.default_value()).collect())
+            }
+            FieldType::Struct(fields) => Value::Struct(
+                fields
+                    .iter()
+                    .map(|(name, t)| (name.clone(), t.default_value()))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+fn parse_all(t: &FieldType, s: &str, enums: &dyn EnumLookup) -> Result<Value, String> {
+    let mut p = ValueParser {
+        chars: s.chars().collect(),
+        pos: 0,
+        enums,
+    };
+    let v = p.parse(t)?;
+    if !p.at_end() {
+        return Err(p.error("处有多余的内容"));
+    }
+    Ok(v)
+}
+
+fn closer(opener: char) -> char {
+    match opener {
+        '(' => ')',
+        '{' => '}',
+        _ => ']',
+    }
+}
+
+impl fmt::Display for FieldType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldType::Int => write!(f, "int"),
+            FieldType::Long => write!(f, "long"),
+            FieldType::Float => write!(f, "float"),
+            FieldType::String => write!(f, "string"),
+            FieldType::Bool => write!(f, "bool"),
+            FieldType::Date => write!(f, "date"),
+            FieldType::DateTime => write!(f, "datetime"),
+            FieldType::Duration => write!(f, "duration"),
+            FieldType::Custom(name) => write!(f, "{}", name),
+            FieldType::Enum(name) => write!(f, "enum<{}>", name),
+            FieldType::Array(inner) => write!(f, "{}[]", inner),
+            FieldType::Tuple(types) => {
+                let names: Vec<String> = types.iter().map(|t| t.to_string()).collect();
+                write!(f, "({})", names.join(","))
+            }
+            FieldType::Struct(fields) => {
+                let names: Vec<String> = fields
+                    .iter()
+                    .map(|(name, t)| format!("{}:{}", name, t))
+                    .collect();
+                write!(f, "{{{}}}", names.join(","))
+            }
+        }
+    }
+}
+
+impl Value {
+    /// number of items of a list, tuple or struct, characters of a string
+    pub fn len(&self) -> usize {
+        match self {
+            Value::List(items) | Value::Tuple(items) => items.len(),
+            Value::Struct(fields) => fields.len(),
+            Value::Number(s) | Value::Str(s) | Value::Raw(s) => s.len(),
+            Value::Bool(_)
+            | Value::Date(_)
+            | Value::DateTime(_)
+            | Value::Duration(_)
+            | Value::Nil => 0,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// json text of the value, a typed `\n` in strings becomes a line break
+    pub fn to_json(&self) -> String {
+        match self {
+            Value::Number(s) => s.clone(),
+            Value::Str(s) => json_string(&s.replace('\r', "").replace("\\n", "\n")),
+            Value::Bool(b) => b.to_string(),
+            Value::Date(secs) => json_string(&format_date(*secs)),
+            Value::DateTime(secs) => json_string(&format_datetime(*secs, 'T')),
+            Value::Duration(secs) => secs.to_string(),
+            Value::Nil => "null".to_string(),
+            Value::Raw(s) => {
+                if s.trim().is_empty() {
+                    "null".to_string()
+                } else {
+                    s.clone()
+                }
+            }
+            Value::List(items) | Value::Tuple(items) => {
+                let items: Vec<String> = items.iter().map(|v| v.to_json()).collect();
+                format!("[{}]", items.join(","))
+            }
+            Value::Struct(fields) => {
+                let fields: Vec<String> = fields
+                    .iter()
+                    .map(|(name, v)| format!("{}:{}", json_string(name), v.to_json()))
+                    .collect();
+                format!("{{{}}}", fields.join(","))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FieldType, Value};
+    use crate::cell::CellValue;
+    use crate::enums::{AnyEnum, EnumLookup};
+
+    struct Quality;
+
+    impl EnumLookup for Quality {
+        fn enum_value(&self, name: &str, label: &str) -> Result<i64, String> {
+            match (name, label) {
+                ("Quality", "WHITE") => Ok(1),
+                ("Quality", "GREEN") => Ok(2),
+                _ => Err(format!("枚举{}没有{}", name, label)),
+            }
+        }
+    }
+
+    fn num(s: &str) -> Value {
+        Value::Number(s.to_string())
+    }
+
+    #[test]
+    fn test_parse_type() {
+        assert_eq!(FieldType::parse("int").unwrap(), FieldType::Int);
+        assert_eq!(
+            FieldType::parse("int[][]").unwrap(),
+            FieldType::Array(Box::new(FieldType::Array(Box::new(FieldType::Int))))
+        );
+        assert_eq!(
+            FieldType::parse("(int, float)[]").unwrap().to_string(),
+            "(int,float)[]"
+        );
+        assert_eq!(
+            FieldType::parse("localize").unwrap(),
+            FieldType::Custom("localize".to_string())
+        );
+        assert!(FieldType::parse("int[").is_err());
+        assert!(FieldType::parse("(int,)").is_err());
+        assert!(FieldType::parse("int float").is_err());
+    }
+
+    #[test]
+    fn test_parse_value() {
+        let t = FieldType::parse("int[]").unwrap();
+        assert_eq!(
+            t.parse_value("1, 2,3", &AnyEnum).unwrap(),
+            Value::List(vec![num("1"), num("2"), num("3")])
+        );
+        assert_eq!(
+            t.parse_value("[1,2]", &AnyEnum).unwrap(),
+            Value::List(vec![num("1"), num("2")])
+        );
+        assert_eq!(t.parse_value("", &AnyEnum).unwrap(), Value::List(vec![]));
+        assert!(t.parse_value("[1,a]", &AnyEnum).is_err());
+        assert!(t.parse_value("[1,2],[3]", &AnyEnum).is_err());
+
+        let t = FieldType::parse("int[][]").unwrap();
+        let v = Value::List(vec![
+            Value::List(vec![num("1"), num("2")]),
+            Value::List(vec![num("3")]),
+        ]);
+        assert_eq!(t.parse_value("[[1,2],[3]]", &AnyEnum).unwrap(), v);
+        assert_eq!(t.parse_value("[1,2],[3]", &AnyEnum).unwrap(), v);
+
+        let t = FieldType::parse("string[]").unwrap();
+        assert_eq!(
+            t.parse_value("[\"a,b\", c ,'d\\'e']", &AnyEnum).unwrap(),
+            Value::List(vec![
+                Value::Str("a,b".to_string()),
+                Value::Str("c".to_string()),
+                Value::Str("d'e".to_string())
+            ])
+        );
+
+        let t = FieldType::parse("(int,float)[]").unwrap();
+        assert_eq!(
+            t.parse_value("(1,2.5),(2,0.1)", &AnyEnum)
+                .unwrap()
+                .to_json(),
+            "[[1,2.5],[2,0.1]]"
+        );
+        assert!(t.parse_value("(1,2.5,3)", &AnyEnum).is_err());
+
+        let t = FieldType::parse("{id:int, count:int}[]").unwrap();
+        assert_eq!(t.to_string(), "{id:int,count:int}[]");
+        assert_eq!(
+            t.parse_value("{id:1001,count:5},{count:2, id:1002},{1003}", &AnyEnum)
+                .unwrap()
+                .to_json(),
+            "[{\"id\":1001,\"count\":5},{\"id\":1002,\"count\":2},{\"id\":1003,\"count\":0}]"
+        );
+        assert_eq!(
+            t.parse_value("{id:1,count:2},{id:3}", &AnyEnum)
+                .unwrap()
+                .len(),
+            2
+        );
+        assert!(t.parse_value("{id:1,num:2}", &AnyEnum).is_err());
+        assert!(t.parse_value("{id:1,id:2}", &AnyEnum).is_err());
+        assert!(t.parse_value("{1,2,3}", &AnyEnum).is_err());
+        assert!(FieldType::parse("{id:int,id:int}").is_err());
+        assert!(FieldType::parse("{}").is_err());
+
+        let t = FieldType::parse("enum<Quality>[]").unwrap();
+        assert_eq!(t.to_string(), "enum<Quality>[]");
+        assert_eq!(
+            t.parse_value("WHITE, GREEN", &Quality).unwrap(),
+            Value::List(vec![num("1"), num("2")])
+        );
+        assert!(t.parse_value("WHITE,RED", &Quality).is_err());
+        assert!(FieldType::parse("enum<>").is_err());
+
+        let t = FieldType::parse("(bool,date,duration)").unwrap();
+        assert_eq!(
+            t.parse_value("是, 2024-02-29, 1h30m", &AnyEnum).unwrap(),
+            Value::Tuple(vec![
+                Value::Bool(true),
+                Value::Date(1709164800),
+                Value::Duration(5400)
+            ])
+        );
+        assert!(FieldType::Bool.parse_value("maybe", &AnyEnum).is_err());
+        assert_eq!(
+            FieldType::DateTime.parse_value("", &AnyEnum).unwrap(),
+            Value::Nil
+        );
+
+        let t = FieldType::Float;
+        assert_eq!(
+            t.parse_cell(&CellValue::Float(0.1), &AnyEnum).unwrap(),
+            num("0.1")
+        );
+        assert_eq!(
+            t.parse_cell(&CellValue::Float(0.1 + 0.2), &AnyEnum)
+                .unwrap(),
+            num("0.3")
+        );
+        let t = FieldType::Int;
+        assert_eq!(
+            t.parse_cell(&CellValue::Float(1.0), &AnyEnum).unwrap(),
+            num("1")
+        );
+        assert_eq!(
+            t.parse_cell(&CellValue::Int(7), &AnyEnum).unwrap(),
+            num("7")
+        );
+        assert!(t.parse_cell(&CellValue::Float(1.5), &AnyEnum).is_err());
+        assert!(t
+            .parse_cell(&CellValue::Error("#DIV/0!".to_string()), &AnyEnum)
+            .is_err());
+        assert_eq!(
+            FieldType::String
+                .parse_cell(&CellValue::Float(1.0), &AnyEnum)
+                .unwrap(),
+            Value::Str("1".to_string())
+        );
+        assert_eq!(
+            FieldType::Duration
+                .parse_cell(&CellValue::DateTime(1.5 / 24.0), &AnyEnum)
+                .unwrap(),
+            Value::Duration(5400)
+        );
+
+        assert_eq!(FieldType::Int.parse_value(" ", &AnyEnum).unwrap(), num("0"));
+        assert!(FieldType::Int.parse_value("1.5", &AnyEnum).is_err());
+        assert_eq!(
+            FieldType::Int.parse_value("007", &AnyEnum).unwrap(),
+            num("7")
+        );
+        assert_eq!(
+            FieldType::Int.parse_value("+3", &AnyEnum).unwrap(),
+            num("3")
+        );
+        assert_eq!(
+            FieldType::Float.parse_value(".5", &AnyEnum).unwrap(),
+            num("0.5")
+        );
+        assert_eq!(
+            FieldType::Float.parse_value("1.", &AnyEnum).unwrap(),
+            num("1")
+        );
+        assert_eq!(
+            FieldType::Float.parse_value("-0.50", &AnyEnum).unwrap(),
+            num("-0.5")
+        );
+        assert_eq!(
+            FieldType::String.parse_value(" a,b ", &AnyEnum).unwrap(),
+            Value::Str(" a,b ".to_string())
+        );
+    }
+}
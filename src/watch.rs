@@ -0,0 +1,182 @@
+/*
+Copyright (c) 2024- rickhan<rick.han@yahoo.com>
+
+Permission is hereby granted, free of charge, to any person obtaining
+a copy of this software and associated documentation files (the
+"Software"), to deal in the Software without restriction, including
+without limitation the rights to use, copy, modify, merge, publish,
+distribute, sublicense, and/or sell copies of the Software, and to
+permit persons to whom the Software is furnished to do so, subject to
+the following conditions:
+
+The above copyright notice and this permission notice shall be
+included in all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+*/
+
+//! watch mode wakes up on platform file notifications, or polls the input
+//! tree where there are none, and compares file stats to see what changed.
+//! it waits for a save to settle before exporting because excel writes a temp
+//! file and renames it over the workbook
+
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+/// how often the input tree is polled, and checked while a save settles
+pub const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// longest wait for a notification before the input tree is checked anyway,
+/// in case the platform dropped one
+pub const IDLE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// modification time and size of each file
+pub type Snapshot = BTreeMap<String, (SystemTime, u64)>;
+
+/// stats `files` under `input`, files removed in the meantime are left out
+pub fn snapshot(input: &str, files: &[String]) -> Snapshot {
+    let mut snap = Snapshot::new();
+    for f in files {
+        if let Ok(meta) = fs::metadata(format!("{}/{}", input, f)) {
+            let modified = meta.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            snap.insert(f.clone(), (modified, meta.len()));
+        }
+    }
+    snap
+}
+
+/// wakes the watch loop when something under the input may have changed
+pub struct Changes {
+    // dropping the watcher stops the notifications
+    watcher: Option<(RecommendedWatcher, Receiver<()>)>,
+}
+
+impl Changes {
+    /// file notifications for `input`, polling when the platform has none
+    pub fn new(input: &str) -> Changes {
+        let (tx, rx) = channel();
+        let watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+            if event.is_ok() {
+                let _ = tx.send(());
+            }
+        })
+        .and_then(|mut w| {
+            w.watch(Path::new(input), RecursiveMode::Recursive)?;
+            Ok(w)
+        });
+        match watcher {
+            Ok(w) => Changes {
+                watcher: Some((w, rx)),
+            },
+            Err(e) => {
+                log::warn!("no file notifications for {}, polling: {}", input, e);
+                Changes { watcher: None }
+            }
+        }
+    }
+
+    /// blocks until a notification arrives or `timeout` passes, true on a
+    /// notification; polling sleeps `POLL_INTERVAL` and always reports one
+    pub fn wait(&self, timeout: Duration) -> bool {
+        let events = match &self.watcher {
+            Some((_, events)) => events,
+            None => {
+                thread::sleep(POLL_INTERVAL.min(timeout));
+                return true;
+            }
+        };
+        match events.recv_timeout(timeout) {
+            Ok(()) => {
+                // one save sends a burst of events
+                while events.try_recv().is_ok() {}
+                true
+            }
+            Err(RecvTimeoutError::Timeout) => false,
+            Err(RecvTimeoutError::Disconnected) => {
+                thread::sleep(POLL_INTERVAL.min(timeout));
+                true
+            }
+        }
+    }
+}
+
+/// reports a change once the tree has been quiet for `delay`
+pub struct Debouncer {
+    delay: Duration,
+    last: Snapshot,
+    changed_at: Option<Instant>,
+}
+
+impl Debouncer {
+    pub fn new(delay: Duration, snap: Snapshot) -> Debouncer {
+        Debouncer {
+            delay,
+            last: snap,
+            changed_at: None,
+        }
+    }
+
+    /// whether a change was seen that has not settled yet
+    pub fn is_settling(&self) -> bool {
+        self.changed_at.is_some()
+    }
+
+    /// feeds a new snapshot, true when a change settled and should be exported
+    pub fn poll(&mut self, snap: Snapshot, now: Instant) -> bool {
+        if snap != self.last {
+            self.last = snap;
+            self.changed_at = Some(now);
+            return false;
+        }
+        match self.changed_at {
+            Some(t) if now.duration_since(t) >= self.delay => {
+                self.changed_at = None;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Debouncer, Snapshot};
+    use std::time::{Duration, Instant, SystemTime};
+
+    fn snap(size: u64) -> Snapshot {
+        let mut s = Snapshot::new();
+        s.insert("item.xlsx".to_string(), (SystemTime::UNIX_EPOCH, size));
+        s
+    }
+
+    #[test]
+    fn test_debounce() {
+        let start = Instant::now();
+        let at = |ms: u64| start + Duration::from_millis(ms);
+        let mut d = Debouncer::new(Duration::from_millis(500), snap(1));
+        assert!(!d.poll(snap(1), at(200)));
+
+        // excel rewrites the file a few times while saving
+        assert!(!d.poll(snap(2), at(400)));
+        assert!(!d.poll(snap(3), at(600)));
+        assert!(!d.poll(snap(3), at(1000)));
+        assert!(d.poll(snap(3), at(1100)));
+        assert!(!d.poll(snap(3), at(2000)));
+
+        // saving by rename can briefly leave the file missing
+        assert!(!d.poll(Snapshot::new(), at(2200)));
+        assert!(!d.poll(snap(3), at(2400)));
+        assert!(d.poll(snap(3), at(2900)));
+    }
+}
@@ -20,24 +20,158 @@ LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
 OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
 WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
 */
-use calamine::{open_workbook, Reader, Xlsx};
-use std::collections::HashMap;
-use std::io::{Error, ErrorKind};
+use calamine::{open_workbook_auto, DataType, Range, Reader};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
 
 use super::checker::{generate_checker, CheckObj};
-use crate::generator::Generator;
-use crate::get_project_name;
+use crate::cell::CellValue;
+use crate::context::ExportContext;
+use crate::diagnostics::{column_letter, Diagnostics};
+use crate::enums::{parse_enum_rows, EnumDef, EnumLookup, ENUM_SHEET_NAME};
+use crate::error::{Error, Location};
+use crate::generator::{exported_names, shared_names, Generator};
+use crate::layout::{HeaderLayout, META_SHEET_NAME};
+use crate::pool::par_map;
+use crate::types::{FieldType, Value};
+
+/// extensions of the workbooks that can be read
+pub const WORKBOOK_EXTENSIONS: [&str; 5] = ["xlsx", "xlsm", "xlsb", "xls", "ods"];
+
+pub fn is_workbook(fname: &str) -> bool {
+    match Path::new(fname).extension().and_then(|e| e.to_str()) {
+        Some(ext) => WORKBOOK_EXTENSIONS.contains(&ext.to_lowercase().as_str()),
+        None => false,
+    }
+}
+
+/// sheets starting with `#` are scratch space and never exported
+pub const SCRATCH_SHEET_PREFIX: &str = "#";
+/// sheets named `t_xxx` are exported as tables of their own
+pub const TABLE_SHEET_PREFIX: &str = "t_";
+/// joins the workbook and the sheet in the name of a per-sheet table
+pub const SHEET_SEPARATOR: char = '#';
+
+/// numbers sort before text and by value, so `2` comes before `10`
+fn compare_keys(a: &CellValue, b: &CellValue) -> Ordering {
+    match (a.as_f64(), b.as_f64()) {
+        (Some(x), Some(y)) => x.total_cmp(&y),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => a.to_string().cmp(&b.to_string()),
+    }
+}
+
+/// the workbook a table comes from, `fname#sheet` for per-sheet tables
+pub fn table_file(name: &str) -> &str {
+    match name.rsplit_once(SHEET_SEPARATOR) {
+        Some((book, _)) if is_workbook(book) => book,
+        _ => name,
+    }
+}
+
+/// the name output files are named after: the file name without its
+/// extension, or the sheet name without `t_` for a per-sheet table
+pub fn table_stem(fname: &str) -> String {
+    let book = table_file(fname);
+    if book != fname {
+        let sheet = &fname[book.len() + 1..];
+        return sheet
+            .strip_prefix(TABLE_SHEET_PREFIX)
+            .unwrap_or(sheet)
+            .to_string();
+    }
+    match Path::new(fname).file_stem() {
+        Some(stem) => stem.to_string_lossy().to_string(),
+        None => fname.to_string(),
+    }
+}
+
+/// what the output files of the table are named after, in its folder and
+/// ignoring case, two tables sharing it would overwrite each other
+pub fn output_name(fname: &str) -> String {
+    let dir = table_file(fname).rsplit_once('/').map_or("", |(d, _)| d);
+    format!("{}/{}", dir, table_stem(fname).to_lowercase())
+}
+
+/// marker after the type declaring a key column, `int!key` or `int!key2`
+/// for the second column of a composite key
+pub const KEY_MARKER: &str = "key";
+
+/// marker for a unique lookup map from the column to the key, `string!index`
+pub const INDEX_MARKER: &str = "index";
+/// marker for a map from each value of the column to the keys of every row
+/// having it, `int!group`
+pub const GROUP_MARKER: &str = "group";
+
+/// lookup map generated for a field besides the table itself
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IndexKind {
+    Unique,
+    Group,
+}
+
+enum Marker {
+    // bare `key` is 0
+    Key(usize),
+    Index(IndexKind),
+}
+
+fn parse_marker(marker: &str) -> Result<Marker, String> {
+    let marker = marker.trim();
+    if marker == INDEX_MARKER {
+        return Ok(Marker::Index(IndexKind::Unique));
+    }
+    if marker == GROUP_MARKER {
+        return Ok(Marker::Index(IndexKind::Group));
+    }
+    let order = match marker.strip_prefix(KEY_MARKER) {
+        Some("") => Some(0),
+        Some(n) => n.parse::<usize>().ok().filter(|n| *n > 0),
+        None => None,
+    };
+    order.map(Marker::Key).ok_or(format!(
+        "未知的标记!{}，主键写作int!key，复合主键写作int!key1、int!key2，索引写作int!index或int!group",
+        marker
+    ))
+}
+
+/// field names the `legacy_keys` layout treats as keys: names containing
+/// `KEY`, `Keys` or `KeyId`, and `id` itself
+pub fn is_legacy_key_name(name: &str) -> bool {
+    name.contains("KEY")
+        || name.contains("Keys")
+        || name.contains("KeyId")
+        || name == "id"
+        || name == "Id"
+        || name == "ID"
+}
+
+/// a lookup map of a table, see `ALLXLSX::indexes`
+pub struct TableIndex<'a> {
+    pub field: &'a XlsTabField,
+    pub kind: IndexKind,
+    /// each value of the field with the keys of the rows having it, in the
+    /// order generators write the rows
+    pub entries: Vec<(Value, Vec<Vec<Value>>)>,
+}
 
 #[derive(Debug)]
 pub struct XlsTabField {
     field_name: String,
     field_type: String,
+    value_type: Result<FieldType, String>,
     field_index: usize,
     row_index: usize,
     checker: CheckObj,
     condition: String,
+    condition_valid: bool,
     client_server: String,
     field_cn_name: String,
+    // position in the key, see `XLSX::key_fields`, `None` for other fields
+    key_order: Option<usize>,
+    index: Option<IndexKind>,
 }
 
 impl XlsTabField {
@@ -45,26 +179,65 @@ impl XlsTabField {
         XlsTabField {
             field_name: "".to_string(),
             field_type: "".to_string(),
+            value_type: Err(String::new()),
             field_index: 0,
             row_index: 0,
             checker: CheckObj::new(),
             condition: "".to_string(),
+            condition_valid: true,
             client_server: "".to_string(),
             field_cn_name: "".to_string(),
+            key_order: None,
+            index: None,
         }
     }
 
     pub fn is_key_field(&self) -> bool {
-        self.field_name.contains("KEY")
-            || self.field_name.contains("Keys")
-            || self.field_name.contains("KeyId")
-            || self.field_name == "id"
-            || self.field_name == "Id"
-            || self.field_name == "ID"
+        self.key_order.is_some()
+    }
+
+    pub fn set_key_order(&mut self, order: Option<usize>) {
+        self.key_order = order;
+    }
+
+    pub fn get_key_order(&self) -> Option<usize> {
+        self.key_order
+    }
+
+    /// audiences from the client/server row, `c`, `s` and `cs` are short for
+    /// client and server, anything else is a custom tag. empty means every side.
+    pub fn side_tags(&self) -> Vec<String> {
+        let mut tags: Vec<String> = vec![];
+        for tag in self.client_server.split([',', '|', ' ']) {
+            let tag = tag.trim().to_lowercase();
+            let expanded = match tag.as_str() {
+                "" => vec![],
+                "c" => vec!["client"],
+                "s" => vec!["server"],
+                "cs" | "sc" => vec!["client", "server"],
+                t => vec![t],
+            };
+            for t in expanded {
+                if !tags.iter().any(|x| x == t) {
+                    tags.push(t.to_string());
+                }
+            }
+        }
+        tags
     }
 
-    pub fn is_remark_field(&self, _target: &String) -> bool {
-        self.client_server == "none"
+    /// whether the field is left out when exporting for `side`, an empty side
+    /// exports every field not marked as `none`. key fields are always exported.
+    pub fn is_remark_field(&self, side: &str) -> bool {
+        if self.client_server.trim().to_lowercase() == "none" {
+            return true;
+        }
+        if side.is_empty() || self.is_key_field() {
+            return false;
+        }
+
+        let tags = self.side_tags();
+        !tags.is_empty() && !tags.iter().any(|t| t == side)
     }
 
     pub fn is_invalid_field(&self) -> bool {
@@ -75,18 +248,96 @@ impl XlsTabField {
         self.field_name = name;
     }
 
-    pub fn get_field_name(&self) -> &String {
+    pub fn get_field_name(&self) -> &str {
         &self.field_name
     }
 
+    /// `t` may end with markers like `int!key` or `int!group`, markers are
+    /// not part of the stored type
     pub fn set_field_type(&mut self, t: String) {
-        self.field_type = t;
+        let mut parts = t.split('!');
+        let ty = parts.next().unwrap_or_default();
+        let ty = if t.contains('!') { ty.trim() } else { ty };
+        self.value_type = FieldType::parse(ty);
+        for marker in parts {
+            match parse_marker(marker) {
+                Ok(Marker::Key(order)) => self.key_order = Some(order),
+                Ok(Marker::Index(kind)) => self.index = Some(kind),
+                Err(e) => self.value_type = Err(e),
+            }
+        }
+        // lookup maps and sql keys need plain values
+        if self.index.is_some()
+            && matches!(
+                self.value_type,
+                Ok(FieldType::Array(_) | FieldType::Tuple(_) | FieldType::Struct(_))
+            )
+        {
+            self.value_type = Err("数组、元组和结构体不能作为索引".to_string());
+        }
+        self.field_type = ty.to_string();
     }
 
-    pub fn get_field_type(&self) -> &String {
+    /// the lookup map declared for this field
+    pub fn get_index(&self) -> Option<IndexKind> {
+        self.index
+    }
+
+    pub fn get_field_type(&self) -> &str {
         &self.field_type
     }
 
+    /// the parsed type, `None` if the type row can not be parsed
+    pub fn get_value_type(&self) -> Option<&FieldType> {
+        self.value_type.as_ref().ok()
+    }
+
+    pub fn type_error(&self) -> Option<&str> {
+        self.value_type.as_ref().err().map(String::as_str)
+    }
+
+    pub fn parse_value(&self, value: &CellValue, enums: &dyn EnumLookup) -> Result<Value, String> {
+        match &self.value_type {
+            Ok(t) => t.parse_cell(value, enums),
+            Err(e) => Err(e.clone()),
+        }
+    }
+
+    /// parsed value for generators, `file` is only used in the error
+    pub fn value_of(
+        &self,
+        file: &str,
+        value: &CellValue,
+        enums: &dyn EnumLookup,
+    ) -> Result<Value, Error> {
+        self.parse_value(value, enums)
+            .map_err(|message| Error::TypeMismatch {
+                location: Box::new(Location {
+                    file: file.to_string(),
+                    field: self.field_name.clone(),
+                    ..Default::default()
+                }),
+                value: value.to_string(),
+                field_type: self.field_type.clone(),
+                message,
+            })
+    }
+
+    /// parsed value of a key or lookup map cell, text is trimmed since
+    /// spaces around a key are never meant
+    pub fn key_of(
+        &self,
+        file: &str,
+        value: &CellValue,
+        enums: &dyn EnumLookup,
+    ) -> Result<Value, Error> {
+        Ok(match self.value_of(file, value, enums)? {
+            Value::Str(s) => Value::Str(s.trim().to_string()),
+            Value::Raw(s) => Value::Raw(s.trim().to_string()),
+            v => v,
+        })
+    }
+
     pub fn set_field_index(&mut self, i: usize) {
         self.field_index = i;
     }
@@ -103,42 +354,88 @@ impl XlsTabField {
         self.row_index as u32
     }
 
-    fn set_condition(&mut self, c: &String) {
-        self.condition = c.clone();
+    fn set_condition(&mut self, c: &str) {
+        self.condition = c.to_string();
     }
 
-    pub fn get_condition(&self) -> &String {
+    pub fn get_condition(&self) -> &str {
         &self.condition
     }
 
-    pub fn set_client_or_server(&mut self, c: &String) {
-        self.client_server = c.clone();
+    pub fn is_condition_valid(&self) -> bool {
+        self.condition_valid
+    }
+
+    pub fn set_client_or_server(&mut self, c: &str) {
+        self.client_server = c.to_string();
+    }
+
+    pub fn get_client_or_server(&self) -> &str {
+        &self.client_server
     }
 
     pub fn set_checkers(&mut self, checkers: CheckObj) {
         self.checker = checkers;
     }
 
-    pub fn is_valid(&self, value: &String) -> bool {
-        return self.checker.test(value);
+    pub fn is_valid(&self, value: &CellValue) -> bool {
+        self.checker.test(value)
     }
 
     pub fn need_full_load(&self) -> bool {
-        return self.checker.need_full_load();
+        self.checker.need_full_load()
+    }
+
+    /// whether the condition asks for `unique` values
+    pub fn is_unique(&self) -> bool {
+        self.checker.is_unique()
     }
 
-    pub fn is_valid_on_all_load(&self, value: &String, all: &ALLXLSX) -> bool {
-        return self.checker.test_on_all_load(value, all);
+    /// tables the checkers of this field look values up in
+    pub fn references(&self) -> Vec<String> {
+        self.checker.references()
     }
 
-    pub fn set_field_cn_name(&mut self, name: &String) {
-        self.field_cn_name = name.clone();
+    pub fn is_valid_on_all_load(&self, value: &CellValue, all: &ALLXLSX) -> bool {
+        self.checker.test_on_all_load(value, all)
+    }
+
+    pub fn set_field_cn_name(&mut self, name: &str) {
+        self.field_cn_name = name.to_string();
+    }
+
+    pub fn get_field_cn_name(&self) -> &str {
+        &self.field_cn_name
+    }
+
+    /// value seen by the checkers, empty numbers are exported as 0
+    pub fn value_or_default(&self, value: &CellValue) -> CellValue {
+        if value.is_empty() {
+            match self.field_type.as_str() {
+                "int" | "float" | "long" => return CellValue::Int(0),
+                _ => {}
+            }
+        }
+
+        value.clone()
+    }
+}
+
+impl Default for XlsTabField {
+    fn default() -> Self {
+        XlsTabField::new()
     }
 }
 
 pub struct XLSX {
     fields: Vec<XlsTabField>,
-    values: Vec<Vec<String>>,
+    values: Vec<Vec<CellValue>>,
+    layout: HeaderLayout,
+    // sheet where the header rows are read from
+    header_sheet: String,
+    // (sheet name, 0-based row) where each value row comes from
+    origins: Vec<(String, usize)>,
+    enums: Vec<EnumDef>,
 }
 
 impl XLSX {
@@ -146,15 +443,28 @@ impl XLSX {
         XLSX {
             fields: vec![],
             values: vec![],
+            layout: HeaderLayout::default(),
+            header_sheet: String::from(""),
+            origins: vec![],
+            enums: vec![],
         }
     }
 
+    pub fn set_layout(&mut self, layout: HeaderLayout) {
+        self.layout = layout;
+    }
+
+    pub fn get_layout(&self) -> &HeaderLayout {
+        &self.layout
+    }
+
     pub fn add_field(&mut self, field: XlsTabField) {
         self.fields.push(field);
     }
 
-    pub fn add_row(&mut self, row: Vec<String>) {
+    pub fn add_row(&mut self, row: Vec<CellValue>, sheet: &str, row_index: usize) {
         self.values.push(row);
+        self.origins.push((sheet.to_string(), row_index));
     }
 
     pub fn field_num(&self) -> usize {
@@ -165,10 +475,93 @@ impl XLSX {
         &self.fields
     }
 
-    pub fn value_list(&self) -> &Vec<Vec<String>> {
+    /// fields that are exported for `side`, see `XlsTabField::is_remark_field`.
+    /// key fields come first, in key order
+    pub fn side_fields(&self, side: &str) -> Vec<&XlsTabField> {
+        let mut fields = self.key_fields();
+        fields.extend(
+            self.fields
+                .iter()
+                .filter(|f| !f.is_key_field() && !f.is_remark_field(side)),
+        );
+        fields
+    }
+
+    /// key fields by their `!keyN` order, then by column
+    pub fn key_fields(&self) -> Vec<&XlsTabField> {
+        let mut keys: Vec<&XlsTabField> = self.fields.iter().filter(|f| f.is_key_field()).collect();
+        keys.sort_by_key(|f| f.key_order);
+        keys
+    }
+
+    pub fn value_list(&self) -> &Vec<Vec<CellValue>> {
         &self.values
     }
 
+    /// enums defined in the `__enum__` sheet of the workbook
+    pub fn enum_list(&self) -> &Vec<EnumDef> {
+        &self.enums
+    }
+
+    pub fn row_origin(&self, row: usize) -> Option<&(String, usize)> {
+        self.origins.get(row)
+    }
+
+    /// rows without key values are skipped by every generator
+    pub fn is_exported_row(&self, row: &[CellValue]) -> bool {
+        for f in &self.fields {
+            if f.is_key_field() {
+                if let Some(v) = row.get(f.as_index() as usize) {
+                    if v.is_empty() {
+                        return false;
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    /// tables referenced by `expect` checks
+    pub fn references(&self) -> Vec<String> {
+        let mut refs: Vec<String> = self.fields.iter().flat_map(|f| f.references()).collect();
+        refs.sort();
+        refs.dedup();
+        refs
+    }
+
+    /// names of the enums used by the field types
+    pub fn enums_used(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .fields
+            .iter()
+            .filter_map(|f| f.get_value_type())
+            .flat_map(|t| t.enum_names())
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// rows in the order generators write them, as in the workbook or
+    /// sorted by the key columns
+    pub fn rows(&self, sort_by_key: bool) -> Vec<&Vec<CellValue>> {
+        let mut rows: Vec<&Vec<CellValue>> = self.values.iter().collect();
+        if sort_by_key {
+            let keys: Vec<usize> = self
+                .key_fields()
+                .iter()
+                .map(|f| f.as_index() as usize)
+                .collect();
+            rows.sort_by(|a, b| {
+                keys.iter()
+                    .map(|k| compare_keys(&a[*k], &b[*k]))
+                    .find(|o| *o != Ordering::Equal)
+                    .unwrap_or(Ordering::Equal)
+            });
+        }
+        rows
+    }
+
     pub fn key_num(&self) -> u32 {
         let mut num = 0;
         for f in &self.fields {
@@ -179,172 +572,630 @@ impl XLSX {
         num
     }
 
-    pub fn parse_from_file(
+    /// opens the workbook, applies its `__meta__` and `__enum__` sheets and
+    /// returns the sheets holding table data
+    fn read_workbook(
         &mut self,
-        path: &String,
+        path: &str,
         fname: &str,
-        is_special_xlsx: bool,
-        target: &String,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+    ) -> Result<Vec<(String, Range<DataType>)>, Error> {
         let full_name = format!("{}/{}", path, fname);
-        let mut workbook: Xlsx<_> = open_workbook(full_name)?;
-        for sheet in workbook.worksheets() {
-            log::trace!("start parsing filename={} sheet name={}", fname, sheet.0);
-            let cells = sheet.1.get_size();
-            let mut i: usize = 0;
-            if self.field_num() == 0 {
-                let mut field_index: usize = 0;
-                while i < cells.1 {
-                    let mut one_field = XlsTabField::new();
-                    if let Some(field_name) = sheet.1.get_value((1, i as u32)) {
-                        one_field.set_field_name(field_name.to_string());
-                    }
-                    if is_special_xlsx {
-                        one_field.set_field_type("string".to_string());
-                    } else if let Some(type_name) = sheet.1.get_value((4, i as u32)) {
-                        one_field.set_field_type(type_name.to_string());
-                        if let Some(expr) = sheet.1.get_value((2, i as u32)) {
-                            one_field.set_condition(&expr.to_string());
-                            let checkers = generate_checker(
-                                expr.to_string(),
-                                type_name.to_string(),
-                                &fname.to_string(),
-                            );
-                            if let Ok(css) = checkers {
-                                one_field.set_checkers(css);
-                            }
-                        }
-                        if let Some(client_or_server) = sheet.1.get_value((3, i as u32)) {
-                            one_field.set_client_or_server(&client_or_server.to_string());
-                        }
+        let mut workbook = open_workbook_auto(full_name).map_err(|e| Error::Workbook {
+            file: fname.to_string(),
+            message: e.to_string(),
+        })?;
+        let sheets = workbook.worksheets();
+        if let Some(meta) = sheets.iter().find(|s| s.0 == META_SHEET_NAME) {
+            let mut rows: Vec<(String, String)> = vec![];
+            for row in meta.1.rows() {
+                let key = row.first().map(|c| c.to_string()).unwrap_or_default();
+                let value = row.get(1).map(|c| c.to_string()).unwrap_or_default();
+                rows.push((key, value));
+            }
+            self.layout
+                .apply_meta_sheet(&rows)
+                .map_err(|message| Error::Layout {
+                    file: fname.to_string(),
+                    message,
+                })?;
+        }
+        if let Some(sheet) = sheets.iter().find(|s| s.0 == ENUM_SHEET_NAME) {
+            let rows: Vec<Vec<String>> = sheet
+                .1
+                .rows()
+                .map(|row| row.iter().map(|c| CellValue::from(c).to_string()).collect())
+                .collect();
+            self.enums = parse_enum_rows(&rows).map_err(|message| Error::Workbook {
+                file: fname.to_string(),
+                message: format!("{} {}", ENUM_SHEET_NAME, message),
+            })?;
+        }
+        self.layout.validate().map_err(|message| Error::Layout {
+            file: fname.to_string(),
+            message,
+        })?;
+
+        Ok(sheets
+            .into_iter()
+            .filter(|s| {
+                s.0 != META_SHEET_NAME
+                    && s.0 != ENUM_SHEET_NAME
+                    && !s.0.starts_with(SCRATCH_SHEET_PREFIX)
+            })
+            .collect())
+    }
+
+    /// reads the header of `sheet` when the table has no fields yet, then
+    /// appends its rows
+    fn parse_sheet(
+        &mut self,
+        fname: &str,
+        sheet: &(String, Range<DataType>),
+        is_special_xlsx: bool,
+    ) {
+        // excel rows are 1-based, calamine rows are 0-based
+        let layout = &self.layout;
+        let header_row = |row: usize| if row > 0 { Some(row as u32 - 1) } else { None };
+        let name_row = layout.field_name as u32 - 1;
+        let type_row = layout.field_type as u32 - 1;
+        let cn_name_row = header_row(layout.cn_name);
+        let condition_row = header_row(layout.condition);
+        let client_server_row = header_row(layout.client_server);
+        let data_start = if is_special_xlsx {
+            layout.field_name
+        } else {
+            layout.data_start - 1
+        };
 
-                        if let Some(cn_name) = sheet.1.get_value((0, i as u32)) {
-                            one_field.set_field_cn_name(&cn_name.to_string());
+        log::trace!("start parsing filename={} sheet name={}", fname, sheet.0);
+        // get_value takes absolute positions, the range may not start at A1
+        let cells = match sheet.1.end() {
+            Some((row, col)) => (row as usize + 1, col as usize + 1),
+            None => (0, 0),
+        };
+        let mut i: usize = 0;
+        if self.field_num() == 0 {
+            self.header_sheet = sheet.0.clone();
+            let mut field_index: usize = 0;
+            while i < cells.1 {
+                let mut one_field = XlsTabField::new();
+                if let Some(field_name) = sheet.1.get_value((name_row, i as u32)) {
+                    one_field.set_field_name(field_name.to_string());
+                }
+                if is_special_xlsx {
+                    one_field.set_field_type("string".to_string());
+                } else if let Some(type_name) = sheet.1.get_value((type_row, i as u32)) {
+                    one_field.set_field_type(type_name.to_string());
+                    if layout.legacy_keys && is_legacy_key_name(one_field.get_field_name()) {
+                        one_field.set_key_order(Some(one_field.get_key_order().unwrap_or(0)));
+                    }
+                    if let Some(expr) = condition_row.and_then(|r| sheet.1.get_value((r, i as u32)))
+                    {
+                        one_field.set_condition(&expr.to_string());
+                        let checkers = generate_checker(
+                            expr.to_string(),
+                            one_field.get_field_type().to_string(),
+                            fname,
+                        );
+                        match checkers {
+                            Ok(css) => one_field.set_checkers(css),
+                            Err(_) => one_field.condition_valid = false,
                         }
                     }
+                    if let Some(client_or_server) =
+                        client_server_row.and_then(|r| sheet.1.get_value((r, i as u32)))
+                    {
+                        one_field.set_client_or_server(&client_or_server.to_string());
+                    }
 
-                    one_field.set_field_index(field_index);
-                    one_field.set_row_index(i);
-                    if !one_field.is_invalid_field() && !one_field.is_remark_field(target) {
-                        self.add_field(one_field);
-                        field_index += 1;
+                    if let Some(cn_name) =
+                        cn_name_row.and_then(|r| sheet.1.get_value((r, i as u32)))
+                    {
+                        one_field.set_field_cn_name(&cn_name.to_string());
                     }
+                }
 
-                    i += 1;
+                one_field.set_field_index(field_index);
+                one_field.set_row_index(i);
+                if !one_field.is_invalid_field() && !one_field.is_remark_field("") {
+                    self.fields.push(one_field);
+                    field_index += 1;
                 }
-            }
 
-            i = 5; // content start from 5th row!
-            if is_special_xlsx {
-                i = 1;
+                i += 1;
             }
+        }
 
-            while i < cells.0 {
-                let mut row_data: Vec<String> = vec![];
-                for field in self.fields_list() {
-                    if let Some(value) = sheet.1.get_value((i as u32, field.get_row_index())) {
-                        row_data.push(value.to_string());
-                    } else {
-                        row_data.push(String::from(""));
-                    }
+        i = data_start;
+        while i < cells.0 {
+            let mut row_data: Vec<CellValue> = vec![];
+            for field in self.fields_list() {
+                if let Some(value) = sheet.1.get_value((i as u32, field.get_row_index())) {
+                    row_data.push(CellValue::from(value));
+                } else {
+                    row_data.push(CellValue::Empty);
                 }
-                i += 1;
-                self.add_row(row_data);
+            }
+            self.add_row(row_data, sheet.0.as_str(), i);
+            i += 1;
+        }
+    }
+
+    /// whether `sheet` is exported as a table of its own, see `parse_tables`
+    fn is_sheet_table(&self, sheet: &str) -> bool {
+        self.layout.per_sheet || sheet.starts_with(TABLE_SHEET_PREFIX)
+    }
+
+    /// parses the workbook as one table, rows of every sheet are appended
+    /// under the header of the first one. sheets exported as tables of
+    /// their own are left out, see `parse_tables`
+    pub fn parse_from_file(
+        &mut self,
+        path: &str,
+        fname: &str,
+        is_special_xlsx: bool,
+    ) -> Result<(), Error> {
+        for sheet in self.read_workbook(path, fname)? {
+            if !self.is_sheet_table(&sheet.0) {
+                self.parse_sheet(fname, &sheet, is_special_xlsx);
             }
         }
         Ok(())
     }
+
+    /// parses the workbook into named tables: sheets named `t_xxx`, or every
+    /// sheet when `per_sheet` is set, become tables named `fname#sheet`, the
+    /// other sheets and the enums make up the table named `fname`
+    pub fn parse_tables(
+        path: &str,
+        fname: &str,
+        layout: HeaderLayout,
+    ) -> Result<Vec<(String, XLSX)>, Error> {
+        let mut book = XLSX::new();
+        book.set_layout(layout);
+        let mut tables: Vec<(String, XLSX)> = vec![];
+        for sheet in book.read_workbook(path, fname)? {
+            if book.is_sheet_table(&sheet.0) {
+                let mut table = XLSX::new();
+                table.set_layout(book.layout.clone());
+                table.parse_sheet(fname, &sheet, false);
+                tables.push((format!("{}{}{}", fname, SHEET_SEPARATOR, sheet.0), table));
+            } else {
+                book.parse_sheet(fname, &sheet, false);
+            }
+        }
+        if book.field_num() > 0 || !book.enums.is_empty() {
+            tables.insert(0, (fname.to_string(), book));
+        }
+        Ok(tables)
+    }
+}
+
+impl Default for XLSX {
+    fn default() -> Self {
+        XLSX::new()
+    }
 }
 
+/// tables by name, iterated in name order so output and errors are stable
 pub struct ALLXLSX {
-    all: HashMap<String, XLSX>,
+    all: BTreeMap<String, XLSX>,
+    // tables only loaded for `expect` and enum lookups, they are neither
+    // checked nor generated
+    lookup_only: HashSet<String>,
 }
 
 impl ALLXLSX {
     pub fn new() -> ALLXLSX {
         ALLXLSX {
-            all: HashMap::new(),
+            all: BTreeMap::new(),
+            lookup_only: HashSet::new(),
         }
     }
 
     pub fn add(&mut self, name: &str, file: XLSX) {
+        self.lookup_only.remove(name);
+        self.all.insert(String::from(name), file);
+    }
+
+    /// adds a table other tables look values up in, see `is_lookup_only`
+    pub fn add_lookup_only(&mut self, name: &str, file: XLSX) {
+        self.lookup_only.insert(String::from(name));
         self.all.insert(String::from(name), file);
     }
 
-    pub fn gen<'a, T: Generator<'a>>(
+    pub fn is_lookup_only(&self, name: &str) -> bool {
+        self.lookup_only.contains(name)
+    }
+
+    /// tables that are checked and generated
+    pub fn tables(&self) -> Vec<(&String, &XLSX)> {
+        self.all
+            .iter()
+            .filter(|(name, _)| !self.lookup_only.contains(*name))
+            .collect()
+    }
+
+    /// a workbook by its path under the input directory, or by its file
+    /// name when no other folder has a workbook with that name
+    pub fn get(&self, name: &str) -> Option<&XLSX> {
+        if let Some(xlsx) = self.all.get(name) {
+            return Some(xlsx);
+        }
+        let mut found = self
+            .all
+            .iter()
+            .filter(|(path, _)| path.rsplit('/').next() == Some(name));
+        match (found.next(), found.next()) {
+            (Some((_, xlsx)), None) => Some(xlsx),
+            _ => None,
+        }
+    }
+
+    /// generates the table called `name` with `T`
+    pub fn gen_table<'a, T: Generator<'a>>(
         &'a self,
-        output: &String,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        for (name, xlsx) in self.all.iter() {
-            let mut generator = T::new(xlsx);
-            let r = generator.generate(name, output, &self);
-            if let Err(e) = r {
-                let notify_error = super::notify_error_info(&e);
-                if let Err(s) = notify_error {
-                    log::error!("notify error occurred! {}", s);
-                }
+        name: &str,
+        ctx: &ExportContext,
+    ) -> Result<(), Error> {
+        let xlsx = match self.all.get(name) {
+            Some(xlsx) => xlsx,
+            None => return Ok(()),
+        };
+        let mut generator = T::new(xlsx);
+        // workbooks holding only enum definitions have no table
+        if xlsx.field_num() > 0 {
+            generator.generate(name, ctx, self)?;
+        }
+        if ctx.options.emit_enums && !xlsx.enums.is_empty() {
+            generator.generate_enums(name, ctx)?;
+        }
+        Ok(())
+    }
+
+    /// generates every table with `T` on `ctx.options.jobs` threads, the
+    /// error of the first failing table by name is returned
+    pub fn gen<'a, T: Generator<'a>>(&'a self, ctx: &ExportContext) -> Result<(), Error> {
+        let names: Vec<&String> = self.tables().into_iter().map(|(name, _)| name).collect();
+        for ret in par_map(&names, ctx.options.jobs, |name| {
+            self.gen_table::<T>(name, ctx)
+        }) {
+            if let Err(e) = ret {
+                ctx.notify_error(&e);
                 return Err(e);
             }
         }
         Ok(())
     }
 
-    pub fn check_xlsx_valid(&self) -> Result<(), Box<dyn std::error::Error>> {
-        for (name, xlsx) in self.all.iter() {
-            let mut i = 0usize;
-            let field_num = xlsx.field_num();
-            while i < field_num {
-                if let Some(field) = xlsx.fields.get(i) {
-                    if field.need_full_load() {
-                        let mut l = 4usize;
-                        for value in &xlsx.values {
-                            l += 1;
-                            if let Some(v) = value.get(i) {
-                                if field.is_valid_on_all_load(v, self) {
-                                    continue;
-                                }
-                            }
+    /// the enum called `name` and the workbook defining it
+    pub fn get_enum(&self, name: &str) -> Option<(&String, &EnumDef)> {
+        for (file, xlsx) in self.all.iter() {
+            if let Some(def) = xlsx.enums.iter().find(|e| e.name == name) {
+                return Some((file, def));
+            }
+        }
+        None
+    }
+
+    pub fn check_xlsx_valid(&self, ctx: &ExportContext, diagnostics: &mut Diagnostics) {
+        for (name, xlsx) in self.tables() {
+            for def in xlsx.enums.iter() {
+                for (other_name, other) in self.all.iter() {
+                    if other_name != name && other.enums.iter().any(|e| e.name == def.name) {
+                        diagnostics.push(Error::Workbook {
+                            file: name.clone(),
+                            message: format!("枚举{}在{}中重复定义", def.name, other_name),
+                        });
+                    }
+                }
+            }
+
+            for field in xlsx.fields.iter() {
+                let message = match field.type_error() {
+                    Some(message) => Some(message.to_string()),
+                    None if field.get_index().is_some() && xlsx.key_num() == 0 => {
+                        Some("表没有主键，不能生成索引".to_string())
+                    }
+                    None => None,
+                };
+                if let Some(message) = message {
+                    diagnostics.push(Error::InvalidType {
+                        location: Box::new(Location {
+                            file: name.clone(),
+                            sheet: xlsx.header_sheet.clone(),
+                            row: xlsx.layout.field_type,
+                            column: column_letter(field.get_row_index() as usize),
+                            field: field.get_field_name().to_string(),
+                        }),
+                        field_type: field.get_field_type().to_string(),
+                        message,
+                    });
+                }
+                if !field.is_condition_valid() {
+                    diagnostics.push(Error::InvalidCondition {
+                        location: Box::new(Location {
+                            file: name.clone(),
+                            sheet: xlsx.header_sheet.clone(),
+                            row: xlsx.layout.condition,
+                            column: column_letter(field.get_row_index() as usize),
+                            field: field.get_field_name().to_string(),
+                        }),
+                        condition: field.get_condition().to_string(),
+                    });
+                }
+            }
+
+            for (r, row) in xlsx.values.iter().enumerate() {
+                if !xlsx.is_exported_row(row) {
+                    continue;
+                }
+
+                for (i, field) in xlsx.fields.iter().enumerate() {
+                    let cell = row.get(i).cloned().unwrap_or_default();
+                    let value = cell.to_string();
+                    let (sheet, row_index) = xlsx.row_origin(r).cloned().unwrap_or_default();
+                    let location = Box::new(Location {
+                        file: name.clone(),
+                        sheet,
+                        row: row_index + 1,
+                        column: column_letter(field.get_row_index() as usize),
+                        field: field.get_field_name().to_string(),
+                    });
+                    if field.type_error().is_some() {
+                        continue;
+                    }
+                    if let Err(message) = field.parse_value(&cell, self) {
+                        diagnostics.push(Error::TypeMismatch {
+                            location,
+                            value,
+                            field_type: field.get_field_type().to_string(),
+                            message,
+                        });
+                        continue;
+                    }
+
+                    let v = field.value_or_default(&cell);
+                    let local_valid = field.is_valid(&v);
+                    let ref_valid = !field.need_full_load() || field.is_valid_on_all_load(&v, self);
+                    if local_valid && ref_valid {
+                        continue;
+                    }
 
-                            let err_info = format!(
-                                "值不合法\n字段名：{}, 字段值：{:?}, 第{}行\n字段要求：{}",
-                                field.get_field_name(),
-                                value.get(i),
-                                l,
-                                field.get_condition()
-                            )
-                            .replace("\"", "\\\"")
-                            .replace("'", "\\\'")
-                            .replace("Some", "");
-                            return Err(Box::new(Error::new(
-                                ErrorKind::InvalidData,
-                                format!(
-                                    "**导表校验失败**\n**项目：{} 文件名：{}**\n{}",
-                                    get_project_name(),
-                                    name,
-                                    err_info
-                                ),
-                            )));
+                    let condition = field.get_condition().to_string();
+                    diagnostics.push(if !local_valid && field.is_key_field() {
+                        Error::InvalidKey {
+                            location,
+                            value,
+                            condition,
                         }
+                    } else if !local_valid {
+                        Error::InvalidValue {
+                            location,
+                            value,
+                            condition,
+                        }
+                    } else {
+                        Error::MissingReference {
+                            location,
+                            value,
+                            condition,
+                        }
+                    });
+                }
+            }
+
+            self.check_duplicates(name, xlsx, diagnostics);
+        }
+        self.check_output_names(ctx, diagnostics);
+
+        if !diagnostics.is_empty() {
+            ctx.notify(&diagnostics.to_markdown(&ctx.project));
+        }
+    }
+
+    /// lookup maps of the `!index` and `!group` fields exported for `side`,
+    /// tables without keys have none. `fname` is only used in errors
+    pub fn indexes<'a>(
+        &self,
+        fname: &str,
+        xlsx: &'a XLSX,
+        side: &str,
+        sort_by_key: bool,
+    ) -> Result<Vec<TableIndex<'a>>, Error> {
+        let keys = xlsx.key_fields();
+        if keys.is_empty() {
+            return Ok(vec![]);
+        }
+        let rows: Vec<&Vec<CellValue>> = xlsx
+            .rows(sort_by_key)
+            .into_iter()
+            .filter(|row| xlsx.is_exported_row(row))
+            .collect();
+        let cell = |row: &Vec<CellValue>, field: &XlsTabField| {
+            row.get(field.as_index() as usize)
+                .cloned()
+                .unwrap_or_default()
+        };
+
+        let mut indexes = vec![];
+        for field in xlsx.side_fields(side) {
+            let Some(kind) = field.get_index() else {
+                continue;
+            };
+            let mut entries: Vec<(Value, Vec<Vec<Value>>)> = vec![];
+            let mut positions: HashMap<Value, usize> = HashMap::new();
+            for row in &rows {
+                let value = cell(row, field);
+                if value.is_empty() {
+                    continue;
+                }
+                let value = field.key_of(fname, &value, self)?;
+                let key = keys
+                    .iter()
+                    .map(|k| k.key_of(fname, &cell(row, k), self))
+                    .collect::<Result<Vec<Value>, Error>>()?;
+                match positions.get(&value) {
+                    Some(i) => entries[*i].1.push(key),
+                    None => {
+                        positions.insert(value.clone(), entries.len());
+                        entries.push((value, vec![key]));
                     }
                 }
-                i += 1;
             }
+            indexes.push(TableIndex {
+                field,
+                kind,
+                entries,
+            });
         }
+        Ok(indexes)
+    }
 
-        Ok(())
+    /// tables exporting files or types of the same name, lookup only tables
+    /// are compared too since their outputs are already there; one error is
+    /// reported for each pair of tables
+    fn check_output_names(&self, ctx: &ExportContext, diagnostics: &mut Diagnostics) {
+        let mut owners: HashMap<String, String> = shared_names(ctx).into_iter().collect();
+        let mut reported: HashSet<(String, String)> = HashSet::new();
+        let mut report = |name: &String, first: &String, exported: String| {
+            let (file, other) = match (self.is_lookup_only(first), self.is_lookup_only(name)) {
+                (true, true) => return,
+                (false, true) => (first, name),
+                _ => (name, first),
+            };
+            if reported.insert((file.clone(), other.clone())) {
+                diagnostics.push(Error::DuplicateOutput {
+                    file: file.clone(),
+                    first: other.clone(),
+                    name: exported,
+                });
+            }
+        };
+        for (name, xlsx) in self.all.iter() {
+            for (key, exported) in exported_names(name, xlsx, ctx) {
+                match owners.get(&key) {
+                    Some(first) => report(name, first, exported),
+                    None => {
+                        owners.insert(key, name.clone());
+                    }
+                }
+            }
+        }
+
+        // enums are only compared with the tables, repeated enums are
+        // reported with the enum checks
+        if !ctx.options.emit_enums {
+            return;
+        }
+        for (name, xlsx) in self.all.iter() {
+            for def in xlsx.enum_list() {
+                for target in ["csharp", "go"].into_iter().filter(|t| ctx.has_target(t)) {
+                    if let Some(first) = owners.get(&format!("{}:{}", target, def.name)) {
+                        report(name, first, def.name.clone());
+                    }
+                }
+            }
+        }
     }
 
-    pub fn has_field(&self, file: &String, field: &String, value: &String) -> bool {
-        if let Some(xlsx) = self.all.get(file) {
+    /// reports rows repeating the key of an earlier row, and values repeated
+    /// in `unique` columns. values are compared as they are parsed, so `是`
+    /// and `true` are the same bool and `1.50` and `1.5` the same float
+    fn check_duplicates(&self, name: &str, xlsx: &XLSX, diagnostics: &mut Diagnostics) {
+        let location = |r: usize, field: &XlsTabField| {
+            let (sheet, row_index) = xlsx.row_origin(r).cloned().unwrap_or_default();
+            Box::new(Location {
+                file: name.to_string(),
+                sheet,
+                row: row_index + 1,
+                column: column_letter(field.get_row_index() as usize),
+                field: field.get_field_name().to_string(),
+            })
+        };
+        let cell = |r: usize, field: &XlsTabField| {
+            xlsx.values[r]
+                .get(field.as_index() as usize)
+                .cloned()
+                .unwrap_or_default()
+        };
+        // cells that do not parse are left to the type checks
+        let value = |r: usize, field: &XlsTabField| field.key_of(name, &cell(r, field), self).ok();
+        let text = |r: usize, field: &XlsTabField| cell(r, field).to_string().trim().to_string();
+        let rows: Vec<usize> = (0..xlsx.values.len())
+            .filter(|r| xlsx.is_exported_row(&xlsx.values[*r]))
+            .collect();
+
+        let keys = xlsx.key_fields();
+        if let Some(first_key) = keys.first() {
+            let mut seen: HashMap<Vec<Value>, usize> = HashMap::new();
+            for r in &rows {
+                let Some(key) = keys
+                    .iter()
+                    .map(|f| value(*r, f))
+                    .collect::<Option<Vec<_>>>()
+                else {
+                    continue;
+                };
+                match seen.get(&key) {
+                    Some(first) => diagnostics.push(Error::DuplicateKey {
+                        location: location(*r, first_key),
+                        first: location(*first, first_key),
+                        value: keys
+                            .iter()
+                            .map(|f| text(*r, f))
+                            .collect::<Vec<String>>()
+                            .join(","),
+                    }),
+                    None => {
+                        seen.insert(key, *r);
+                    }
+                }
+            }
+        }
+
+        for field in xlsx.fields.iter().filter(|f| {
+            (f.is_unique() || f.get_index() == Some(IndexKind::Unique)) && !f.is_key_field()
+        }) {
+            let mut seen: HashMap<Value, usize> = HashMap::new();
+            for r in &rows {
+                if cell(*r, field).is_empty() {
+                    continue;
+                }
+                let Some(parsed) = value(*r, field) else {
+                    continue;
+                };
+                match seen.get(&parsed) {
+                    Some(first) => diagnostics.push(Error::DuplicateValue {
+                        location: location(*r, field),
+                        first: location(*first, field),
+                        value: text(*r, field),
+                        condition: if field.is_unique() {
+                            field.get_condition().to_string()
+                        } else {
+                            format!("{}!{}", field.get_field_type(), INDEX_MARKER)
+                        },
+                    }),
+                    None => {
+                        seen.insert(parsed, *r);
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn has_field(&self, file: &str, field: &str, value: &CellValue) -> bool {
+        if let Some(xlsx) = self.get(file) {
             let mut i = 0usize;
             let field_num = xlsx.field_num();
-            let trimd = value.trim();
+            let text = value.to_string();
+            let trimd = text.trim();
             while i < field_num {
                 if let Some(f) = xlsx.fields.get(i) {
                     if f.get_field_name().eq(field) {
                         for val in &xlsx.values {
                             if let Some(v) = val.get(i) {
-                                if v.trim().eq(trimd) {
+                                if v.to_string().trim().eq(trimd) {
                                     return true;
                                 }
                             }
@@ -359,6 +1210,24 @@ impl ALLXLSX {
     }
 }
 
+impl Default for ALLXLSX {
+    fn default() -> Self {
+        ALLXLSX::new()
+    }
+}
+
+impl EnumLookup for ALLXLSX {
+    fn enum_value(&self, name: &str, label: &str) -> Result<i64, String> {
+        match self.get_enum(name) {
+            Some((_, def)) => match def.value_of(label) {
+                Some(v) => Ok(v),
+                None => Err(format!("枚举{}没有{}", name, label)),
+            },
+            None => Err(format!("枚举{}未定义", name)),
+        }
+    }
+}
+
 #[cfg(test)]
 mod hashmaptests {
     use std::collections::HashMap;
@@ -367,16 +1236,482 @@ mod hashmaptests {
     fn test_string_key() {
         let mut h: HashMap<String, String> = HashMap::new();
         h.insert("test".to_string(), String::from("waht"));
-        if let Some(_) = h.get(&String::from("test")) {
-            assert!(true);
-        } else {
-            assert!(false);
+        assert!(h.contains_key("test"));
+        assert!(!h.contains_key("xxxx"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_legacy_key_name, is_workbook, table_stem, IndexKind, XlsTabField, XLSX};
+    use crate::cell::CellValue;
+    use crate::context::ExportContext;
+    use crate::diagnostics::Diagnostics;
+    use crate::fixture::{self, sheet1, text};
+    use crate::layout::HeaderLayout;
+    use crate::types::Value;
+
+    fn field(name: &str, tag: &str) -> XlsTabField {
+        let mut f = XlsTabField::new();
+        f.set_field_name(name.to_string());
+        f.set_client_or_server(tag);
+        f
+    }
+
+    #[test]
+    fn test_side_tags() {
+        let client = "client".to_string();
+        let server = "server".to_string();
+        assert!(!field("name", "").is_remark_field(&client));
+        assert!(!field("name", "c").is_remark_field(&client));
+        assert!(field("name", "c").is_remark_field(&server));
+        assert!(!field("name", "CS").is_remark_field(&server));
+        assert!(field("name", "none").is_remark_field(""));
+        assert!(field("name", "gm").is_remark_field(&client));
+        assert!(!field("name", "s,gm").is_remark_field("gm"));
+        let mut key = field("id", "s");
+        key.set_field_type("int!key".to_string());
+        assert!(!key.is_remark_field(&client));
+        assert_eq!(
+            field("name", "cs|gm").side_tags(),
+            vec!["client", "server", "gm"]
+        );
+    }
+
+    #[test]
+    fn test_key_markers() {
+        let typed = |name: &str, t: &str| {
+            let mut f = field(name, "");
+            f.set_field_type(t.to_string());
+            f
+        };
+        let key = typed("MonkeyIdle", "int!key");
+        assert!(key.is_key_field());
+        assert_eq!(key.get_field_type(), "int");
+        assert!(key.type_error().is_none());
+        assert!(!typed("id", "int").is_key_field());
+        assert!(typed("id", "int!primary").type_error().is_some());
+        assert!(typed("id", "int!key0").type_error().is_some());
+        // scalar keys are written from their parsed values
+        for t in ["bool!key", "date!key", "datetime!key", "duration!key"] {
+            assert!(typed("id", t).type_error().is_none());
         }
 
-        if let Some(_) = h.get(&String::from("xxxx")) {
-            assert!(false);
-        } else {
-            assert!(true);
+        assert!(is_legacy_key_name("KEY_id"));
+        assert!(is_legacy_key_name("ItemKeyId"));
+        assert!(is_legacy_key_name("id"));
+        assert!(!is_legacy_key_name("name"));
+
+        let mut xlsx = XLSX::new();
+        for (i, (name, t)) in [
+            ("name", "string"),
+            ("sub", "int!key2"),
+            ("main", "int!key1"),
+        ]
+        .iter()
+        .enumerate()
+        {
+            let mut f = typed(name, t);
+            f.set_field_index(i);
+            xlsx.add_field(f);
         }
+        let names = |fields: Vec<&XlsTabField>| -> Vec<String> {
+            fields
+                .iter()
+                .map(|f| f.get_field_name().to_string())
+                .collect()
+        };
+        assert_eq!(names(xlsx.key_fields()), vec!["main", "sub"]);
+        assert_eq!(names(xlsx.side_fields("")), vec!["main", "sub", "name"]);
+    }
+
+    #[test]
+    fn test_legacy_keys() {
+        let rows = vec![
+            text(&["KEY_id", "id", "name"]),
+            text(&["int", "int", "string"]),
+            vec![CellValue::Int(1), CellValue::Int(2), CellValue::from("a")],
+        ];
+        let books = [("item.xlsx", sheet1(rows))];
+        let mut layout = fixture::layout();
+        let keys = |layout: &HeaderLayout| -> u32 {
+            fixture::parse("xlsxto_legacy_keys", &books, layout)[0]
+                .1
+                .key_num()
+        };
+        assert_eq!(keys(&layout), 0);
+        layout.legacy_keys = true;
+        assert_eq!(keys(&layout), 2);
+    }
+
+    #[test]
+    fn test_duplicates() {
+        let header = vec![
+            text(&["type", "id", "name"]),
+            text(&["int!key", "int!key", "string"]),
+            text(&["", "", "unique()"]),
+        ];
+        let mut first = header.clone();
+        first.push(text(&["1", "1", "a"]));
+        first.push(text(&["1", "2", "b"]));
+        let mut second = header;
+        second.push(text(&["1", "2", "c"]));
+        second.push(text(&["2", "1", "a"]));
+        second.push(text(&["2", "2", ""]));
+        second.push(text(&["2", "3", ""]));
+        let sheets = vec![("A".to_string(), first), ("B".to_string(), second)];
+        let layout = HeaderLayout {
+            condition: 3,
+            data_start: 4,
+            ..fixture::layout()
+        };
+        let all = fixture::load("xlsxto_duplicates", &[("item.xlsx", sheets)], &layout);
+
+        let mut diagnostics = Diagnostics::new();
+        all.check_xlsx_valid(&ExportContext::new(""), &mut diagnostics);
+        let report = diagnostics.to_text("");
+        let errors: Vec<&str> = report.lines().skip(1).collect();
+        assert_eq!(
+            errors,
+            vec![
+                "item.xlsx 工作表：B 第4行 A列 键重复: 字段名：type, 键值：1,2, 与item.xlsx 工作表：A 第5行 A列重复",
+                "item.xlsx 工作表：B 第5行 C列 字段值重复: 字段名：name, 字段值：a, 字段要求：unique(), 与item.xlsx 工作表：A 第4行 C列重复",
+            ]
+        );
+
+        // values are compared as they are parsed
+        let rows = vec![
+            text(&["open", "rate"]),
+            text(&["bool!key", "float"]),
+            text(&["", "unique()"]),
+            text(&["true", "1.5"]),
+            text(&["是", "1.50"]),
+        ];
+        let books = [("flag.xlsx", sheet1(rows))];
+        let all = fixture::load("xlsxto_typed_duplicates", &books, &layout);
+        let mut diagnostics = Diagnostics::new();
+        all.check_xlsx_valid(&ExportContext::new(""), &mut diagnostics);
+        let report = diagnostics.to_text("");
+        let errors: Vec<&str> = report.lines().skip(1).collect();
+        assert_eq!(
+            errors,
+            vec![
+                "flag.xlsx 工作表：Sheet1 第5行 A列 键重复: 字段名：open, 键值：是, 与flag.xlsx 工作表：Sheet1 第4行 A列重复",
+                "flag.xlsx 工作表：Sheet1 第5行 B列 字段值重复: 字段名：rate, 字段值：1.50, 字段要求：unique(), 与flag.xlsx 工作表：Sheet1 第4行 B列重复",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_indexes() {
+        let item = vec![
+            text(&["id", "type", "name"]),
+            text(&["int!key", "int!group", "string!index"]),
+            text(&["1", "3", "a"]),
+            text(&["2", "5", "b"]),
+            text(&["3", "3", "a"]),
+            text(&["4", "", ""]),
+        ];
+        let plain = vec![text(&["type"]), text(&["int!group"]), text(&["1"])];
+        let list = vec![
+            text(&["id", "tags"]),
+            text(&["int!key", "int[]!group"]),
+            text(&["1", "[1]"]),
+        ];
+        let books = [
+            ("item.xlsx", sheet1(item)),
+            ("list.xlsx", sheet1(list)),
+            ("plain.xlsx", sheet1(plain)),
+        ];
+        let all = fixture::load("xlsxto_indexes", &books, &fixture::layout());
+
+        let item = all.get("item.xlsx").unwrap();
+        let indexes = all.indexes("item.xlsx", item, "", false).unwrap();
+        let entries = |i: usize| -> Vec<(Value, Vec<Vec<Value>>)> { indexes[i].entries.clone() };
+        let num = |n: &str| Value::Number(n.to_string());
+        let keys =
+            |ids: &[&str]| -> Vec<Vec<Value>> { ids.iter().map(|id| vec![num(id)]).collect() };
+        assert_eq!(indexes.len(), 2);
+        assert_eq!(indexes[0].kind, IndexKind::Group);
+        assert_eq!(
+            entries(0),
+            vec![(num("3"), keys(&["1", "3"])), (num("5"), keys(&["2"]))]
+        );
+        assert_eq!(indexes[1].kind, IndexKind::Unique);
+        assert_eq!(
+            entries(1)[0],
+            (Value::Str("a".to_string()), keys(&["1", "3"]))
+        );
+        assert!(all
+            .indexes("plain.xlsx", all.get("plain.xlsx").unwrap(), "", false)
+            .unwrap()
+            .is_empty());
+
+        // values are grouped as they are parsed, not as they are typed
+        let flags = vec![
+            text(&["id", "open"]),
+            text(&["int!key", "bool!group"]),
+            text(&["1", "是"]),
+            text(&["2", "0"]),
+            text(&["3", "true"]),
+        ];
+        let flag = fixture::load(
+            "xlsxto_typed_indexes",
+            &[("flag.xlsx", sheet1(flags))],
+            &fixture::layout(),
+        );
+        let indexes = flag
+            .indexes("flag.xlsx", flag.get("flag.xlsx").unwrap(), "", false)
+            .unwrap();
+        assert_eq!(
+            indexes[0].entries,
+            vec![
+                (Value::Bool(true), keys(&["1", "3"])),
+                (Value::Bool(false), keys(&["2"]))
+            ]
+        );
+
+        let mut diagnostics = Diagnostics::new();
+        all.check_xlsx_valid(&ExportContext::new(""), &mut diagnostics);
+        let report = diagnostics.to_text("");
+        let errors: Vec<&str> = report.lines().skip(1).collect();
+        assert_eq!(
+            errors,
+            vec![
+                "item.xlsx 工作表：Sheet1 第5行 C列 字段值重复: 字段名：name, 字段值：a, 字段要求：string!index, 与item.xlsx 工作表：Sheet1 第3行 C列重复",
+                "list.xlsx 工作表：Sheet1 第2行 B列 字段类型不合法: 字段名：tags, 类型：int[], 数组、元组和结构体不能作为索引",
+                "plain.xlsx 工作表：Sheet1 第2行 A列 字段类型不合法: 字段名：type, 类型：int, 表没有主键，不能生成索引",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_workbook_names() {
+        assert!(is_workbook("item.xlsx"));
+        assert!(is_workbook("item.XLS"));
+        assert!(is_workbook("item.ods"));
+        assert!(!is_workbook("item.csv"));
+        assert!(!is_workbook("xlsx"));
+        assert_eq!(table_stem("item.xls"), "item");
+        assert_eq!(table_stem("item.back.xlsb"), "item.back");
+        assert_eq!(table_stem("battle/skill.xlsx#t_buff"), "buff");
+        assert_eq!(table_stem("battle/skill.xlsx#Weapon"), "Weapon");
+        assert_eq!(table_stem("a#b.xlsx"), "a#b");
+    }
+
+    #[test]
+    fn test_sorted_rows() {
+        let mut xlsx = XLSX::new();
+        let fields = [
+            ("type", "int!key"),
+            ("id", "string!key"),
+            ("name", "string"),
+        ];
+        for (i, (name, t)) in fields.iter().enumerate() {
+            let mut f = field(name, "");
+            f.set_field_type(t.to_string());
+            f.set_field_index(i);
+            xlsx.add_field(f);
+        }
+        let rows = [(2, "10", "a"), (1, "b", "b"), (2, "2", "c"), (1, "a", "d")];
+        for (r, (t, id, name)) in rows.iter().enumerate() {
+            let row = vec![
+                CellValue::Int(*t),
+                CellValue::from(*id),
+                CellValue::from(*name),
+            ];
+            xlsx.add_row(row, "Sheet1", r);
+        }
+
+        let names = |rows: Vec<&Vec<CellValue>>| -> Vec<String> {
+            rows.iter().map(|r| r[2].to_string()).collect()
+        };
+        assert_eq!(names(xlsx.rows(false)), vec!["a", "b", "c", "d"]);
+        assert_eq!(names(xlsx.rows(true)), vec!["d", "b", "c", "a"]);
+    }
+
+    #[test]
+    fn test_sheet_tables() {
+        let table = |id: &str| vec![text(&["id"]), text(&["int!key"]), text(&[id])];
+        let sheets = vec![
+            ("Sheet1".to_string(), table("1")),
+            ("#notes".to_string(), table("2")),
+            ("t_buff".to_string(), table("3")),
+            ("Sheet2".to_string(), table("4")),
+        ];
+        let books = [("skill.xlsx", sheets.clone())];
+        let row_counts = |tables: Vec<(String, XLSX)>| -> Vec<(String, usize)> {
+            tables
+                .into_iter()
+                .map(|(name, xlsx)| (name, xlsx.values.len()))
+                .collect()
+        };
+
+        // scratch sheets are skipped, other sheets make up the workbook table
+        let tables = fixture::parse("xlsxto_sheet_tables", &books, &fixture::layout());
+        assert_eq!(
+            row_counts(tables),
+            vec![
+                ("skill.xlsx".to_string(), 2),
+                ("skill.xlsx#t_buff".to_string(), 1)
+            ]
+        );
+
+        let layout = HeaderLayout {
+            per_sheet: true,
+            ..fixture::layout()
+        };
+        let tables = fixture::parse("xlsxto_per_sheet", &books, &layout);
+        let names: Vec<String> = tables.iter().map(|(name, _)| name.clone()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "skill.xlsx#Sheet1",
+                "skill.xlsx#t_buff",
+                "skill.xlsx#Sheet2"
+            ]
+        );
+        let stems: Vec<String> = names.iter().map(|n| table_stem(n)).collect();
+        assert_eq!(stems, vec!["Sheet1", "buff", "Sheet2"]);
+    }
+
+    #[test]
+    fn test_output_names() {
+        let table = || vec![text(&["id"]), text(&["int!key"]), text(&["1"])];
+        let skill = vec![
+            ("Sheet1".to_string(), table()),
+            ("t_buff".to_string(), table()),
+        ];
+        let books = [("buff.xlsx", sheet1(table())), ("skill.xlsx", skill)];
+        let all = fixture::load("xlsxto_output_names", &books, &fixture::layout());
+
+        let mut diagnostics = Diagnostics::new();
+        all.check_xlsx_valid(&ExportContext::new(""), &mut diagnostics);
+        let report = diagnostics.to_text("");
+        let errors: Vec<&str> = report.lines().skip(1).collect();
+        assert_eq!(
+            errors,
+            vec!["skill.xlsx#t_buff 导出名重复: 导出名：buff, 与buff.xlsx重复"]
+        );
+
+        // lookup maps write files and tables named after the table
+        let item = vec![
+            text(&["id", "name"]),
+            text(&["int!key", "string!index"]),
+            text(&["1", "a"]),
+        ];
+        let books = [
+            ("item.xlsx", sheet1(item)),
+            ("item_by_name.xlsx", sheet1(table())),
+        ];
+        let all = fixture::load("xlsxto_output_indexes", &books, &fixture::layout());
+        let mut ctx = ExportContext::new("");
+        ctx.targets = vec!["json".to_string()];
+        let mut diagnostics = Diagnostics::new();
+        all.check_xlsx_valid(&ctx, &mut diagnostics);
+        let report = diagnostics.to_text("");
+        let errors: Vec<&str> = report.lines().skip(1).collect();
+        assert_eq!(
+            errors,
+            vec!["item_by_name.xlsx 导出名重复: 导出名：item_by_name, 与item.xlsx重复"]
+        );
+        ctx.targets = vec!["csharp".to_string()];
+        let mut diagnostics = Diagnostics::new();
+        all.check_xlsx_valid(&ctx, &mut diagnostics);
+        assert!(diagnostics.is_empty());
+
+        // classes of every folder share one c# namespace
+        let books = [
+            ("a/item.xlsx", sheet1(table())),
+            ("b/item.xlsx", sheet1(table())),
+            ("table_reader.xlsx", sheet1(table())),
+        ];
+        let all = fixture::load("xlsxto_output_types", &books, &fixture::layout());
+        let errors = |targets: &[&str]| -> Vec<String> {
+            let mut ctx = ExportContext::new("");
+            ctx.targets = targets.iter().map(|t| t.to_string()).collect();
+            let mut diagnostics = Diagnostics::new();
+            all.check_xlsx_valid(&ctx, &mut diagnostics);
+            let report = diagnostics.to_text("");
+            report.lines().skip(1).map(String::from).collect()
+        };
+        assert!(errors(&["lua"]).is_empty());
+        assert_eq!(
+            errors(&["csharp"]),
+            vec![
+                "b/item.xlsx 导出名重复: 导出名：Item, 与a/item.xlsx重复",
+                "table_reader.xlsx 导出名重复: 导出名：TableReader, 与TableReader.cs重复",
+            ]
+        );
+
+        // an index on a `key` column repeats the map of the keys
+        let bag = vec![
+            text(&["id", "key"]),
+            text(&["int!key", "int!index"]),
+            text(&["1", "2"]),
+        ];
+        let bags = fixture::load(
+            "xlsxto_output_members",
+            &[("bag.xlsx", sheet1(bag))],
+            &fixture::layout(),
+        );
+        let mut ctx = ExportContext::new("");
+        ctx.targets = vec!["csharp".to_string()];
+        let mut diagnostics = Diagnostics::new();
+        bags.check_xlsx_valid(&ctx, &mut diagnostics);
+        let report = diagnostics.to_text("");
+        let errors: Vec<&str> = report.lines().skip(1).collect();
+        assert_eq!(
+            errors,
+            vec!["bag.xlsx 导出名重复: 导出名：BagTable.ByKey, 与bag.xlsx重复"]
+        );
+
+        // go types and files of every folder share one package
+        let books = [
+            ("a/item.xlsx", sheet1(table())),
+            ("b/item.xlsx", sheet1(table())),
+            ("a_item.xlsx", sheet1(table())),
+            ("time.xlsx", sheet1(table())),
+        ];
+        let all = fixture::load("xlsxto_output_go", &books, &fixture::layout());
+        let mut ctx = ExportContext::new("");
+        ctx.targets = vec!["go".to_string()];
+        let mut diagnostics = Diagnostics::new();
+        all.check_xlsx_valid(&ctx, &mut diagnostics);
+        let report = diagnostics.to_text("");
+        let errors: Vec<&str> = report.lines().skip(1).collect();
+        assert_eq!(
+            errors,
+            vec![
+                "a_item.xlsx 导出名重复: 导出名：a_item_table.go, 与a/item.xlsx重复",
+                "b/item.xlsx 导出名重复: 导出名：Item, 与a/item.xlsx重复",
+                "time.xlsx 导出名重复: 导出名：Time, 与xlsxto.go重复",
+            ]
+        );
+
+        // go fields are pascal case, and an index on `key` repeats `ByKey`
+        let bag = vec![
+            text(&["id", "key"]),
+            text(&["int!key", "int!index"]),
+            text(&["1", "2"]),
+        ];
+        let user = vec![
+            text(&["id", "item_id", "itemId"]),
+            text(&["int!key", "int", "int"]),
+            text(&["1", "2", "3"]),
+        ];
+        let books = [("bag.xlsx", sheet1(bag)), ("user.xlsx", sheet1(user))];
+        let all = fixture::load("xlsxto_output_go_fields", &books, &fixture::layout());
+        let mut diagnostics = Diagnostics::new();
+        all.check_xlsx_valid(&ctx, &mut diagnostics);
+        let report = diagnostics.to_text("");
+        let errors: Vec<&str> = report.lines().skip(1).collect();
+        assert_eq!(
+            errors,
+            vec![
+                "bag.xlsx 导出名重复: 导出名：BagTable.ByKey, 与bag.xlsx重复",
+                "user.xlsx 导出名重复: 导出名：User.ItemId, 与user.xlsx重复",
+            ]
+        );
     }
 }
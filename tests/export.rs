@@ -0,0 +1,62 @@
+/*
+Copyright (c) 2024- rickhan<rick.han@yahoo.com>
+
+Permission is hereby granted, free of charge, to any person obtaining
+a copy of this software and associated documentation files (the
+"Software"), to deal in the Software without restriction, including
+without limitation the rights to use, copy, modify, merge, publish,
+distribute, sublicense, and/or sell copies of the Software, and to
+permit persons to whom the Software is furnished to do so, subject to
+the following conditions:
+
+The above copyright notice and this permission notice shall be
+included in all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+*/
+use std::fs;
+use xlsxto::{
+    CSharpGenerator, Diagnostics, ExportContext, GoGenerator, LuaGenerator, ALLXLSX, XLSX,
+};
+
+#[test]
+fn test_export_examples() {
+    let mut all = ALLXLSX::new();
+    for name in ["example.xlsx", "example2.xlsx"] {
+        let mut xlsx = XLSX::new();
+        xlsx.parse_from_file("examples", name, false).unwrap();
+        all.add(name, xlsx);
+    }
+
+    let out = std::env::temp_dir().join("xlsxto_export_examples");
+    fs::create_dir_all(&out).unwrap();
+    let ctx = ExportContext::new(out.to_str().unwrap());
+
+    let mut diagnostics = Diagnostics::new();
+    all.check_xlsx_valid(&ctx, &mut diagnostics);
+    assert!(diagnostics.is_empty());
+
+    all.gen::<LuaGenerator>(&ctx).unwrap();
+    assert_eq!(
+        fs::read_to_string(out.join("t_example.lua")).unwrap(),
+        fs::read_to_string("generated/t_example.lua").unwrap()
+    );
+
+    all.gen::<CSharpGenerator>(&ctx).unwrap();
+    assert_eq!(
+        fs::read_to_string(out.join("Example.cs")).unwrap(),
+        fs::read_to_string("generated/Example.cs").unwrap()
+    );
+
+    all.gen::<GoGenerator>(&ctx).unwrap();
+    assert_eq!(
+        fs::read_to_string(out.join("example_table.go")).unwrap(),
+        fs::read_to_string("generated/example_table.go").unwrap()
+    );
+}